@@ -0,0 +1,51 @@
+// Decode throughput on representative shapes, and sequential vs parallel
+// decoding of a batch. Run with `cargo bench --bench decode`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+
+use btc_tx_decoder::batch::decode_batch_sequential;
+use btc_tx_decoder::decode_transaction;
+use btc_tx_decoder::synthetic::{consolidation, dusting, taproot_script_path};
+
+const SAMPLE_TX: &str = "0200000000010131811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c10100000000fdffffff0220a107000000000016001485d78eb795bd9c8a21afefc8b6fdaedf718368094c08100000000000160014840ab165c9c2555d4a31b9208ad806f89d2535e20247304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01210260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff43030e00";
+
+fn decode(c: &mut Criterion) {
+    let workloads = [
+        ("segwit_sample", SAMPLE_TX.to_string()),
+        ("consolidation_1000_inputs", consolidation(1_000)),
+        ("dusting_10000_outputs", dusting(10_000)),
+        (
+            "taproot_script_path_100k_witness",
+            taproot_script_path(100_000),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("decode");
+    for (name, hex) in &workloads {
+        group.throughput(Throughput::Bytes(hex.len() as u64 / 2));
+        group.bench_with_input(BenchmarkId::from_parameter(name), hex, |b, hex| {
+            b.iter(|| decode_transaction(black_box(hex)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// 10,000 copies of the sample, one per line as --batch reads them
+fn batch(c: &mut Criterion) {
+    let lines = vec![SAMPLE_TX.to_string(); 10_000];
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(lines.len() as u64));
+    group.bench_function("sequential", |b| {
+        b.iter(|| decode_batch_sequential(black_box(&lines)))
+    });
+    #[cfg(feature = "rayon")]
+    group.bench_function("parallel", |b| {
+        b.iter(|| btc_tx_decoder::batch::decode_batch_parallel(black_box(&lines)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decode, batch);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+#![no_std]
+
+extern crate alloc;
+
+use btc_tx_decoder::error::DecodeError;
+use btc_tx_decoder::wire::{decode, decode_hex, RawTransaction};
+
+// The decoder's segwit test vector
+pub const SAMPLE_TX: &str = "0200000000010131811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c10100000000fdffffff0220a107000000000016001485d78eb795bd9c8a21afefc8b6fdaedf718368094c08100000000000160014840ab165c9c2555d4a31b9208ad806f89d2535e20247304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01210260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff43030e00";
+
+// What a signer would call: hex in, structs out
+pub fn decode_sample() -> Result<RawTransaction, DecodeError> {
+    decode_hex(SAMPLE_TX)
+}
+
+// The same from raw bytes, for callers that never see hex
+pub fn decode_bytes(bytes: &[u8]) -> Result<RawTransaction, DecodeError> {
+    decode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use btc_tx_decoder::compact_size::CompactSize;
+    use btc_tx_decoder::wire::{parse_input, parse_output};
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_fields() {
+        let tx = decode_sample().unwrap();
+        assert_eq!(tx, decode_bytes(&bytes(SAMPLE_TX)).unwrap());
+
+        assert_eq!(tx.version, 2);
+        assert_eq!(tx.flag, Some(1));
+        assert_eq!(tx.input_count.value, 1);
+        assert_eq!(
+            tx.inputs[0].txid[..],
+            bytes("31811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c1")[..]
+        );
+        assert_eq!(tx.inputs[0].vout, 1);
+        assert!(tx.inputs[0].script_sig.is_empty());
+        assert_eq!(tx.inputs[0].sequence, Some(0xfffffffd));
+
+        assert_eq!(tx.output_count.map(|count| count.value), Some(2));
+        assert_eq!(tx.outputs[0].value, 500_000);
+        assert_eq!(
+            tx.outputs[0].script_pubkey,
+            bytes("001485d78eb795bd9c8a21afefc8b6fdaedf71836809")
+        );
+        assert_eq!(tx.outputs[1].value, 1_050_700);
+        assert_eq!(
+            tx.outputs[1].script_pubkey,
+            bytes("0014840ab165c9c2555d4a31b9208ad806f89d2535e2")
+        );
+
+        assert_eq!(tx.witness.len(), 1);
+        let items: Vec<usize> = tx.witness[0].items.iter().map(|i| i.data.len()).collect();
+        assert_eq!(items, [71, 33]);
+        assert_eq!(tx.locktime, Some(918_339));
+        assert!(tx.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_core_parsers() {
+        let raw = bytes(SAMPLE_TX);
+        assert_eq!(CompactSize::read(&raw, 6).unwrap().0.value, 1);
+
+        let tx = decode_sample().unwrap();
+        let (input, size) = parse_input(&raw, 7).unwrap();
+        assert_eq!((input, size), (tx.inputs[0].clone(), 41));
+        let (output, size) = parse_output(&raw, 49).unwrap();
+        assert_eq!((output, size), (tx.outputs[0].clone(), 31));
+
+        assert!(matches!(
+            decode_bytes(&raw[..70]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert!(matches!(decode_hex("zz"), Err(DecodeError::InvalidHex(_))));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::hashes::double_sha256;
+use crate::network::Network;
+use crate::script::is_witness_program;
+use crate::BitcoinTransaction;
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// BIP173 and BIP350 checksum constants
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+// The mainnet address paying to `script_pubkey`, for the templates that have
+// one: P2PKH, P2SH and witness programs.
+pub fn address(script_pubkey: &[u8]) -> Option<String> {
+    address_on(script_pubkey, Network::Mainnet)
+}
+
+// `address` for another network
+pub fn address_on(script_pubkey: &[u8], network: Network) -> Option<String> {
+    match classify_script(script_pubkey, &[], &ClassifyOptions::default()) {
+        ScriptType::P2pkh => {
+            return Some(base58check(network.p2pkh_version(), &script_pubkey[3..23]))
+        }
+        ScriptType::P2sh { .. } => {
+            return Some(base58check(network.p2sh_version(), &script_pubkey[2..22]))
+        }
+        _ => {}
+    }
+    match script_pubkey {
+        [version @ (0x00 | 0x51..=0x60), len, program @ ..]
+            if (2..=40).contains(&program.len()) && *len as usize == program.len() =>
+        {
+            let version = match version {
+                0x00 => 0,
+                v => v - 0x50,
+            };
+            segwit_address(network.bech32_hrp(), version, program)
+        }
+        _ => None,
+    }
+}
+
+// Warnings for outputs that look like witness programs but have no address
+// on any network: v0 programs that are neither 20 nor 32 bytes, which can
+// never be spent.
+pub(crate) fn check_addresses(tx: &BitcoinTransaction) -> Vec<String> {
+    tx.outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, output)| output.address.is_none())
+        .filter_map(|(i, output)| {
+            let script = hex::decode(&output.scriptpubkey).ok()?;
+            is_witness_program(&script).then(|| {
+                format!(
+                    "output {} is a {}-byte witness program with no address",
+                    i,
+                    script.len() - 2
+                )
+            })
+        })
+        .collect()
+}
+
+fn base58check(version: u8, payload: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend_from_slice(payload);
+    let checksum = double_sha256(&data);
+    data.extend_from_slice(&checksum[..4]);
+
+    // Repeated division of the big-endian number by 58
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &data {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    // Each leading zero byte is written as a '1'
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    std::iter::repeat_n(b'1', zeros)
+        .chain(digits.iter().rev().map(|&d| BASE58_ALPHABET[d as usize]))
+        .map(char::from)
+        .collect()
+}
+
+fn segwit_address(hrp: &str, version: u8, program: &[u8]) -> Option<String> {
+    // v0 programs are 20 or 32 bytes; later versions use bech32m
+    let checksum_const = match (version, program.len()) {
+        (0, 20 | 32) => BECH32_CONST,
+        (0, _) => return None,
+        _ => BECH32M_CONST,
+    };
+
+    let mut data = vec![version];
+    data.extend(to_base32(program));
+
+    let mut values = hrp_expand(hrp);
+    values.extend(&data);
+    values.extend([0u8; 6]);
+    let polymod = bech32_polymod(&values) ^ checksum_const;
+    data.extend((0..6).map(|i| ((polymod >> (5 * (5 - i))) & 31) as u8));
+
+    let encoded: String = data
+        .iter()
+        .map(|&d| BECH32_CHARSET[d as usize] as char)
+        .collect();
+    Some(format!("{}1{}", hrp, encoded))
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut values: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 31));
+    values
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut checksum: u32 = 1;
+    for &value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+// 8-bit bytes to 5-bit groups, zero-padding the last group
+fn to_base32(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        acc = (acc << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(((acc >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        out.push(((acc << (5 - bits)) & 31) as u8);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address_of(script: &str) -> Option<String> {
+        address(&hex::decode(script).unwrap())
+    }
+
+    #[test]
+    fn test_segwit_addresses() {
+        // BIP173 and BIP350 examples
+        assert_eq!(
+            address_of("0014751e76e8199196d454941c45d1b3a323f1433bd6").as_deref(),
+            Some("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+        );
+        assert_eq!(
+            address_of("512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .as_deref(),
+            Some("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0")
+        );
+    }
+
+    #[test]
+    fn test_networks() {
+        let p2wpkh = hex::decode("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        let p2pkh = hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap();
+        let p2sh = hex::decode("a914000000000000000000000000000000000000000087").unwrap();
+        let expected = [
+            (
+                Network::Mainnet,
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                "1",
+                "3",
+            ),
+            (
+                Network::Testnet3,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+                "m",
+                "2",
+            ),
+            (
+                Network::Testnet4,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+                "m",
+                "2",
+            ),
+            (
+                Network::Signet,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+                "m",
+                "2",
+            ),
+            (
+                Network::Regtest,
+                "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080",
+                "m",
+                "2",
+            ),
+            (
+                Network::Liquid,
+                "ex1qw508d6qejxtdg4y5r3zarvary0c5xw7kxw5fx4",
+                "Q",
+                "G",
+            ),
+        ];
+        for (network, segwit, p2pkh_prefix, p2sh_prefix) in expected {
+            assert_eq!(address_on(&p2wpkh, network).as_deref(), Some(segwit));
+            assert!(address_on(&p2pkh, network)
+                .unwrap()
+                .starts_with(p2pkh_prefix));
+            assert!(address_on(&p2sh, network).unwrap().starts_with(p2sh_prefix));
+        }
+        assert_eq!(address_on(&[0x00, 0x03, 1, 2, 3], Network::Signet), None);
+    }
+
+    #[test]
+    fn test_base58_addresses() {
+        // The genesis block's coinbase key
+        assert_eq!(
+            address_of("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").as_deref(),
+            Some("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa")
+        );
+        assert_eq!(
+            address_of("a914000000000000000000000000000000000000000087")
+                .unwrap()
+                .chars()
+                .next(),
+            Some('3')
+        );
+        assert_eq!(address_of("6a0568656c6c6f"), None);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::error::DecodeError;
+use crate::wire::{self, Field};
+use crate::{decode_transaction_with_limits, normalize_hex, DecodeLimits};
+
+// Bytes per line; longer scripts and witness items wrap
+const LINE_BYTES: usize = 32;
+
+// The raw transaction as a hexdump with one labelled line per field, in
+// serialization order:
+//
+//   02000000  version
+//   00 01     marker+flag
+//   01        input count
+//   ...
+//
+// The hex column is as wide as a full line of LINE_BYTES bytes.
+pub fn annotate_hex(hex: &str) -> Result<String, DecodeError> {
+    let bytes =
+        hex::decode(normalize_hex(hex)).map_err(|e| DecodeError::InvalidHex(e.to_string()))?;
+    let limits = DecodeLimits::default();
+    // Decoding first settles segwit against legacy, see wire::prefer_legacy
+    let is_segwit = decode_transaction_with_limits(hex, &limits)?.is_segwit();
+
+    let mut fields = Vec::new();
+    wire::parse_body(&bytes, is_segwit, &limits, &mut |field, offset, len| {
+        fields.push((field, offset, len))
+    })?;
+
+    let width = LINE_BYTES * 2;
+    let mut out = String::new();
+    for (field, offset, len) in fields {
+        let span = &bytes[offset..offset + len];
+        let (hex, label) = match field {
+            // Shown together with the marker
+            Field::Flag => continue,
+            Field::Marker => (
+                format!("{:02x} {:02x}", bytes[offset], bytes[offset + 1]),
+                "marker+flag".to_string(),
+            ),
+            _ if span.is_empty() => continue,
+            _ => (hex::encode(&span[..len.min(LINE_BYTES)]), label(field)),
+        };
+        out.push_str(&format!("{:<width$}  {}\n", hex, label, width = width));
+        for chunk in span.chunks(LINE_BYTES).skip(1) {
+            out.push_str(&hex::encode(chunk));
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+fn label(field: Field) -> String {
+    match field {
+        Field::Version => "version".to_string(),
+        Field::Marker | Field::Flag => "marker+flag".to_string(),
+        Field::InputCount => "input count".to_string(),
+        Field::Txid(i) => format!("input {} txid", i),
+        Field::Vout(i) => format!("input {} vout", i),
+        Field::ScriptSigSize(i) => format!("input {} scriptSig length", i),
+        Field::ScriptSig(i) => format!("input {} scriptSig", i),
+        Field::Sequence(i) => format!("input {} sequence", i),
+        Field::OutputCount => "output count".to_string(),
+        Field::Amount(i) => format!("output {} amount", i),
+        Field::ScriptPubKeySize(i) => format!("output {} scriptPubKey length", i),
+        Field::ScriptPubKey(i) => format!("output {} scriptPubKey", i),
+        Field::StackItems(i) => format!("witness {} item count", i),
+        Field::ItemSize(i, j) => format!("witness {} item {} length", i, j),
+        Field::Item(i, j) => format!("witness {} item {}", i, j),
+        Field::Locktime => "locktime".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_annotate_segwit() {
+        let expected = "\
+02000000                                                          version
+00 01                                                             marker+flag
+01                                                                input count
+31811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c1  input 0 txid
+01000000                                                          input 0 vout
+00                                                                input 0 scriptSig length
+fdffffff                                                          input 0 sequence
+02                                                                output count
+20a1070000000000                                                  output 0 amount
+16                                                                output 0 scriptPubKey length
+001485d78eb795bd9c8a21afefc8b6fdaedf71836809                      output 0 scriptPubKey
+4c08100000000000                                                  output 1 amount
+16                                                                output 1 scriptPubKey length
+0014840ab165c9c2555d4a31b9208ad806f89d2535e2                      output 1 scriptPubKey
+02                                                                witness 0 item count
+47                                                                witness 0 item 0 length
+304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28  witness 0 item 0
+a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e
+97dbb1e3a85c01
+21                                                                witness 0 item 1 length
+0260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4  witness 0 item 1
+ff
+43030e00                                                          locktime
+";
+        assert_eq!(annotate_hex(SAMPLE_TX).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_annotate_legacy() {
+        let hex = TxBuilder::new()
+            .add_input(&"ab".repeat(32), 0)
+            .add_output_sats(1_000, &[0x51])
+            .build_hex()
+            .unwrap();
+        let annotated = annotate_hex(&hex).unwrap();
+        let labels: Vec<&str> = annotated
+            .lines()
+            .map(|line| line[2 * LINE_BYTES..].trim())
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "version",
+                "input count",
+                "input 0 txid",
+                "input 0 vout",
+                "input 0 scriptSig length",
+                "input 0 sequence",
+                "output count",
+                "output 0 amount",
+                "output 0 scriptPubKey length",
+                "output 0 scriptPubKey",
+                "locktime",
+            ]
+        );
+        assert!(annotate_hex("zz").is_err());
+    }
+}
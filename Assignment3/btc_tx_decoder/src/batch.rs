@@ -0,0 +1,109 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{decode_transaction, BitcoinTransaction};
+
+// Decode each line independently; results line up with the input.
+pub fn decode_batch_sequential(lines: &[String]) -> Vec<Result<BitcoinTransaction, String>> {
+    lines
+        .iter()
+        .map(|line| decode_transaction(line.trim()))
+        .collect()
+}
+
+// Same as decode_batch_sequential but spread over rayon's thread pool. The
+// results keep the input order regardless of which worker finishes first,
+// and a bad line only affects its own slot.
+#[cfg(feature = "rayon")]
+pub fn decode_batch_parallel(lines: &[String]) -> Vec<Result<BitcoinTransaction, String>> {
+    lines
+        .par_iter()
+        .map(|line| decode_transaction(line.trim()))
+        .collect()
+}
+
+// Decodes many transactions, in parallel with the rayon feature. Results
+// are in input order either way.
+pub fn decode_batch(hex_list: &[String]) -> Vec<Result<BitcoinTransaction, String>> {
+    #[cfg(feature = "rayon")]
+    return decode_batch_parallel(hex_list);
+    #[cfg(not(feature = "rayon"))]
+    return decode_batch_sequential(hex_list);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SAMPLE_TX;
+    #[cfg(feature = "rayon")]
+    use crate::{encode_transaction, TxOutput};
+
+    // Variations of the sample with distinct locktimes and amounts, with a
+    // malformed line every so often.
+    #[cfg(feature = "rayon")]
+    fn generated_lines(count: u32) -> Vec<String> {
+        let template = decode_transaction(SAMPLE_TX).unwrap();
+        (0..count)
+            .map(|i| {
+                if i % 37 == 5 {
+                    return format!("not hex {}", i);
+                }
+                let mut tx = template.clone();
+                tx.locktime = hex::encode(i.to_le_bytes());
+                let value_sats = 1_000 + i as u64;
+                tx.outputs[0] = TxOutput {
+                    amount: hex::encode(value_sats.to_le_bytes()),
+                    value_sats,
+                    ..tx.outputs[0].clone()
+                };
+                encode_transaction(&tx).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_batch_sequential_keeps_errors_in_place() {
+        let lines = vec![
+            SAMPLE_TX.to_string(),
+            "zz".to_string(),
+            SAMPLE_TX.to_string(),
+        ];
+        let results = decode_batch_sequential(&lines);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn test_decode_batch_keeps_order() {
+        // Distinct locktimes, so each result can be matched to its line
+        let template = decode_transaction(SAMPLE_TX).unwrap();
+        let lines: Vec<String> = (0..200u32)
+            .map(|i| {
+                let mut tx = template.clone();
+                tx.locktime = hex::encode(i.to_le_bytes());
+                crate::encode_transaction(&tx).unwrap()
+            })
+            .collect();
+        let results = decode_batch(&lines);
+        assert_eq!(results.len(), lines.len());
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(
+                result.as_ref().unwrap().locktime,
+                hex::encode((i as u32).to_le_bytes())
+            );
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_decode_batch_parallel_matches_sequential() {
+        let lines = generated_lines(500);
+        let parallel = decode_batch_parallel(&lines);
+        assert_eq!(parallel, decode_batch_sequential(&lines));
+
+        assert_eq!(parallel.len(), 500);
+        assert!(parallel[5].is_err());
+        assert_eq!(parallel[499].as_ref().unwrap().locktime, "f3010000");
+    }
+}
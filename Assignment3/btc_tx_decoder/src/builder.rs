@@ -0,0 +1,449 @@
+use crate::error::DecodeError;
+use crate::script::{encode_push, OP_RETURN};
+use crate::wire::{RawInput, RawOutput, RawTransaction, RawWitness, RawWitnessItem};
+use crate::{encode_transaction, from_raw, BitcoinTransaction, CompactSize};
+
+// Builds transactions from their fields. scriptSigs and witnesses default to
+// empty, so an unsigned transaction needs neither; the marker and flag are
+// set as soon as any input has a witness.
+//
+//     let tx = TxBuilder::new()
+//         .version(2)
+//         .add_input(txid, 0)
+//         .sequence(0xfffffffd)
+//         .add_witness(vec![signature, pubkey])
+//         .add_output_sats(50_000, &script_pubkey)
+//         .build()?;
+#[derive(Debug, Clone)]
+pub struct TxBuilder {
+    version: i32,
+    inputs: Vec<RawInput>,
+    // One stack per input, empty for inputs without a witness
+    witness: Vec<Vec<Vec<u8>>>,
+    outputs: Vec<RawOutput>,
+    locktime: u32,
+    // The first bad argument, reported by build()
+    error: Option<DecodeError>,
+}
+
+impl Default for TxBuilder {
+    fn default() -> Self {
+        TxBuilder {
+            version: 2,
+            inputs: Vec::new(),
+            witness: Vec::new(),
+            outputs: Vec::new(),
+            locktime: 0,
+            error: None,
+        }
+    }
+}
+
+impl TxBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn version(mut self, version: i32) -> Self {
+        self.version = version;
+        self
+    }
+
+    // `txid` is in display order, as block explorers show it; it is stored
+    // byte-reversed as on the wire. The input's sequence defaults to final.
+    pub fn add_input(mut self, txid: &str, vout: u32) -> Self {
+        match hex::decode(txid).map(<[u8; 32]>::try_from) {
+            Ok(Ok(mut txid)) => {
+                txid.reverse();
+                self.inputs.push(RawInput {
+                    txid,
+                    vout,
+                    script_sig_len: compact_size(0),
+                    script_sig: Vec::new(),
+                    sequence: Some(u32::MAX),
+                });
+                self.witness.push(Vec::new());
+            }
+            _ => self.fail(format!("input txid {:?} is not 32 bytes of hex", txid)),
+        }
+        self
+    }
+
+    // Sets the scriptSig of the most recently added input.
+    pub fn script_sig(mut self, script_sig: &[u8]) -> Self {
+        match self.inputs.last_mut() {
+            Some(input) => {
+                input.script_sig_len = compact_size(script_sig.len());
+                input.script_sig = script_sig.to_vec();
+            }
+            None => self.fail("scriptSig set before any input was added".to_string()),
+        }
+        self
+    }
+
+    // Sets the sequence of the most recently added input.
+    pub fn sequence(mut self, sequence: u32) -> Self {
+        match self.inputs.last_mut() {
+            Some(input) => input.sequence = Some(sequence),
+            None => self.fail("sequence set before any input was added".to_string()),
+        }
+        self
+    }
+
+    // Sets the witness stack of the most recently added input, bottom item
+    // first.
+    pub fn add_witness(mut self, items: Vec<Vec<u8>>) -> Self {
+        match self.witness.last_mut() {
+            Some(stack) => *stack = items,
+            None => self.fail("witness added before any input was added".to_string()),
+        }
+        self
+    }
+
+    pub fn add_output_sats(mut self, value_sats: u64, script_pubkey: &[u8]) -> Self {
+        self.outputs.push(RawOutput {
+            value: value_sats,
+            script_pubkey_len: compact_size(script_pubkey.len()),
+            script_pubkey: script_pubkey.to_vec(),
+        });
+        self
+    }
+
+    // A zero-value OP_RETURN output carrying `data` in a single push.
+    pub fn add_op_return(self, data: &[u8]) -> Self {
+        let mut script = vec![OP_RETURN];
+        script.extend(encode_push(data));
+        self.add_output_sats(0, &script)
+    }
+
+    pub fn locktime(mut self, locktime: u32) -> Self {
+        self.locktime = locktime;
+        self
+    }
+
+    // The fields go through the same conversion as a decoded transaction,
+    // so the result is annotated the same way.
+    pub fn build(self) -> Result<BitcoinTransaction, DecodeError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if self.inputs.is_empty() {
+            return Err(DecodeError::InvalidTransaction(
+                "transaction needs at least one input".to_string(),
+            ));
+        }
+        if self.outputs.is_empty() {
+            return Err(DecodeError::InvalidTransaction(
+                "transaction needs at least one output".to_string(),
+            ));
+        }
+        Ok(self.assemble())
+    }
+
+    // build() followed by encode_transaction()
+    pub fn build_hex(self) -> Result<String, DecodeError> {
+        self.build().and_then(|tx| encode_transaction(&tx))
+    }
+
+    fn fail(&mut self, message: String) {
+        self.error
+            .get_or_insert(DecodeError::InvalidTransaction(message));
+    }
+
+    fn assemble(self) -> BitcoinTransaction {
+        let is_segwit = self.witness.iter().any(|stack| !stack.is_empty());
+        let witness = if is_segwit {
+            self.witness
+                .into_iter()
+                .map(|stack| RawWitness {
+                    stack_items: compact_size(stack.len()),
+                    items: stack
+                        .into_iter()
+                        .map(|data| RawWitnessItem {
+                            size: compact_size(data.len()),
+                            data,
+                        })
+                        .collect(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        from_raw(RawTransaction {
+            version: self.version,
+            flag: is_segwit.then_some(0x01),
+            input_count: compact_size(self.inputs.len()),
+            inputs: self.inputs,
+            output_count: Some(compact_size(self.outputs.len())),
+            outputs: self.outputs,
+            witness,
+            locktime: Some(self.locktime),
+            warnings: Vec::new(),
+        })
+    }
+}
+
+// Assembles a transaction from fields that are already final, scriptSigs and
+// witnesses included, e.g. to put a signed transaction back together. Unlike
+// TxBuilder nothing is checked, so build() can't fail.
+//
+//     let tx = TransactionBuilder::new()
+//         .add_input(prev_txid, 1, &[], 0xfffffffd)
+//         .add_witness(vec![signature, pubkey])
+//         .add_output(50_000, &script_pubkey)
+//         .build();
+#[derive(Debug, Clone, Default)]
+pub struct TransactionBuilder {
+    inner: TxBuilder,
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_version(self, version: i32) -> Self {
+        TransactionBuilder {
+            inner: self.inner.version(version),
+        }
+    }
+
+    // `txid` is in wire order, as it appears in the serialized input.
+    pub fn add_input(self, txid: [u8; 32], vout: u32, script_sig: &[u8], sequence: u32) -> Self {
+        let mut display = txid;
+        display.reverse();
+        TransactionBuilder {
+            inner: self
+                .inner
+                .add_input(&hex::encode(display), vout)
+                .script_sig(script_sig)
+                .sequence(sequence),
+        }
+    }
+
+    // Sets the witness stack of the most recently added input, bottom item
+    // first. Panics when no input has been added yet.
+    pub fn add_witness(self, items: Vec<Vec<u8>>) -> Self {
+        assert!(
+            !self.inner.inputs.is_empty(),
+            "add_witness called before any input was added"
+        );
+        TransactionBuilder {
+            inner: self.inner.add_witness(items),
+        }
+    }
+
+    pub fn add_output(self, value_sats: u64, script_pubkey: &[u8]) -> Self {
+        TransactionBuilder {
+            inner: self.inner.add_output_sats(value_sats, script_pubkey),
+        }
+    }
+
+    pub fn set_locktime(self, locktime: u32) -> Self {
+        TransactionBuilder {
+            inner: self.inner.locktime(locktime),
+        }
+    }
+
+    pub fn build(self) -> BitcoinTransaction {
+        self.inner.assemble()
+    }
+}
+
+// A length or count in its shortest encoding
+fn compact_size(value: usize) -> CompactSize {
+    let encoded = CompactSize::encode(value as u64);
+    CompactSize {
+        value: value as u64,
+        encoded: hex::encode(encoded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::input_kind::InputKind;
+    use crate::tests::SAMPLE_TX;
+    use crate::Serialization;
+
+    const PREV_TXID: &str = "c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131";
+
+    #[test]
+    fn test_build_round_trip() {
+        let script = hex::decode("0014841b80d2cc75f5345c482af96294d04fdd66b2b7").unwrap();
+        let hex = TxBuilder::new()
+            .version(2)
+            .add_input(PREV_TXID, 3)
+            .sequence(0xfffffffd)
+            .add_output_sats(50_000, &script)
+            .add_op_return(b"hello")
+            .locktime(800_000)
+            .build_hex()
+            .unwrap();
+
+        let tx = decode_transaction(&hex).unwrap();
+        assert_eq!(tx.version, "02000000");
+        assert_eq!(tx.serialization, Serialization::Legacy);
+        assert_eq!(
+            tx.inputs[0].txid,
+            "31811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c1"
+        );
+        assert_eq!(tx.inputs[0].vout, "03000000");
+        assert_eq!(tx.inputs[0].scriptsig, "");
+        assert_eq!(tx.inputs[0].sequence, "fdffffff");
+        assert_eq!(tx.outputs[0].value_sats, 50_000);
+        assert_eq!(tx.outputs[0].scriptpubkey, hex::encode(&script));
+        assert_eq!(tx.outputs[1].scriptpubkey, "6a0568656c6c6f");
+        assert_eq!(tx.locktime, hex::encode(800_000u32.to_le_bytes()));
+    }
+
+    #[test]
+    fn test_build_sample_structure() {
+        // The sample transaction without its signature: same txid
+        let sample = decode_transaction(SAMPLE_TX).unwrap();
+        let tx = TxBuilder::new()
+            .version(2)
+            .add_input(PREV_TXID, 1)
+            .sequence(0xfffffffd)
+            .add_output_sats(
+                500_000,
+                &hex::decode("001485d78eb795bd9c8a21afefc8b6fdaedf71836809").unwrap(),
+            )
+            .add_output_sats(
+                1_050_700,
+                &hex::decode("0014840ab165c9c2555d4a31b9208ad806f89d2535e2").unwrap(),
+            )
+            .locktime(918_339)
+            .build()
+            .unwrap();
+
+        // Unsigned, so the input has no witness yet
+        assert_eq!(tx.inputs[0].input_kind, InputKind::Legacy);
+        assert_eq!(tx.outputs, sample.outputs);
+        assert_eq!(
+            encode_transaction(&tx).unwrap(),
+            crate::strip_witness(&sample).unwrap()
+        );
+        assert_eq!(tx.txid().unwrap(), sample.txid().unwrap());
+    }
+
+    #[test]
+    fn test_build_validation() {
+        let script = [OP_RETURN];
+        assert!(TxBuilder::new()
+            .add_output_sats(0, &script)
+            .build()
+            .is_err());
+        assert!(TxBuilder::new().add_input(PREV_TXID, 0).build().is_err());
+        assert!(TxBuilder::new()
+            .add_input("abcd", 0)
+            .add_output_sats(0, &script)
+            .build()
+            .is_err());
+        assert!(TxBuilder::new()
+            .sequence(0)
+            .add_input(PREV_TXID, 0)
+            .add_output_sats(0, &script)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_build_signed_sample() {
+        let sample = decode_transaction(SAMPLE_TX).unwrap();
+        let bytes = |hex_str: &str| hex::decode(hex_str).unwrap();
+        let tx = TxBuilder::new()
+            .add_input(PREV_TXID, 1)
+            .sequence(0xfffffffd)
+            .add_witness(
+                sample
+                    .witness_items(0)
+                    .iter()
+                    .map(|item| bytes(item))
+                    .collect(),
+            )
+            .add_output_sats(
+                500_000,
+                &bytes("001485d78eb795bd9c8a21afefc8b6fdaedf71836809"),
+            )
+            .add_output_sats(
+                1_050_700,
+                &bytes("0014840ab165c9c2555d4a31b9208ad806f89d2535e2"),
+            )
+            .locktime(918_339)
+            .build()
+            .unwrap();
+
+        assert_eq!(tx, sample);
+        assert_eq!(encode_transaction(&tx).unwrap(), SAMPLE_TX);
+
+        // With a scriptSig and no witness it stays legacy
+        let legacy = TxBuilder::new()
+            .add_input(PREV_TXID, 1)
+            .script_sig(&[0x51])
+            .add_output_sats(1_000, &[0x51])
+            .build()
+            .unwrap();
+        assert_eq!(legacy.serialization, Serialization::Legacy);
+        assert_eq!(legacy.inputs[0].scriptsigsize, "01");
+        assert_eq!(
+            decode_transaction(&encode_transaction(&legacy).unwrap()).unwrap(),
+            legacy
+        );
+
+        // Per-input fields need an input to apply to
+        assert!(TxBuilder::new()
+            .add_witness(vec![vec![0x01]])
+            .add_input(PREV_TXID, 0)
+            .add_output_sats(0, &[0x51])
+            .build()
+            .is_err());
+        assert!(TxBuilder::new()
+            .script_sig(&[0x51])
+            .add_input(PREV_TXID, 0)
+            .add_output_sats(0, &[0x51])
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_transaction_builder_sample() {
+        let sample = decode_transaction(SAMPLE_TX).unwrap();
+        let bytes = |hex_str: &str| hex::decode(hex_str).unwrap();
+        let mut prev_txid = [0u8; 32];
+        prev_txid.copy_from_slice(&bytes(&sample.inputs[0].txid));
+
+        let tx = TransactionBuilder::new()
+            .set_version(2)
+            .add_input(prev_txid, 1, &[], 0xfffffffd)
+            .add_witness(
+                sample
+                    .witness_items(0)
+                    .iter()
+                    .map(|item| bytes(item))
+                    .collect(),
+            )
+            .add_output(
+                500_000,
+                &bytes("001485d78eb795bd9c8a21afefc8b6fdaedf71836809"),
+            )
+            .add_output(
+                1_050_700,
+                &bytes("0014840ab165c9c2555d4a31b9208ad806f89d2535e2"),
+            )
+            .set_locktime(918_339)
+            .build();
+
+        assert_eq!(tx, sample);
+        assert_eq!(encode_transaction(&tx).unwrap(), SAMPLE_TX);
+
+        // Nothing is checked: no outputs still builds
+        let legacy = TransactionBuilder::new()
+            .add_input(prev_txid, 0, &[0x51], u32::MAX)
+            .build();
+        assert_eq!(legacy.serialization, Serialization::Legacy);
+        assert_eq!(legacy.inputs[0].scriptsig, "51");
+        assert!(legacy.outputs.is_empty());
+    }
+}
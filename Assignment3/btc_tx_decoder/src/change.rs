@@ -0,0 +1,223 @@
+use crate::classify::{classify_script, ClassifyOptions};
+use crate::consensus::PrevOut;
+use crate::hashes::{hash160, sha256};
+use crate::inner::last_push;
+use crate::input_kind::InputKind;
+use crate::script::{instructions, Instruction};
+use crate::witness::Witness;
+use crate::BitcoinTransaction;
+
+// An output is marked likely change when it scores at least this, and
+// strictly more than every other output
+pub const CHANGE_THRESHOLD: f64 = 0.5;
+
+const SAME_TYPE_WEIGHT: f64 = 0.35;
+const ODD_VALUE_WEIGHT: f64 = 0.35;
+const REUSE_WEIGHT: f64 = 0.5;
+const LAST_INDEX_WEIGHT: f64 = 0.1;
+
+// Payments tend to be whole multiples of this (0.0001 BTC); change is
+// whatever is left over.
+const ROUND_UNIT: u64 = 10_000;
+
+// Scores each output's chance of being change, from 0.0 to 1.0. Guesswork
+// from common wallet behaviour:
+// - it has the same script type as the inputs
+// - its value isn't round while another output's is
+// - it pays back to a script one of the inputs spends
+// - it is the last output
+// Input scripts come from `prevouts` when given, otherwise they are rebuilt
+// from the scriptSig or witness where that is possible. With fewer than two
+// outputs every score is 0.
+pub fn change_scores(tx: &BitcoinTransaction, prevouts: Option<&[PrevOut]>) -> Vec<f64> {
+    if tx.outputs.len() < 2 {
+        return vec![0.0; tx.outputs.len()];
+    }
+
+    let input_scripts: Vec<Option<Vec<u8>>> = (0..tx.inputs.len())
+        .map(|i| match prevouts.and_then(|prevouts| prevouts.get(i)) {
+            Some(prevout) => hex::decode(&prevout.script_pubkey).ok(),
+            None => spent_script(tx, i),
+        })
+        .collect();
+    let script_type =
+        |script: &[u8]| classify_script(script, &[], &ClassifyOptions::default()).name();
+    let mut input_types = input_scripts.iter().flatten().map(|s| script_type(s));
+    let input_type = input_types
+        .next()
+        .filter(|&first| input_types.all(|t| t == first));
+
+    let scripts: Vec<Vec<u8>> = tx
+        .outputs
+        .iter()
+        .map(|output| hex::decode(&output.scriptpubkey).unwrap_or_default())
+        .collect();
+    let is_round = |value: u64| value > 0 && value.is_multiple_of(ROUND_UNIT);
+
+    (0..tx.outputs.len())
+        .map(|i| {
+            let mut score = 0.0;
+            if input_type == Some(script_type(&scripts[i])) {
+                score += SAME_TYPE_WEIGHT;
+            }
+            let other_round = tx
+                .outputs
+                .iter()
+                .enumerate()
+                .any(|(j, other)| j != i && is_round(other.value_sats));
+            if !is_round(tx.outputs[i].value_sats) && other_round {
+                score += ODD_VALUE_WEIGHT;
+            }
+            if input_scripts.iter().flatten().any(|s| *s == scripts[i]) {
+                score += REUSE_WEIGHT;
+            }
+            if i == tx.outputs.len() - 1 {
+                score += LAST_INDEX_WEIGHT;
+            }
+            f64::min(score, 1.0)
+        })
+        .collect()
+}
+
+// Sets `change_score` on every output and `likely_change` on the one that
+// clears CHANGE_THRESHOLD ahead of the rest, if any.
+pub fn detect_change(tx: &mut BitcoinTransaction, prevouts: Option<&[PrevOut]>) {
+    let scores = change_scores(tx, prevouts);
+    let best = scores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .filter(|&(i, &score)| {
+            score >= CHANGE_THRESHOLD
+                && scores
+                    .iter()
+                    .enumerate()
+                    .all(|(j, &other)| j == i || other < score)
+        })
+        .map(|(i, _)| i);
+
+    for (i, (output, score)) in tx.outputs.iter_mut().zip(scores).enumerate() {
+        output.change_score = Some(score);
+        output.likely_change = best == Some(i);
+    }
+}
+
+// The scriptPubKey an input spends, when its scriptSig or witness reveals
+// the key or script it commits to. Taproot outputs can't be rebuilt.
+fn spent_script(tx: &BitcoinTransaction, input_index: usize) -> Option<Vec<u8>> {
+    let script_sig = hex::decode(&tx.inputs.get(input_index)?.scriptsig).ok()?;
+    let witness = Witness::from_tx(tx, input_index);
+
+    if !script_sig.is_empty() {
+        let pushes: Vec<&[u8]> = instructions(&script_sig)
+            .map(|r| match r {
+                Ok((_, Instruction::Push { data, .. })) => Some(data),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        return match pushes.as_slice() {
+            // P2PKH: <signature> <pubkey>
+            [_, key] if is_pubkey(key) && witness.is_empty() => {
+                Some([&[0x76, 0xa9, 0x14][..], &hash160(key), &[0x88, 0xac]].concat())
+            }
+            _ => {
+                let redeem = last_push(&script_sig)?;
+                Some([&[0xa9, 0x14][..], &hash160(&redeem), &[0x87]].concat())
+            }
+        };
+    }
+
+    match witness.items() {
+        [_, key] if is_pubkey(key) && key.len() == 33 => {
+            Some([&[0x00, 0x14][..], &hash160(key)].concat())
+        }
+        // P2WSH: the witness script comes last
+        [.., script] if tx.inputs[input_index].input_kind == InputKind::NativeSegwitV0 => {
+            Some([&[0x00, 0x20][..], &sha256(script)].concat())
+        }
+        _ => None,
+    }
+}
+
+fn is_pubkey(data: &[u8]) -> bool {
+    matches!(data, [0x02 | 0x03, ..] if data.len() == 33)
+        || matches!(data, [0x04, ..] if data.len() == 65)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_payment_with_change() {
+        // A round 0.005 BTC payment and 0.010507 BTC back, both P2WPKH like
+        // the input
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        detect_change(&mut tx, None);
+        assert_eq!(tx.outputs[0].change_score, Some(SAME_TYPE_WEIGHT));
+        assert_eq!(
+            tx.outputs[1].change_score,
+            Some(SAME_TYPE_WEIGHT + ODD_VALUE_WEIGHT + LAST_INDEX_WEIGHT)
+        );
+        assert!(!tx.outputs[0].likely_change);
+        assert!(tx.outputs[1].likely_change);
+
+        // Paying back to the input's own key outweighs the rest
+        let key = hex::decode(&tx.witness_items(0)[1]).unwrap();
+        tx.outputs[0].scriptpubkey = hex::encode([&[0x00, 0x14][..], &hash160(&key)].concat());
+        detect_change(&mut tx, None);
+        assert!(tx.outputs[0].likely_change);
+        assert!(!tx.outputs[1].likely_change);
+    }
+
+    #[test]
+    fn test_consolidation() {
+        // Three P2WPKH inputs swept into two round P2WPKH outputs: both
+        // match the input type and neither value stands out, so the last
+        // index alone can't lift either over the threshold
+        let witness = decode_transaction(SAMPLE_TX).unwrap().witness_items(0);
+        let witness: Vec<Vec<u8>> = witness
+            .iter()
+            .map(|item| hex::decode(item).unwrap())
+            .collect();
+        let p2wpkh = |byte: &str| hex::decode(format!("0014{}", byte.repeat(20))).unwrap();
+        let mut tx = TxBuilder::new()
+            .add_input(&"11".repeat(32), 0)
+            .add_witness(witness.clone())
+            .add_input(&"22".repeat(32), 1)
+            .add_witness(witness.clone())
+            .add_input(&"33".repeat(32), 2)
+            .add_witness(witness)
+            .add_output_sats(1_000_000, &p2wpkh("44"))
+            .add_output_sats(2_000_000, &p2wpkh("55"))
+            .build()
+            .unwrap();
+        detect_change(&mut tx, None);
+        assert_eq!(tx.outputs[0].change_score, Some(SAME_TYPE_WEIGHT));
+        assert_eq!(
+            tx.outputs[1].change_score,
+            Some(SAME_TYPE_WEIGHT + LAST_INDEX_WEIGHT)
+        );
+        assert!(tx
+            .iter_outputs()
+            .all(|output| output.change_score < Some(CHANGE_THRESHOLD) && !output.likely_change));
+    }
+
+    #[test]
+    fn test_no_signal() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        // A P2PKH prevout: no output matches the input type, and every value
+        // is round
+        let prevouts = [PrevOut {
+            value_sats: 2_000_000,
+            script_pubkey: format!("76a914{}88ac", "44".repeat(20)),
+        }];
+        tx.outputs[1].value_sats = 1_000_000;
+        tx.outputs.push(tx.outputs[0].clone());
+        detect_change(&mut tx, Some(&prevouts));
+        assert!(tx.outputs.iter().all(|output| !output.likely_change));
+    }
+}
@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+
+use crate::script::{
+    instructions, is_witness_program, Instruction, OP_1, OP_16, OP_CHECKMULTISIG, OP_CHECKSIG,
+    OP_DUP, OP_EQUAL, OP_EQUALVERIFY, OP_HASH160, OP_RETURN,
+};
+use crate::BitcoinTransaction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassifyOptions {
+    // How many levels of redeem/witness scripts classify_script descends
+    // into below the top-level script.
+    pub max_script_recursion: usize,
+}
+
+impl Default for ClassifyOptions {
+    fn default() -> Self {
+        ClassifyOptions {
+            max_script_recursion: 3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptType {
+    P2pk,
+    P2pkh,
+    // The inner script is None when the redeem/witness script wasn't supplied
+    P2sh {
+        redeem_script: Option<Box<ScriptType>>,
+    },
+    P2wpkh,
+    P2wsh {
+        witness_script: Option<Box<ScriptType>>,
+    },
+    P2tr,
+    Multisig {
+        required: u8,
+        keys: u8,
+    },
+    NullData,
+    WitnessUnknown {
+        version: u8,
+    },
+    NonStandard,
+    // A script was supplied at this level but max_script_recursion was hit
+    RecursionLimit,
+}
+
+impl ScriptType {
+    // The serialized `type` tag, e.g. "p2wpkh"
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptType::P2pk => "p2pk",
+            ScriptType::P2pkh => "p2pkh",
+            ScriptType::P2sh { .. } => "p2sh",
+            ScriptType::P2wpkh => "p2wpkh",
+            ScriptType::P2wsh { .. } => "p2wsh",
+            ScriptType::P2tr => "p2tr",
+            ScriptType::Multisig { .. } => "multisig",
+            ScriptType::NullData => "null_data",
+            ScriptType::WitnessUnknown { .. } => "witness_unknown",
+            ScriptType::NonStandard => "non_standard",
+            ScriptType::RecursionLimit => "recursion_limit",
+        }
+    }
+
+    // The name for display, with the version spelled out for witness
+    // programs nothing defines yet, e.g. "witness_v2_unknown"
+    pub fn label(&self) -> String {
+        match self {
+            ScriptType::WitnessUnknown { version } => format!("witness_v{}_unknown", version),
+            other => other.name().to_string(),
+        }
+    }
+}
+
+// Classifies `script`, descending into the scripts it commits to. `revealed`
+// holds those scripts outermost first: for P2SH-P2WSH that's the redeem
+// script followed by the witness script. Revealed scripts are taken as given,
+// their hashes aren't checked against the commitment.
+pub fn classify_script(
+    script: &[u8],
+    revealed: &[Vec<u8>],
+    options: &ClassifyOptions,
+) -> ScriptType {
+    classify_at(script, revealed, 0, options)
+}
+
+// Classifies the output spent by an input, using its scriptSig and witness
+// for the redeem and witness scripts.
+pub fn classify_spend(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_pubkey: &[u8],
+    options: &ClassifyOptions,
+) -> ScriptType {
+    classify_script(script_pubkey, &revealed_scripts(tx, input_index), options)
+}
+
+// The redeem script (last scriptSig push) and witness script (last witness
+// item) of an input, whichever are present.
+fn revealed_scripts(tx: &BitcoinTransaction, input_index: usize) -> Vec<Vec<u8>> {
+    let mut revealed = Vec::new();
+    let Some(input) = tx.inputs.get(input_index) else {
+        return revealed;
+    };
+
+    let script_sig = hex::decode(&input.scriptsig).unwrap_or_default();
+    let last_push = instructions(&script_sig)
+        .map_while(Result::ok)
+        .last()
+        .and_then(|(_, instruction)| match instruction {
+            Instruction::Push { data, .. } => Some(data.to_vec()),
+            Instruction::Op(_) => None,
+        });
+    revealed.extend(last_push);
+
+    if let Some(item) = tx.witness_items(input_index).last() {
+        revealed.extend(hex::decode(item).ok());
+    }
+    revealed
+}
+
+fn classify_at(
+    script: &[u8],
+    revealed: &[Vec<u8>],
+    depth: usize,
+    options: &ClassifyOptions,
+) -> ScriptType {
+    // Next level down, if the caller supplied one
+    let inner = || {
+        let (next, rest) = revealed.split_first()?;
+        if depth >= options.max_script_recursion {
+            return Some(Box::new(ScriptType::RecursionLimit));
+        }
+        Some(Box::new(classify_at(next, rest, depth + 1, options)))
+    };
+
+    match script {
+        [OP_HASH160, 0x14, hash @ .., OP_EQUAL] if hash.len() == 20 => ScriptType::P2sh {
+            redeem_script: inner(),
+        },
+        [OP_DUP, OP_HASH160, 0x14, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] if hash.len() == 20 => {
+            ScriptType::P2pkh
+        }
+        [0x00, 0x14, hash @ ..] if hash.len() == 20 => ScriptType::P2wpkh,
+        [0x00, 0x20, hash @ ..] if hash.len() == 32 => ScriptType::P2wsh {
+            witness_script: inner(),
+        },
+        [0x51, 0x20, key @ ..] if key.len() == 32 => ScriptType::P2tr,
+        [OP_RETURN, ..] => ScriptType::NullData,
+        [version, ..] if is_witness_program(script) => ScriptType::WitnessUnknown {
+            version: if *version == 0 { 0 } else { version - OP_1 + 1 },
+        },
+        [len, pubkey @ .., OP_CHECKSIG]
+            if *len as usize == pubkey.len() && (pubkey.len() == 33 || pubkey.len() == 65) =>
+        {
+            ScriptType::P2pk
+        }
+        _ => classify_multisig(script).unwrap_or(ScriptType::NonStandard),
+    }
+}
+
+// OP_m <pubkey>... OP_n OP_CHECKMULTISIG
+fn classify_multisig(script: &[u8]) -> Option<ScriptType> {
+    let parsed: Vec<_> = instructions(script)
+        .map(|r| r.map(|(_, instruction)| instruction))
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    let small_int = |instruction: &Instruction| match instruction {
+        Instruction::Op(op) if (OP_1..=OP_16).contains(op) => Some(op - OP_1 + 1),
+        _ => None,
+    };
+
+    let [first, keys @ .., last_n, Instruction::Op(OP_CHECKMULTISIG)] = parsed.as_slice() else {
+        return None;
+    };
+    let required = small_int(first)?;
+    let total = small_int(last_n)?;
+    let keys_ok = keys.iter().all(
+        |key| matches!(key, Instruction::Push { data, .. } if data.len() == 33 || data.len() == 65),
+    );
+
+    if keys_ok && keys.len() == total as usize && required <= total {
+        Some(ScriptType::Multisig {
+            required,
+            keys: total,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    fn script(hex_str: &str) -> Vec<u8> {
+        hex::decode(hex_str).unwrap()
+    }
+
+    fn multisig_2_of_2() -> Vec<u8> {
+        let key = format!("21{}", "02".repeat(33));
+        script(&format!("52{}{}52ae", key, key))
+    }
+
+    #[test]
+    fn test_classify_standard_scripts() {
+        let options = ClassifyOptions::default();
+        let cases = [
+            (
+                "76a91485d78eb795bd9c8a21afefc8b6fdaedf7183680988ac",
+                ScriptType::P2pkh,
+            ),
+            (
+                "001485d78eb795bd9c8a21afefc8b6fdaedf71836809",
+                ScriptType::P2wpkh,
+            ),
+            (&format!("5120{}", "11".repeat(32)), ScriptType::P2tr),
+            (
+                &format!("5210{}", "11".repeat(16)),
+                ScriptType::WitnessUnknown { version: 2 },
+            ),
+            ("6a0568656c6c6f", ScriptType::NullData),
+            (&format!("21{}ac", "02".repeat(33)), ScriptType::P2pk),
+            ("51", ScriptType::NonStandard),
+        ];
+        for (hex_str, expected) in cases {
+            assert_eq!(classify_script(&script(hex_str), &[], &options), expected);
+        }
+        assert_eq!(
+            classify_script(&multisig_2_of_2(), &[], &options),
+            ScriptType::Multisig {
+                required: 2,
+                keys: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_classify_p2sh_p2wsh_multisig() {
+        let p2sh = script(&format!("a914{}87", "00".repeat(20)));
+        let p2wsh = script(&format!("0020{}", "00".repeat(32)));
+        let revealed = vec![p2wsh, multisig_2_of_2()];
+
+        assert_eq!(
+            classify_script(&p2sh, &revealed, &ClassifyOptions::default()),
+            ScriptType::P2sh {
+                redeem_script: Some(Box::new(ScriptType::P2wsh {
+                    witness_script: Some(Box::new(ScriptType::Multisig {
+                        required: 2,
+                        keys: 2
+                    }))
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn test_classify_stops_at_recursion_limit() {
+        // P2SH whose "redeem script" is another P2SH, many levels deep
+        let p2sh = script(&format!("a914{}87", "00".repeat(20)));
+        let revealed = vec![p2sh.clone(); 1000];
+
+        let mut result = classify_script(&p2sh, &revealed, &ClassifyOptions::default());
+        let mut levels = 0;
+        while let ScriptType::P2sh {
+            redeem_script: Some(inner),
+        } = result
+        {
+            levels += 1;
+            result = *inner;
+        }
+        assert_eq!(levels, 4);
+        assert_eq!(result, ScriptType::RecursionLimit);
+
+        let options = ClassifyOptions {
+            max_script_recursion: 0,
+        };
+        assert_eq!(
+            classify_script(&p2sh, &revealed, &options),
+            ScriptType::P2sh {
+                redeem_script: Some(Box::new(ScriptType::RecursionLimit))
+            }
+        );
+    }
+
+    #[test]
+    fn test_classify_spend_sample() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let prevout = script("0014841b80d2cc75f5345c482af96294d04fdd66b2b7");
+        assert_eq!(
+            classify_spend(&tx, 0, &prevout, &ClassifyOptions::default()),
+            ScriptType::P2wpkh
+        );
+    }
+}
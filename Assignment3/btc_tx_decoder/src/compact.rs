@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::input_kind::InputKind;
+use crate::{BitcoinTransaction, Network, Serialization, TxInput, TxOutput, WitnessStack};
+
+// What btc_tx_decoder_compact prints: btc_tx_decoder's JSON without the
+// segwit-only fields when they are empty, i.e. for legacy transactions.
+// BitcoinTransaction itself keeps them so its JSON shape doesn't depend on
+// the serialization.
+#[derive(Serialize)]
+pub(crate) struct CompactTransaction<'a> {
+    version: &'a str,
+    version_meaning: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    marker: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    flag: &'a str,
+    serialization: Serialization,
+    inputcount: &'a str,
+    inputs: &'a [TxInput],
+    outputcount: &'a str,
+    outputs: &'a [TxOutput],
+    // In the numeric-key shape, as btc_tx_decoder prints it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    witness: Vec<Value>,
+    locktime: &'a str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+    rbf_signaled: bool,
+    input_kind_counts: &'a BTreeMap<InputKind, usize>,
+    network: Network,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    warnings: &'a [String],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    hints: &'a [String],
+}
+
+impl<'a> From<&'a BitcoinTransaction> for CompactTransaction<'a> {
+    fn from(tx: &'a BitcoinTransaction) -> Self {
+        // Destructured so a new field can't be forgotten here
+        let BitcoinTransaction {
+            version,
+            version_meaning,
+            marker,
+            flag,
+            serialization,
+            inputcount,
+            inputs,
+            outputcount,
+            outputs,
+            witness,
+            locktime,
+            truncated,
+            rbf_signaled,
+            input_kind_counts,
+            network,
+            warnings,
+            hints,
+        } = tx;
+        CompactTransaction {
+            version,
+            version_meaning,
+            marker,
+            flag,
+            serialization: *serialization,
+            inputcount,
+            inputs,
+            outputcount,
+            outputs,
+            witness: witness.iter().map(WitnessStack::to_legacy_json).collect(),
+            locktime,
+            truncated: *truncated,
+            rbf_signaled: *rbf_signaled,
+            input_kind_counts,
+            network: *network,
+            warnings,
+            hints,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::TxBuilder;
+    use crate::tests::SAMPLE_TX;
+    use crate::{btc_tx_decoder, btc_tx_decoder_compact};
+    use serde_json::Value;
+
+    fn keys(json: &str) -> Vec<String> {
+        let value: Value = serde_json::from_str(json).unwrap();
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_compact_legacy() {
+        let hex = TxBuilder::new()
+            .add_input(&"ab".repeat(32), 0)
+            .add_output_sats(1_000, &[0x51])
+            .build_hex()
+            .unwrap();
+        let compact = keys(&btc_tx_decoder_compact(&hex).unwrap());
+        for key in ["marker", "flag", "witness", "warnings"] {
+            assert!(!compact.contains(&key.to_string()), "{}", key);
+        }
+        assert!(compact.contains(&"version".to_string()));
+
+        // The regular output is unchanged
+        let full = keys(&btc_tx_decoder(&hex).unwrap());
+        assert!(full.contains(&"witness".to_string()));
+    }
+
+    #[test]
+    fn test_compact_segwit() {
+        let compact: Value =
+            serde_json::from_str(&btc_tx_decoder_compact(SAMPLE_TX).unwrap()).unwrap();
+        let full: Value = serde_json::from_str(&btc_tx_decoder(SAMPLE_TX).unwrap()).unwrap();
+        assert_eq!(compact["witness"], full["witness"]);
+        assert_eq!(compact["marker"], "00");
+        assert_eq!(compact["flag"], "01");
+    }
+}
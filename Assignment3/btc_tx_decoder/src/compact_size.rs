@@ -0,0 +1,130 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use crate::error::DecodeError;
+
+// A length or count prefix as it appeared on the wire: the decoded value and
+// its raw encoding, which a non-canonical encoder may have padded.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompactSize {
+    pub value: u64,
+    // Hex, exactly as read
+    pub encoded: String,
+}
+
+impl CompactSize {
+    // Reads the CompactSize at `pos`, returning it with the bytes consumed.
+    pub fn read(bytes: &[u8], pos: usize) -> Result<(CompactSize, usize), DecodeError> {
+        let first = *bytes.get(pos).ok_or(DecodeError::UnexpectedEof {
+            needed: 1,
+            available: 0,
+        })?;
+        let size = match first {
+            0..=0xfc => 1,
+            0xfd => 3,
+            0xfe => 5,
+            0xff => 9,
+        };
+        let raw = bytes
+            .get(pos..pos + size)
+            .ok_or(DecodeError::UnexpectedEof {
+                needed: size,
+                available: bytes.len() - pos,
+            })?;
+
+        let value = if size == 1 {
+            first as u64
+        } else {
+            let mut buf = [0u8; 8];
+            buf[..size - 1].copy_from_slice(&raw[1..]);
+            u64::from_le_bytes(buf)
+        };
+        let compact_size = CompactSize {
+            value,
+            encoded: hex::encode(raw),
+        };
+        Ok((compact_size, size))
+    }
+
+    // The shortest encoding of `value`
+    pub fn encode(value: u64) -> Vec<u8> {
+        match value {
+            0..=0xfc => vec![value as u8],
+            0xfd..=0xffff => {
+                let mut out = vec![0xfd];
+                out.extend_from_slice(&(value as u16).to_le_bytes());
+                out
+            }
+            0x10000..=0xffff_ffff => {
+                let mut out = vec![0xfe];
+                out.extend_from_slice(&(value as u32).to_le_bytes());
+                out
+            }
+            _ => {
+                let mut out = vec![0xff];
+                out.extend_from_slice(&value.to_le_bytes());
+                out
+            }
+        }
+    }
+
+    // The value as a length. Anything too big for usize saturates, which no
+    // buffer can satisfy, so bounds checks still reject it.
+    pub fn len(&self) -> usize {
+        usize::try_from(self.value).unwrap_or(usize::MAX)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value == 0
+    }
+
+    // Whether the value was written with its shortest encoding
+    pub fn is_canonical(&self) -> bool {
+        self.encoded == hex::encode(Self::encode(self.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_size_round_trip() {
+        for (value, size) in [
+            (0xfc, 1),
+            (0xfd, 3),
+            (0xffff, 3),
+            (0x10000, 5),
+            (0xffff_ffff, 5),
+            (0x1_0000_0000, 9),
+            (u64::MAX, 9),
+        ] {
+            let encoded = CompactSize::encode(value);
+            assert_eq!(encoded.len(), size);
+            let (compact_size, consumed) = CompactSize::read(&encoded, 0).unwrap();
+            assert_eq!(compact_size.value, value);
+            assert_eq!(compact_size.encoded, hex::encode(&encoded));
+            assert!(compact_size.is_canonical());
+            assert_eq!(consumed, size);
+        }
+    }
+
+    #[test]
+    fn test_compact_size_errors() {
+        let (padded, _) = CompactSize::read(&[0xfd, 0x16, 0x00], 0).unwrap();
+        assert_eq!(padded.value, 0x16);
+        assert!(!padded.is_canonical());
+
+        assert_eq!(
+            CompactSize::read(&[0x00, 0xfe, 0x01, 0x02], 1),
+            Err(DecodeError::UnexpectedEof {
+                needed: 5,
+                available: 3
+            })
+        );
+        assert!(CompactSize::read(&[], 0).is_err());
+    }
+}
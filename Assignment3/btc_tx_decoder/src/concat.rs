@@ -0,0 +1,92 @@
+use std::ops::Range;
+
+use crate::error::DecodeError;
+use crate::{normalize_hex, parse_transaction_prefix, BitcoinTransaction, DecodeLimits};
+
+// Decodes back-to-back raw transactions with no framing between them, as in
+// mempool dumps. Each transaction comes with the byte range it took in the
+// input. Fails with PartialDecode on the first one that doesn't decode.
+pub fn decode_all(hex: &str) -> Result<Vec<(BitcoinTransaction, Range<usize>)>, DecodeError> {
+    match decode_all_lenient(hex) {
+        (txs, None) => Ok(txs),
+        (_, Some(error)) => Err(error),
+    }
+}
+
+// Like decode_all, but keeps the transactions decoded before a failure.
+// The error, if any, is a PartialDecode (or InvalidHex, with nothing
+// decoded).
+pub fn decode_all_lenient(
+    hex: &str,
+) -> (Vec<(BitcoinTransaction, Range<usize>)>, Option<DecodeError>) {
+    let bytes = match hex::decode(normalize_hex(hex)) {
+        Ok(bytes) => bytes,
+        Err(e) => return (Vec::new(), Some(DecodeError::InvalidHex(e.to_string()))),
+    };
+
+    let mut txs = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        match parse_transaction_prefix(&bytes[offset..], &DecodeLimits::default()) {
+            Ok((tx, len)) => {
+                txs.push((tx, offset..offset + len));
+                offset += len;
+            }
+            Err(error) => {
+                let error = DecodeError::PartialDecode {
+                    decoded: txs.len(),
+                    offset,
+                    error: Box::new(error),
+                };
+                return (txs, Some(error));
+            }
+        }
+    }
+    (txs, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+    use crate::tests::SAMPLE_TX;
+    use crate::{decode_transaction, encode_transaction};
+
+    fn legacy(value_sats: u64) -> String {
+        TxBuilder::new()
+            .add_input(&"ab".repeat(32), 3)
+            .add_output_sats(value_sats, &[0x51])
+            .build_hex()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_decode_all() {
+        let (first, last) = (legacy(1_000), legacy(2_000));
+        let hex = format!("{}{}{}", first, SAMPLE_TX, last);
+        let txs = decode_all(&hex).unwrap();
+
+        let ends: Vec<Range<usize>> = txs.iter().map(|(_, range)| range.clone()).collect();
+        let (a, b) = (first.len() / 2, first.len() / 2 + SAMPLE_TX.len() / 2);
+        assert_eq!(ends, vec![0..a, a..b, b..hex.len() / 2]);
+        assert_eq!(txs[0].0, decode_transaction(&first).unwrap());
+        assert_eq!(txs[1].0, decode_transaction(SAMPLE_TX).unwrap());
+        assert!(txs[1].0.is_segwit());
+        assert_eq!(encode_transaction(&txs[2].0).unwrap(), last);
+    }
+
+    #[test]
+    fn test_decode_all_failure() {
+        let hex = format!("{}{}{}", legacy(1_000), SAMPLE_TX, &SAMPLE_TX[..100]);
+        let offset = hex.len() / 2 - 50;
+        let error = decode_all(&hex).unwrap_err();
+        assert!(matches!(
+            error,
+            DecodeError::PartialDecode { decoded: 2, offset: o, .. } if o == offset
+        ));
+
+        let (txs, error) = decode_all_lenient(&hex);
+        assert_eq!(txs.len(), 2);
+        assert!(error.is_some());
+    }
+}
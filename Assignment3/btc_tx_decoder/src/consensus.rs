@@ -0,0 +1,398 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::DecodeError;
+use crate::lint::MAX_MONEY;
+use crate::{encode_transaction, parse_transaction, BitcoinTransaction, CompactSize};
+
+pub use crate::limits::MAX_BLOCK_WEIGHT;
+
+const NULL_TXID: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+// OP_RETURN, a 36-byte push and the commitment's tag
+const WITNESS_COMMITMENT_HEADER: &str = "6a24aa21a9ed";
+
+// The output spent by an input
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrevOut {
+    pub value_sats: u64,
+    pub script_pubkey: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleStatus {
+    Pass,
+    Fail,
+    // The rule needs data that wasn't supplied (prevouts)
+    Skipped,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RuleResult {
+    pub rule: &'static str,
+    pub status: RuleStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConsensusReport {
+    // No rule failed
+    pub passed: bool,
+    pub rules: Vec<RuleResult>,
+}
+
+impl ConsensusReport {
+    pub fn rule(&self, rule: &str) -> Option<&RuleResult> {
+        self.rules.iter().find(|r| r.rule == rule)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &RuleResult> {
+        self.rules.iter().filter(|r| r.status == RuleStatus::Fail)
+    }
+}
+
+// Runs the context-free consensus checks on a decoded transaction, plus value
+// conservation when the spent outputs are given (one per input, in order).
+// Script and signature validation are out of scope.
+pub fn consensus_check(tx: &BitcoinTransaction, prevouts: Option<&[PrevOut]>) -> ConsensusReport {
+    let rules = vec![
+        check("canonical-compact-size", canonical_compact_sizes(tx)),
+        check("canonical-segwit", canonical_segwit(tx)),
+        check("serialization-roundtrip", roundtrip(tx)),
+        check(
+            "inputs-not-empty",
+            fail_if(tx.inputs.is_empty(), || "no inputs".to_string()),
+        ),
+        check(
+            "outputs-not-empty",
+            fail_if(tx.outputs.is_empty(), || "no outputs".to_string()),
+        ),
+        check("size-limit", size_limit(tx)),
+        check(
+            "output-value-range",
+            validate_amounts(tx).map_err(|e| e.to_string()),
+        ),
+        check("no-duplicate-inputs", duplicate_inputs(tx)),
+        check("prevout-not-null", null_prevouts(tx)),
+        match prevouts {
+            Some(prevouts) => check("value-conservation", value_conservation(tx, prevouts)),
+            None => RuleResult {
+                rule: "value-conservation",
+                status: RuleStatus::Skipped,
+                detail: Some("prevouts not supplied".to_string()),
+            },
+        },
+    ];
+
+    ConsensusReport {
+        passed: rules.iter().all(|r| r.status != RuleStatus::Fail),
+        rules,
+    }
+}
+
+fn check(rule: &'static str, result: Result<(), String>) -> RuleResult {
+    match result {
+        Ok(()) => RuleResult {
+            rule,
+            status: RuleStatus::Pass,
+            detail: None,
+        },
+        Err(detail) => RuleResult {
+            rule,
+            status: RuleStatus::Fail,
+            detail: Some(detail),
+        },
+    }
+}
+
+fn fail_if(failed: bool, detail: impl FnOnce() -> String) -> Result<(), String> {
+    if failed {
+        Err(detail())
+    } else {
+        Ok(())
+    }
+}
+
+// Every length prefix uses the shortest CompactSize encoding
+fn canonical_compact_sizes(tx: &BitcoinTransaction) -> Result<(), String> {
+    let mut fields = vec![
+        ("inputcount".to_string(), tx.inputcount.as_str()),
+        ("outputcount".to_string(), tx.outputcount.as_str()),
+    ];
+    for (i, input) in tx.inputs.iter().enumerate() {
+        fields.push((format!("input {} scriptsigsize", i), &input.scriptsigsize));
+    }
+    for (i, output) in tx.outputs.iter().enumerate() {
+        fields.push((
+            format!("output {} scriptpubkeysize", i),
+            &output.scriptpubkeysize,
+        ));
+    }
+    for (i, stack) in tx.witness.iter().enumerate() {
+        fields.push((format!("witness {} stackitems", i), &stack.stackitems));
+    }
+
+    for (name, field) in fields {
+        let bytes = hex::decode(field).map_err(|e| format!("{}: {}", name, e))?;
+        let compact_size = match CompactSize::read(&bytes, 0) {
+            Ok((compact_size, size)) if size == bytes.len() => compact_size,
+            _ => return Err(format!("{} is not a CompactSize", name)),
+        };
+        if !compact_size.is_canonical() {
+            return Err(format!("{} {} is not minimally encoded", name, field));
+        }
+    }
+    Ok(())
+}
+
+// Bitcoin Core refuses the segwit serialization when every witness is empty
+fn canonical_segwit(tx: &BitcoinTransaction) -> Result<(), String> {
+    fail_if(
+        tx.is_segwit() && (0..tx.inputs.len()).all(|i| tx.witness_items(i).is_empty()),
+        || "segwit serialization with no witness data".to_string(),
+    )
+}
+
+fn roundtrip(tx: &BitcoinTransaction) -> Result<(), String> {
+    let encoded = encode_transaction(tx)?;
+    let bytes = hex::decode(&encoded).map_err(|e| e.to_string())?;
+    let reparsed = parse_transaction(&bytes)?;
+    fail_if(encode_transaction(&reparsed).ok() != Some(encoded), || {
+        "re-encoding does not reproduce the transaction".to_string()
+    })
+}
+
+fn size_limit(tx: &BitcoinTransaction) -> Result<(), String> {
+    let stripped_weight = tx.base_size() * 4;
+    fail_if(stripped_weight > MAX_BLOCK_WEIGHT, || {
+        format!(
+            "stripped size {} bytes exceeds the block weight limit",
+            tx.base_size()
+        )
+    })
+}
+
+// Each output value and their sum must be within 0..=MAX_MONEY, as in
+// CheckTransaction. The running total stops at the first value over the
+// limit, so it can't overflow.
+pub fn validate_amounts(tx: &BitcoinTransaction) -> Result<(), DecodeError> {
+    let mut total: u64 = 0;
+    for (i, output) in tx.outputs.iter().enumerate() {
+        if output.value_sats > MAX_MONEY {
+            return Err(DecodeError::MoneyRangeError {
+                output: Some(i),
+                value: output.value_sats,
+            });
+        }
+        total += output.value_sats;
+        if total > MAX_MONEY {
+            return Err(DecodeError::MoneyRangeError {
+                output: None,
+                value: total,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn duplicate_inputs(tx: &BitcoinTransaction) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for (i, input) in tx.inputs.iter().enumerate() {
+        if !seen.insert((&input.txid, &input.vout)) {
+            return Err(format!("input {} spends an outpoint already spent", i));
+        }
+    }
+    Ok(())
+}
+
+// A single input spending the null prevout
+pub(crate) fn is_coinbase(tx: &BitcoinTransaction) -> bool {
+    matches!(tx.inputs.as_slice(), [input] if input.txid == NULL_TXID && input.vout == "ffffffff")
+}
+
+// The witness commitment of a coinbase (BIP141): the hash of the block's
+// witness merkle root and the witness reserved value, in an output whose
+// scriptPubKey starts OP_RETURN, a 36-byte push and 0xaa21a9ed. If several
+// outputs match, the last one counts. Hex, in script byte order.
+pub fn extract_witness_commitment(tx: &BitcoinTransaction) -> Option<String> {
+    if !is_coinbase(tx) {
+        return None;
+    }
+    tx.outputs.iter().rev().find_map(|output| {
+        let script = output.scriptpubkey.as_str();
+        match script.strip_prefix(WITNESS_COMMITMENT_HEADER) {
+            Some(rest) if rest.len() >= 64 => Some(rest[..64].to_string()),
+            _ => None,
+        }
+    })
+}
+
+// Coinbase: a single null prevout with a 2-100 byte scriptSig. Anything else
+// may not spend the null prevout.
+fn null_prevouts(tx: &BitcoinTransaction) -> Result<(), String> {
+    let is_null = |i: usize| tx.inputs[i].txid == NULL_TXID && tx.inputs[i].vout == "ffffffff";
+
+    if tx.inputs.len() == 1 && is_null(0) {
+        let len = tx.inputs[0].scriptsig.len() / 2;
+        return fail_if(!(2..=100).contains(&len), || {
+            format!("coinbase scriptSig is {} bytes, must be 2 to 100", len)
+        });
+    }
+    match (0..tx.inputs.len()).find(|&i| is_null(i)) {
+        Some(i) => Err(format!("input {} spends the null prevout", i)),
+        None => Ok(()),
+    }
+}
+
+fn value_conservation(tx: &BitcoinTransaction, prevouts: &[PrevOut]) -> Result<(), String> {
+    if prevouts.len() != tx.inputs.len() {
+        return Err(format!(
+            "{} prevouts supplied for {} inputs",
+            prevouts.len(),
+            tx.inputs.len()
+        ));
+    }
+
+    let mut total_in: u64 = 0;
+    for (i, prevout) in prevouts.iter().enumerate() {
+        total_in = total_in.saturating_add(prevout.value_sats);
+        if prevout.value_sats > MAX_MONEY || total_in > MAX_MONEY {
+            return Err(format!("input {} value is out of range", i));
+        }
+    }
+    let total_out = tx.total_output_value();
+    fail_if(total_out > total_in, || {
+        format!(
+            "outputs spend {} sats but inputs only provide {}",
+            total_out, total_in
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    fn status(report: &ConsensusReport, rule: &str) -> RuleStatus {
+        report.rule(rule).unwrap().status
+    }
+
+    #[test]
+    fn test_consensus_check_sample() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let report = consensus_check(&tx, None);
+
+        assert!(report.passed);
+        for rule in [
+            "canonical-compact-size",
+            "canonical-segwit",
+            "serialization-roundtrip",
+        ] {
+            assert_eq!(status(&report, rule), RuleStatus::Pass);
+        }
+        assert_eq!(status(&report, "value-conservation"), RuleStatus::Skipped);
+
+        let prevouts = [PrevOut {
+            value_sats: 1_551_000,
+            script_pubkey: String::new(),
+        }];
+        let report = consensus_check(&tx, Some(&prevouts));
+        assert_eq!(status(&report, "value-conservation"), RuleStatus::Pass);
+    }
+
+    #[test]
+    fn test_consensus_check_failures() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.inputs.push(tx.inputs[0].clone());
+        tx.inputcount = "02".to_string();
+        tx.witness.push(tx.witness[0].clone());
+
+        let report = consensus_check(&tx, None);
+        assert!(!report.passed);
+        let failed: Vec<_> = report.failures().map(|r| r.rule).collect();
+        assert_eq!(failed, vec!["no-duplicate-inputs"]);
+
+        let prevouts = vec![
+            PrevOut {
+                value_sats: 500_000,
+                script_pubkey: String::new(),
+            };
+            2
+        ];
+        let report = consensus_check(&tx, Some(&prevouts));
+        assert_eq!(status(&report, "value-conservation"), RuleStatus::Fail);
+    }
+
+    #[test]
+    fn test_non_canonical_compact_size() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.outputs[0].scriptpubkeysize = "fd1600".to_string();
+        let report = consensus_check(&tx, None);
+        assert_eq!(status(&report, "canonical-compact-size"), RuleStatus::Fail);
+    }
+
+    #[test]
+    fn test_validate_amounts() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(validate_amounts(&tx), Ok(()));
+
+        // ffffffffffffff7f: the largest amount the 8 bytes can hold
+        let hex =
+            encode_transaction(&tx)
+                .unwrap()
+                .replacen("20a1070000000000", "ffffffffffffff7f", 1);
+        let corrupted = decode_transaction(&hex).unwrap();
+        assert_eq!(
+            validate_amounts(&corrupted),
+            Err(DecodeError::MoneyRangeError {
+                output: Some(0),
+                value: i64::MAX as u64
+            })
+        );
+        assert_eq!(
+            status(&consensus_check(&corrupted, None), "output-value-range"),
+            RuleStatus::Fail
+        );
+
+        // Each value in range, the sum not
+        tx.outputs[0].value_sats = MAX_MONEY;
+        assert_eq!(
+            validate_amounts(&tx),
+            Err(DecodeError::MoneyRangeError {
+                output: None,
+                value: MAX_MONEY + 1_050_700
+            })
+        );
+    }
+
+    #[test]
+    fn test_extract_witness_commitment() {
+        use crate::builder::TxBuilder;
+
+        let commitment = [0x5c; 32];
+        let mut script = hex::decode(WITNESS_COMMITMENT_HEADER).unwrap();
+        script.extend(commitment);
+        let p2wpkh = hex::decode("001485d78eb795bd9c8a21afefc8b6fdaedf71836809").unwrap();
+        let coinbase = TxBuilder::new()
+            .add_input(&"00".repeat(32), 0xffffffff)
+            .script_sig(&[0x03, 0x40, 0x0d, 0x03])
+            // The witness reserved value
+            .add_witness(vec![vec![0; 32]])
+            .add_output_sats(312_500_000, &p2wpkh)
+            .add_output_sats(0, &script)
+            .build()
+            .unwrap();
+        assert_eq!(extract_witness_commitment(&coinbase), Some("5c".repeat(32)));
+
+        // Not a coinbase, or no commitment
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(extract_witness_commitment(&tx), None);
+        let mut coinbase = coinbase;
+        coinbase.outputs.pop();
+        assert_eq!(extract_witness_commitment(&coinbase), None);
+    }
+}
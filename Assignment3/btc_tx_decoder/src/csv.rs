@@ -0,0 +1,293 @@
+use std::str::FromStr;
+
+use crate::address::address_on;
+use crate::classify::{classify_script, ClassifyOptions};
+use crate::{le_hex_to_u64, BitcoinTransaction};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Csv,
+    // report::report
+    Text,
+    // pretty::format_pretty
+    Pretty,
+    // annotated::annotate_hex
+    Annotated,
+    // explain::explain, one line per field
+    Explain,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "text" => Ok(OutputFormat::Text),
+            "pretty" => Ok(OutputFormat::Pretty),
+            "annotated" => Ok(OutputFormat::Annotated),
+            "explain" => Ok(OutputFormat::Explain),
+            _ => Err(format!(
+                "unknown format: {} (expected json, csv, text, pretty, annotated or explain)",
+                s
+            )),
+        }
+    }
+}
+
+// Which rows to_csv emits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvRows {
+    Inputs,
+    #[default]
+    Outputs,
+    // Inputs then outputs of each transaction, under a combined header with
+    // a `row` column telling them apart
+    Both,
+}
+
+impl FromStr for CsvRows {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "inputs" => Ok(CsvRows::Inputs),
+            "outputs" => Ok(CsvRows::Outputs),
+            "both" => Ok(CsvRows::Both),
+            _ => Err(format!(
+                "unknown rows: {} (expected inputs, outputs or both)",
+                s
+            )),
+        }
+    }
+}
+
+const OUTPUT_COLUMNS: &[&str] = &["txid", "vout_index", "value_sats", "script_type", "address"];
+const INPUT_COLUMNS: &[&str] = &[
+    "txid",
+    "input_index",
+    "prevout_txid",
+    "prevout_vout",
+    "sequence",
+    "witness_items",
+];
+const BOTH_COLUMNS: &[&str] = &[
+    "row",
+    "txid",
+    "index",
+    "value_sats",
+    "script_type",
+    "address",
+    "prevout_txid",
+    "prevout_vout",
+    "sequence",
+    "witness_items",
+];
+
+// One header line, then the rows of every transaction in order; the txid
+// column tells transactions apart. Numbers are decimal and txids are in
+// display order.
+pub fn to_csv(txs: &[BitcoinTransaction], rows: CsvRows) -> String {
+    let header = match rows {
+        CsvRows::Inputs => INPUT_COLUMNS,
+        CsvRows::Outputs => OUTPUT_COLUMNS,
+        CsvRows::Both => BOTH_COLUMNS,
+    };
+    let mut out = csv_line(header.iter().map(|c| c.to_string()));
+
+    for tx in txs {
+        // Empty for a truncated transaction
+        let txid = tx.txid().unwrap_or_default();
+        if rows != CsvRows::Outputs {
+            for (index, fields) in input_fields(tx).into_iter().enumerate() {
+                let [prevout_txid, prevout_vout, sequence, witness_items] = fields;
+                let line = match rows {
+                    CsvRows::Both => vec![
+                        "input".to_string(),
+                        txid.clone(),
+                        index.to_string(),
+                        String::new(),
+                        String::new(),
+                        String::new(),
+                        prevout_txid,
+                        prevout_vout,
+                        sequence,
+                        witness_items,
+                    ],
+                    _ => vec![
+                        txid.clone(),
+                        index.to_string(),
+                        prevout_txid,
+                        prevout_vout,
+                        sequence,
+                        witness_items,
+                    ],
+                };
+                out.push_str(&csv_line(line));
+            }
+        }
+        if rows != CsvRows::Inputs {
+            for (index, fields) in output_fields(tx).into_iter().enumerate() {
+                let [value_sats, script_type, address] = fields;
+                let mut line = vec![
+                    txid.clone(),
+                    index.to_string(),
+                    value_sats,
+                    script_type,
+                    address,
+                ];
+                if rows == CsvRows::Both {
+                    line.insert(0, "output".to_string());
+                    line.extend(std::iter::repeat_n(String::new(), 4));
+                }
+                out.push_str(&csv_line(line));
+            }
+        }
+    }
+    out
+}
+
+// prevout_txid, prevout_vout, sequence and witness_items of each input
+fn input_fields(tx: &BitcoinTransaction) -> Vec<[String; 4]> {
+    tx.inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let mut prevout = hex::decode(&input.txid).unwrap_or_default();
+            prevout.reverse();
+            [
+                hex::encode(prevout),
+                number(&input.vout),
+                number(&input.sequence),
+                tx.witness_items(index).len().to_string(),
+            ]
+        })
+        .collect()
+}
+
+// value_sats, script_type and address of each output
+fn output_fields(tx: &BitcoinTransaction) -> Vec<[String; 3]> {
+    tx.outputs
+        .iter()
+        .map(|output| {
+            let script = hex::decode(&output.scriptpubkey).unwrap_or_default();
+            let script_type = classify_script(&script, &[], &ClassifyOptions::default());
+            [
+                output.value_sats.to_string(),
+                script_type.label(),
+                address_on(&script, tx.network).unwrap_or_default(),
+            ]
+        })
+        .collect()
+}
+
+fn number(le_hex: &str) -> String {
+    le_hex_to_u64(le_hex)
+        .map(|n| n.to_string())
+        .unwrap_or_default()
+}
+
+// RFC 4180: quote fields holding a comma, quote or line break, doubling any
+// quotes inside.
+fn csv_line(fields: impl IntoIterator<Item = String>) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    // Splits CSV text into records, honouring quoted fields
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        for line in text.lines() {
+            let mut fields = Vec::new();
+            let mut field = String::new();
+            let mut quoted = false;
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (c, quoted) {
+                    ('"', true) if chars.peek() == Some(&'"') => {
+                        field.push('"');
+                        chars.next();
+                    }
+                    ('"', _) => quoted = !quoted,
+                    (',', false) => fields.push(std::mem::take(&mut field)),
+                    _ => field.push(c),
+                }
+            }
+            fields.push(field);
+            records.push(fields);
+        }
+        records
+    }
+
+    #[test]
+    fn test_outputs_csv() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let records = parse_csv(&to_csv(&[tx], CsvRows::Outputs));
+
+        assert_eq!(records[0], OUTPUT_COLUMNS);
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[1],
+            vec![
+                "04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842",
+                "0",
+                "500000",
+                "p2wpkh",
+                "bc1qshtcadu4hkwg5gd0alytdldwmaccx6qfh9fg9u",
+            ]
+        );
+        assert_eq!(records[2][2], "1050700");
+    }
+
+    #[test]
+    fn test_inputs_and_batch_csv() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let mut other = tx.clone();
+        other.locktime = "00000000".to_string();
+        let records = parse_csv(&to_csv(&[tx, other.clone()], CsvRows::Both));
+
+        assert_eq!(records[0], BOTH_COLUMNS);
+        // input, two outputs, for each transaction
+        assert_eq!(records.len(), 7);
+        let input = &records[1];
+        assert_eq!(input[0], "input");
+        assert_eq!(
+            input[6],
+            "c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131"
+        );
+        assert_eq!(input[7], "1");
+        assert_eq!(input[8], "4294967293");
+        assert_eq!(input[9], "2");
+        assert_eq!(records[4][1], other.txid().unwrap());
+        assert_eq!(records[6][0], "output");
+
+        let records = parse_csv(&to_csv(&[other], CsvRows::Inputs));
+        assert_eq!(records[0], INPUT_COLUMNS);
+        assert_eq!(records[1][4], "4294967293");
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let line = csv_line(["a,b".to_string(), "say \"hi\"".to_string(), "c".to_string()]);
+        assert_eq!(line, "\"a,b\",\"say \"\"hi\"\"\",c\n");
+        assert_eq!(parse_csv(&line)[0], vec!["a,b", "say \"hi\"", "c"]);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::address::address_on;
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::network::Network;
+use crate::script::{instructions, Instruction};
+
+// BIP380 descriptor checksum: characters are mapped through INPUT_CHARSET
+// and the result is a BCH code over GF(32) written in the bech32 alphabet.
+const INPUT_CHARSET: &[u8] =
+    b"0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u64; 5] = [
+    0xf5dee51989,
+    0xa9fdca3312,
+    0x1bab10e32d,
+    0x3706b1677a,
+    0x644d626ffd,
+];
+
+// The output descriptor for a scriptPubKey, checksum included, as Bitcoin
+// Core infers it without wallet data: pk() and multi() when the keys are in
+// the script, rawtr() for a taproot output key (tr() would tweak it again),
+// addr() when only a hash is committed to and raw() for anything else.
+pub fn descriptor_for_script(script: &[u8]) -> String {
+    descriptor_for_script_on(script, Network::Mainnet)
+}
+
+// descriptor_for_script, with addr() in the address format of `network`
+pub fn descriptor_for_script_on(script: &[u8], network: Network) -> String {
+    let descriptor = match classify_script(script, &[], &ClassifyOptions::default()) {
+        ScriptType::P2pk => format!("pk({})", hex::encode(&script[1..script.len() - 1])),
+        ScriptType::Multisig { required, .. } => {
+            let keys: Vec<String> = instructions(script)
+                .filter_map(|r| match r {
+                    Ok((_, Instruction::Push { data, .. })) => Some(hex::encode(data)),
+                    _ => None,
+                })
+                .collect();
+            format!("multi({},{})", required, keys.join(","))
+        }
+        ScriptType::P2tr => format!("rawtr({})", hex::encode(&script[2..])),
+        _ => match address_on(script, network) {
+            Some(address) => format!("addr({})", address),
+            None => format!("raw({})", hex::encode(script)),
+        },
+    };
+    add_checksum(&descriptor)
+}
+
+// `descriptor#checksum`. Every descriptor built here only uses characters
+// from INPUT_CHARSET.
+fn add_checksum(descriptor: &str) -> String {
+    let mut symbols = Vec::new();
+    let mut groups = Vec::new();
+    for c in descriptor.bytes() {
+        let value = INPUT_CHARSET
+            .iter()
+            .position(|&x| x == c)
+            .unwrap_or_default() as u64;
+        symbols.push(value & 31);
+        groups.push(value >> 5);
+        if groups.len() == 3 {
+            symbols.push(groups[0] * 9 + groups[1] * 3 + groups[2]);
+            groups.clear();
+        }
+    }
+    match groups.as_slice() {
+        [a] => symbols.push(*a),
+        [a, b] => symbols.push(a * 3 + b),
+        _ => {}
+    }
+    symbols.extend([0; 8]);
+
+    let checksum = polymod(&symbols) ^ 1;
+    let checksum: String = (0..8)
+        .map(|i| CHECKSUM_CHARSET[((checksum >> (5 * (7 - i))) & 31) as usize] as char)
+        .collect();
+    format!("{}#{}", descriptor, checksum)
+}
+
+fn polymod(symbols: &[u64]) -> u64 {
+    let mut chk: u64 = 1;
+    for &value in symbols {
+        let top = chk >> 35;
+        chk = ((chk & 0x7_ffff_ffff) << 5) ^ value;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(hex_str: &str) -> String {
+        descriptor_for_script(&hex::decode(hex_str).unwrap())
+    }
+
+    #[test]
+    fn test_checksum() {
+        // BIP380 test vector
+        assert_eq!(add_checksum("raw(deadbeef)"), "raw(deadbeef)#89f8spxm");
+        assert_eq!(
+            add_checksum("pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)"),
+            "pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)#gn28ywm7"
+        );
+    }
+
+    #[test]
+    fn test_descriptor_for_script() {
+        assert_eq!(
+            descriptor("001485d78eb795bd9c8a21afefc8b6fdaedf71836809"),
+            "addr(bc1qshtcadu4hkwg5gd0alytdldwmaccx6qfh9fg9u)#04ktdfdp"
+        );
+        assert_eq!(
+            descriptor("210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac"),
+            "pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)#gn28ywm7"
+        );
+        assert_eq!(
+            descriptor(&format!("5120{}", "11".repeat(32))),
+            format!("rawtr({})#kperrpt7", "11".repeat(32))
+        );
+        assert_eq!(
+            descriptor(&format!("5121{}21{}52ae", "02".repeat(33), "03".repeat(33))),
+            format!("multi(1,{},{})#xc0jydyd", "02".repeat(33), "03".repeat(33))
+        );
+        assert_eq!(descriptor("6a0401020304"), "raw(6a0401020304)#g0fz96j4");
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::address::address_on;
+use crate::{le_hex_to_u64, BitcoinTransaction, Network, TxInput};
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+// What differs between two transactions. Inputs are matched by outpoint and
+// outputs by scriptPubKey (in order, when several pay the same script), so
+// a reordering alone isn't reported. Addresses are shown for the new
+// transaction's network.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxDiff {
+    #[serde(skip)]
+    pub network: Network,
+    pub version: Option<Change<i32>>,
+    pub locktime: Option<Change<u32>>,
+    pub inputs: Vec<InputDiff>,
+    pub outputs: Vec<OutputDiff>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum InputDiff {
+    Added {
+        outpoint: String,
+    },
+    Removed {
+        outpoint: String,
+    },
+    Modified {
+        outpoint: String,
+        sequence: Option<Change<u32>>,
+        script_sig: Option<Change<String>>,
+        witness: Option<Change<Vec<String>>>,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum OutputDiff {
+    Added {
+        script_pubkey: String,
+        value_sats: u64,
+    },
+    Removed {
+        script_pubkey: String,
+        value_sats: u64,
+    },
+    Modified {
+        script_pubkey: String,
+        value_sats: Change<u64>,
+    },
+}
+
+impl TxDiff {
+    pub fn is_empty(&self) -> bool {
+        self.version.is_none()
+            && self.locktime.is_none()
+            && self.inputs.is_empty()
+            && self.outputs.is_empty()
+    }
+}
+
+pub fn diff(a: &BitcoinTransaction, b: &BitcoinTransaction) -> TxDiff {
+    let number = |field: &str| le_hex_to_u64(field).unwrap_or_default();
+
+    let version = |tx: &BitcoinTransaction| number(&tx.version) as u32 as i32;
+    let locktime = |tx: &BitcoinTransaction| number(&tx.locktime) as u32;
+
+    TxDiff {
+        network: b.network,
+        version: change(version(a), version(b)),
+        locktime: change(locktime(a), locktime(b)),
+        inputs: diff_inputs(a, b),
+        outputs: diff_outputs(a, b),
+    }
+}
+
+fn change<T: PartialEq>(old: T, new: T) -> Option<Change<T>> {
+    (old != new).then_some(Change { old, new })
+}
+
+fn diff_inputs(a: &BitcoinTransaction, b: &BitcoinTransaction) -> Vec<InputDiff> {
+    let sequence = |input: &TxInput| le_hex_to_u64(&input.sequence).unwrap_or_default() as u32;
+
+    let mut diffs = Vec::new();
+    for (i, old) in a.inputs.iter().enumerate() {
+        let outpoint = outpoint(old);
+        let Some(j) = b
+            .inputs
+            .iter()
+            .position(|new| self::outpoint(new) == outpoint)
+        else {
+            diffs.push(InputDiff::Removed { outpoint });
+            continue;
+        };
+        let new = &b.inputs[j];
+        let sequence = change(sequence(old), sequence(new));
+        let script_sig = change(old.scriptsig.clone(), new.scriptsig.clone());
+        let witness = change(a.witness_items(i), b.witness_items(j));
+        if sequence.is_some() || script_sig.is_some() || witness.is_some() {
+            diffs.push(InputDiff::Modified {
+                outpoint,
+                sequence,
+                script_sig,
+                witness,
+            });
+        }
+    }
+    for new in &b.inputs {
+        let outpoint = outpoint(new);
+        if !a.inputs.iter().any(|old| self::outpoint(old) == outpoint) {
+            diffs.push(InputDiff::Added { outpoint });
+        }
+    }
+    diffs
+}
+
+fn diff_outputs(a: &BitcoinTransaction, b: &BitcoinTransaction) -> Vec<OutputDiff> {
+    let mut unmatched: Vec<_> = b.outputs.iter().map(Some).collect();
+
+    let mut diffs = Vec::new();
+    for old in &a.outputs {
+        let matched = unmatched
+            .iter_mut()
+            .find(|new| new.is_some_and(|new| new.scriptpubkey == old.scriptpubkey))
+            .and_then(Option::take);
+        match matched {
+            Some(new) => {
+                if let Some(value_sats) = change(old.value_sats, new.value_sats) {
+                    diffs.push(OutputDiff::Modified {
+                        script_pubkey: old.scriptpubkey.clone(),
+                        value_sats,
+                    });
+                }
+            }
+            None => diffs.push(OutputDiff::Removed {
+                script_pubkey: old.scriptpubkey.clone(),
+                value_sats: old.value_sats,
+            }),
+        }
+    }
+    for new in unmatched.into_iter().flatten() {
+        diffs.push(OutputDiff::Added {
+            script_pubkey: new.scriptpubkey.clone(),
+            value_sats: new.value_sats,
+        });
+    }
+    diffs
+}
+
+// `txid:vout` with the txid as block explorers show it
+fn outpoint(input: &TxInput) -> String {
+    let mut txid = hex::decode(&input.txid).unwrap_or_default();
+    txid.reverse();
+    format!(
+        "{}:{}",
+        hex::encode(txid),
+        le_hex_to_u64(&input.vout).unwrap_or_default()
+    )
+}
+
+// An output's address, or its script when it has none
+fn destination(script_pubkey: &str, network: Network) -> String {
+    let script = hex::decode(script_pubkey).unwrap_or_default();
+    address_on(&script, network).unwrap_or_else(|| script_pubkey.to_string())
+}
+
+// One line per change: `~` for something changed in place, with `-` and `+`
+// for what was removed and added.
+impl fmt::Display for TxDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        if let Some(Change { old, new }) = &self.version {
+            writeln!(f, "~ version   {} -> {}", old, new)?;
+        }
+        if let Some(Change { old, new }) = &self.locktime {
+            writeln!(f, "~ locktime  {} -> {}", old, new)?;
+        }
+
+        for input in &self.inputs {
+            match input {
+                InputDiff::Added { outpoint } => writeln!(f, "+ input     {}", outpoint)?,
+                InputDiff::Removed { outpoint } => writeln!(f, "- input     {}", outpoint)?,
+                InputDiff::Modified {
+                    outpoint,
+                    sequence,
+                    script_sig,
+                    witness,
+                } => {
+                    writeln!(f, "~ input     {}", outpoint)?;
+                    if let Some(Change { old, new }) = sequence {
+                        writeln!(f, "    sequence   0x{:08x} -> 0x{:08x}", old, new)?;
+                    }
+                    if let Some(Change { old, new }) = script_sig {
+                        writeln!(f, "  - scriptSig  {}", old)?;
+                        writeln!(f, "  + scriptSig  {}", new)?;
+                    }
+                    if let Some(Change { old, new }) = witness {
+                        writeln!(f, "  - witness    [{}]", old.join(", "))?;
+                        writeln!(f, "  + witness    [{}]", new.join(", "))?;
+                    }
+                }
+            }
+        }
+
+        for output in &self.outputs {
+            match output {
+                OutputDiff::Added {
+                    script_pubkey,
+                    value_sats,
+                } => writeln!(
+                    f,
+                    "+ output    {}  {} sats",
+                    destination(script_pubkey, self.network),
+                    value_sats
+                )?,
+                OutputDiff::Removed {
+                    script_pubkey,
+                    value_sats,
+                } => writeln!(
+                    f,
+                    "- output    {}  {} sats",
+                    destination(script_pubkey, self.network),
+                    value_sats
+                )?,
+                OutputDiff::Modified {
+                    script_pubkey,
+                    value_sats: Change { old, new },
+                } => writeln!(
+                    f,
+                    "~ output    {}  {} -> {} sats ({:+})",
+                    destination(script_pubkey, self.network),
+                    old,
+                    new,
+                    *new as i128 - *old as i128
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SAMPLE_TX;
+    use crate::{decode_transaction, WitnessStack};
+
+    const OUTPOINT: &str = "c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131:1";
+
+    #[test]
+    fn test_rbf_replacement() {
+        let original = decode_transaction(SAMPLE_TX).unwrap();
+        let mut replacement = original.clone();
+        // Pay the extra fee out of the second output and add a change output
+        replacement.outputs[1].value_sats -= 2_000;
+        let mut change_output = replacement.outputs[0].clone();
+        change_output.scriptpubkey = format!("0014{}", "22".repeat(20));
+        change_output.value_sats = 1_000;
+        replacement.outputs.push(change_output);
+
+        let diff = diff(&original, &replacement);
+        assert_eq!(diff.version, None);
+        assert!(diff.inputs.is_empty());
+        assert_eq!(
+            diff.outputs,
+            vec![
+                OutputDiff::Modified {
+                    script_pubkey: "0014840ab165c9c2555d4a31b9208ad806f89d2535e2".to_string(),
+                    value_sats: Change {
+                        old: 1_050_700,
+                        new: 1_048_700
+                    }
+                },
+                OutputDiff::Added {
+                    script_pubkey: format!("0014{}", "22".repeat(20)),
+                    value_sats: 1_000
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "\
+~ output    bc1qss9tzewfcf246j33hysg4kqxlzwj2d0zn4wd90  1050700 -> 1048700 sats (-2000)
++ output    bc1qyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zc6v074  1000 sats
+"
+        );
+    }
+
+    #[test]
+    fn test_addresses_follow_network() {
+        let mut original = decode_transaction(SAMPLE_TX).unwrap();
+        let mut replacement = original.clone();
+        replacement.outputs[1].value_sats -= 2_000;
+        original.set_network(Network::Testnet4);
+        replacement.set_network(Network::Testnet4);
+
+        assert_eq!(
+            diff(&original, &replacement).to_string(),
+            "~ output    tb1qss9tzewfcf246j33hysg4kqxlzwj2d0zen477u  1050700 -> 1048700 sats (-2000)\n"
+        );
+    }
+
+    #[test]
+    fn test_signing() {
+        let signed = decode_transaction(SAMPLE_TX).unwrap();
+        let mut unsigned = signed.clone();
+        unsigned.witness = vec![WitnessStack::new(Vec::new())];
+
+        let diff = diff(&unsigned, &signed);
+        assert!(diff.outputs.is_empty());
+        assert_eq!(diff.locktime, None);
+        assert_eq!(
+            diff.inputs,
+            vec![InputDiff::Modified {
+                outpoint: OUTPOINT.to_string(),
+                sequence: None,
+                script_sig: None,
+                witness: Some(Change {
+                    old: Vec::new(),
+                    new: signed.witness_items(0)
+                }),
+            }]
+        );
+        assert!(diff.to_string().starts_with(&format!(
+            "~ input     {}\n  - witness    []\n  + witness    [30440220",
+            OUTPOINT
+        )));
+    }
+
+    #[test]
+    fn test_no_differences() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(diff(&tx, &tx).is_empty());
+        assert_eq!(diff(&tx, &tx).to_string(), "no differences\n");
+    }
+}
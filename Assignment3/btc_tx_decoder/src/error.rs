@@ -0,0 +1,143 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidHex(String),
+    InvalidTransaction(String),
+    InvalidPsbt(String),
+    InputIndexOutOfRange {
+        index: usize,
+        inputs: usize,
+    },
+    MissingAmount,
+    NoMatchingOutput {
+        index: usize,
+    },
+    // The input ended partway through a field
+    UnexpectedEof {
+        needed: usize,
+        available: usize,
+    },
+    Io(String),
+    InvalidJson(String),
+    // A field of a JSON transaction doesn't hold what its wire format needs
+    InvalidField {
+        field: String,
+        reason: String,
+    },
+    // A count or length read from the wire is over the caller's
+    // DecodeLimits
+    LimitExceeded {
+        field: &'static str,
+        count: u64,
+        limit: usize,
+    },
+    // A count read from the wire can't fit in what is left of the input,
+    // even with every element at its smallest
+    CountTooLarge {
+        field: &'static str,
+        count: u64,
+        available: usize,
+    },
+    // Strict mode rejected a signature or key encoding that consensus
+    // tolerates, see signature::EncodingIssue
+    StrictEncoding {
+        input: usize,
+        reason: String,
+    },
+    // Concatenated transactions: `decoded` of them were read before `error`
+    // at byte `offset`
+    PartialDecode {
+        decoded: usize,
+        offset: usize,
+        error: Box<DecodeError>,
+    },
+    // A lenient decode stopped at a truncated script, so the transaction
+    // can't be serialized or hashed
+    Truncated,
+    // An output value, or the outputs' total when `output` is None, is over
+    // MAX_MONEY
+    MoneyRangeError {
+        output: Option<usize>,
+        value: u64,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidHex(e) => write!(f, "Invalid hex: {}", e),
+            DecodeError::InvalidTransaction(e) => write!(f, "Invalid transaction: {}", e),
+            DecodeError::InvalidPsbt(e) => write!(f, "Invalid PSBT: {}", e),
+            DecodeError::InputIndexOutOfRange { index, inputs } => write!(
+                f,
+                "Input index {} out of range (transaction has {} inputs)",
+                index, inputs
+            ),
+            DecodeError::MissingAmount => write!(f, "Segwit signing requires the input amount"),
+            DecodeError::NoMatchingOutput { index } => {
+                write!(f, "SIGHASH_SINGLE input {} has no matching output", index)
+            }
+            DecodeError::UnexpectedEof { needed, available } => write!(
+                f,
+                "Unexpected end of input: needed {} bytes, {} available",
+                needed, available
+            ),
+            DecodeError::Io(e) => write!(f, "I/O error: {}", e),
+            DecodeError::InvalidJson(e) => write!(f, "Invalid transaction JSON: {}", e),
+            DecodeError::InvalidField { field, reason } => write!(f, "{} {}", field, reason),
+            DecodeError::LimitExceeded {
+                field,
+                count,
+                limit,
+            } => write!(f, "{} {} exceeds the limit of {}", field, count, limit),
+            DecodeError::CountTooLarge {
+                field,
+                count,
+                available,
+            } => write!(
+                f,
+                "{} {} can't fit in the {} bytes left",
+                field, count, available
+            ),
+            DecodeError::StrictEncoding { input, reason } => {
+                write!(f, "input {} fails strict encoding: {}", input, reason)
+            }
+            DecodeError::PartialDecode {
+                decoded,
+                offset,
+                error,
+            } => write!(
+                f,
+                "transaction {} at byte {}: {} ({} decoded before it)",
+                decoded + 1,
+                offset,
+                error,
+                decoded
+            ),
+            DecodeError::Truncated => write!(
+                f,
+                "transaction is truncated: a lenient decode stopped partway through it"
+            ),
+            DecodeError::MoneyRangeError {
+                output: Some(index),
+                value,
+            } => write!(f, "output {} value {} exceeds MAX_MONEY", index, value),
+            DecodeError::MoneyRangeError {
+                output: None,
+                value,
+            } => write!(f, "total output value {} exceeds MAX_MONEY", value),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for String {
+    fn from(e: DecodeError) -> String {
+        e.to_string()
+    }
+}
@@ -0,0 +1,498 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::consensus::is_coinbase;
+use crate::input_kind::InputKind;
+use crate::script::{instructions, Instruction};
+use crate::witness_role::WitnessRole;
+use crate::{le_hex_to_u64, BitcoinTransaction};
+
+// Locktimes from here on are unix timestamps, below it block heights
+const LOCKTIME_THRESHOLD: u64 = 500_000_000;
+const SEQUENCE_FINAL: u64 = 0xffffffff;
+// BIP68: bit 31 disables the relative locktime, bit 22 makes it count
+// 512-second units instead of blocks, and the low 16 bits hold the value
+const SEQUENCE_DISABLE_FLAG: u64 = 1 << 31;
+const SEQUENCE_TYPE_FLAG: u64 = 1 << 22;
+const SEQUENCE_MASK: u64 = 0xffff;
+
+// One field of the transaction in plain English. `path` is the field's
+// dotted path as query takes it, `value` the field decoded.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub path: String,
+    pub value: String,
+    pub sentence: String,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}: {}", self.path, self.value, self.sentence)
+    }
+}
+
+// Every field of the transaction, in serialization order, with what it
+// means for this transaction: whether a sequence signals RBF or sets a
+// relative locktime, whether the locktime is a height or a time, and so on.
+pub fn explain(tx: &BitcoinTransaction) -> Vec<Explanation> {
+    let number = |field: &str| le_hex_to_u64(field).unwrap_or_default();
+    let mut out = Vec::new();
+    let mut push = |path: String, value: String, sentence: String| {
+        out.push(Explanation {
+            path,
+            value,
+            sentence,
+        })
+    };
+
+    let version = number(&tx.version) as u32 as i32;
+    push(
+        "version".to_string(),
+        version.to_string(),
+        explain_version(version).to_string(),
+    );
+    if tx.is_segwit() {
+        push(
+            "marker".to_string(),
+            tx.marker.clone(),
+            "segwit marker, so a witness for each input follows the outputs".to_string(),
+        );
+        push(
+            "flag".to_string(),
+            tx.flag.clone(),
+            "segwit flag, always 1 so far".to_string(),
+        );
+    }
+
+    let coinbase = is_coinbase(tx);
+    push(
+        "inputcount".to_string(),
+        tx.inputs.len().to_string(),
+        if coinbase {
+            "a single coinbase input, so this is the first transaction of its block".to_string()
+        } else {
+            format!("spends {}", plural(tx.inputs.len(), "previous output"))
+        },
+    );
+    for (i, input) in tx.inputs.iter().enumerate() {
+        let mut txid = hex::decode(&input.txid).unwrap_or_default();
+        txid.reverse();
+        let vout = number(&input.vout);
+        push(
+            format!("inputs.{}.txid", i),
+            hex::encode(txid),
+            if coinbase {
+                format!(
+                    "input {} spends the null outpoint, claiming the block subsidy and fees instead of a previous output",
+                    i
+                )
+            } else {
+                format!(
+                    "input {} spends output {} of the transaction with this txid",
+                    i, vout
+                )
+            },
+        );
+        push(
+            format!("inputs.{}.scriptsig", i),
+            format!("{} bytes", input.scriptsig.len() / 2),
+            explain_script_sig(tx, i, coinbase),
+        );
+        match le_hex_to_u64(&input.sequence) {
+            Some(sequence) => push(
+                format!("inputs.{}.sequence", i),
+                format!("0x{:08x}", sequence),
+                explain_sequence(sequence, version),
+            ),
+            None => push(
+                format!("inputs.{}.sequence", i),
+                "missing".to_string(),
+                "the scriptSig is truncated, so the sequence after it was never read".to_string(),
+            ),
+        }
+    }
+
+    push(
+        "outputcount".to_string(),
+        tx.outputs.len().to_string(),
+        format!("creates {}", plural(tx.outputs.len(), "output")),
+    );
+    for (i, output) in tx.outputs.iter().enumerate() {
+        let script = hex::decode(&output.scriptpubkey).unwrap_or_default();
+        let script_type = classify_script(&script, &[], &ClassifyOptions::default());
+        let mut sentence = match script_type {
+            ScriptType::NullData => format!(
+                "output {} is an OP_RETURN output carrying {} of data and can never be spent",
+                i,
+                plural(pushed_bytes(&script[1..]), "byte")
+            ),
+            _ => format!(
+                "output {} pays {} sats to {}",
+                i,
+                thousands(output.value_sats),
+                describe_destination(&script_type)
+            ),
+        };
+        if let Some(address) = &output.address {
+            sentence.push_str(&format!(" {}", address));
+        }
+        if output.is_dust {
+            sentence.push_str(", below the dust threshold");
+        }
+        push(
+            format!("outputs.{}.value_sats", i),
+            output.value_sats.to_string(),
+            sentence,
+        );
+    }
+
+    for (i, stack) in tx.witness.iter().enumerate() {
+        if stack.items.is_empty() {
+            if tx.is_segwit() {
+                push(
+                    format!("witness.{}", i),
+                    "0 items".to_string(),
+                    format!("input {} has no witness, it is not a segwit spend", i),
+                );
+            }
+            continue;
+        }
+        let roles: Vec<&str> = stack
+            .items
+            .iter()
+            .map(|item| match item.role {
+                Some(WitnessRole::Signature) => "signature",
+                Some(WitnessRole::Pubkey) => "public key",
+                Some(WitnessRole::WitnessScript) => "witness script",
+                None => "data",
+            })
+            .collect();
+        let kind = match tx.inputs.get(i).map(|input| input.input_kind) {
+            Some(InputKind::NativeSegwitV0) => "a native segwit v0 spend",
+            Some(InputKind::P2shWrappedSegwit) => "a P2SH-wrapped segwit spend",
+            Some(InputKind::Taproot) => "a taproot spend",
+            _ => "a spend of an unrecognized kind",
+        };
+        push(
+            format!("witness.{}", i),
+            plural(stack.items.len(), "item"),
+            format!(
+                "input {} is {}, its witness holding: {}",
+                i,
+                kind,
+                roles.join(", ")
+            ),
+        );
+    }
+
+    if tx.truncated {
+        push(
+            "locktime".to_string(),
+            "missing".to_string(),
+            "the decode stopped at a truncated script, so the fields after it were never read"
+                .to_string(),
+        );
+        return out;
+    }
+    let locktime = number(&tx.locktime);
+    let all_final = tx
+        .inputs
+        .iter()
+        .all(|input| number(&input.sequence) == SEQUENCE_FINAL);
+    let mut sentence = match locktime {
+        0 => "no locktime, valid in any block".to_string(),
+        height if height < LOCKTIME_THRESHOLD => {
+            format!("not valid in a block before height {}", height + 1)
+        }
+        time => format!(
+            "not valid in a block until the median time past is after {}",
+            utc_date(time)
+        ),
+    };
+    if locktime != 0 && all_final {
+        sentence.push_str(", but not enforced since every input's sequence is final");
+    }
+    push("locktime".to_string(), locktime.to_string(), sentence);
+
+    out
+}
+
+fn explain_version(version: i32) -> &'static str {
+    match version {
+        1 => "the original version, BIP68 relative timelocks are not enforced",
+        2 => "this transaction opts into BIP68 relative timelocks",
+        3 => "a TRUC transaction (BIP431): BIP68 relative timelocks, and relayed only in small packages",
+        _ => "a nonstandard version, valid in a block but not relayed",
+    }
+}
+
+fn explain_script_sig(tx: &BitcoinTransaction, input_index: usize, coinbase: bool) -> String {
+    let input = &tx.inputs[input_index];
+    let script_sig = hex::decode(&input.scriptsig).unwrap_or_default();
+    if coinbase {
+        return match coinbase_height(&script_sig) {
+            Some(height) => format!(
+                "coinbase data, starting with the block height {} (BIP34)",
+                height
+            ),
+            None => "coinbase data, free for the miner to fill".to_string(),
+        };
+    }
+    if script_sig.is_empty() {
+        return if tx.witness_items(input_index).is_empty() {
+            "empty, so the spent output needs no unlocking data or the input is unsigned"
+        } else {
+            "empty, the input is unlocked by its witness alone"
+        }
+        .to_string();
+    }
+    if input.input_kind == InputKind::P2shWrappedSegwit {
+        return "pushes the redeem script of a P2SH-wrapped segwit output; the signature is in the witness".to_string();
+    }
+    if let Some(redeem_type) = &input.redeem_script_type {
+        return format!(
+            "unlocks a P2SH output, revealing a {} redeem script",
+            redeem_type.label()
+        );
+    }
+    let pushes: Option<Vec<&[u8]>> = instructions(&script_sig)
+        .map(|instruction| match instruction {
+            Ok((_, Instruction::Push { data, .. })) => Some(data),
+            _ => None,
+        })
+        .collect();
+    match pushes.as_deref() {
+        Some([_, pubkey]) if matches!(pubkey.len(), 33 | 65) => {
+            "a signature and a public key, unlocking a P2PKH output".to_string()
+        }
+        Some([_]) => "a single signature, unlocking a P2PK output".to_string(),
+        _ => "unlocking data for the spent output's script".to_string(),
+    }
+}
+
+fn explain_sequence(sequence: u64, version: i32) -> String {
+    if sequence == SEQUENCE_FINAL {
+        return "final: no RBF signal, and no locktime of either kind".to_string();
+    }
+    let relative = if sequence & SEQUENCE_DISABLE_FLAG != 0 {
+        "relative locktime disabled".to_string()
+    } else if version < 2 {
+        "relative locktime not enforced below version 2".to_string()
+    } else if sequence & SEQUENCE_TYPE_FLAG != 0 {
+        format!(
+            "relative locktime of {} seconds",
+            thousands((sequence & SEQUENCE_MASK) * 512)
+        )
+    } else {
+        format!(
+            "relative locktime of {}",
+            plural((sequence & SEQUENCE_MASK) as usize, "block")
+        )
+    };
+    if sequence == SEQUENCE_FINAL - 1 {
+        format!("no RBF signal, enables the locktime, {}", relative)
+    } else {
+        format!("signals RBF (BIP125), {}", relative)
+    }
+}
+
+fn describe_destination(script_type: &ScriptType) -> String {
+    match script_type {
+        ScriptType::P2pk => "a bare public key (P2PK)".to_string(),
+        ScriptType::P2pkh => "a P2PKH (legacy) address".to_string(),
+        ScriptType::P2sh { .. } => "a P2SH (script hash) address".to_string(),
+        ScriptType::P2wpkh => "a P2WPKH (native segwit v0) address".to_string(),
+        ScriptType::P2wsh { .. } => "a P2WSH (native segwit v0 script hash) address".to_string(),
+        ScriptType::P2tr => "a P2TR (taproot, segwit v1) address".to_string(),
+        ScriptType::Multisig { required, keys } => {
+            format!("a bare {}-of-{} multisig", required, keys)
+        }
+        ScriptType::WitnessUnknown { version } => format!(
+            "a segwit v{} program, which nothing defines yet and anyone can spend",
+            version
+        ),
+        _ => "a nonstandard script".to_string(),
+    }
+}
+
+// The height a BIP34 coinbase starts with: its first push, a little-endian
+// number
+fn coinbase_height(script_sig: &[u8]) -> Option<u64> {
+    match instructions(script_sig).next()? {
+        Ok((_, Instruction::Push { data, .. })) if (1..=8).contains(&data.len()) => Some(
+            data.iter()
+                .rev()
+                .fold(0, |height, &byte| height << 8 | u64::from(byte)),
+        ),
+        _ => None,
+    }
+}
+
+// The data an OP_RETURN script carries, over all its pushes
+fn pushed_bytes(script: &[u8]) -> usize {
+    instructions(script)
+        .map(|instruction| match instruction {
+            Ok((_, Instruction::Push { data, .. })) => data.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+// 1050700 -> "1,050,700"
+fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+// A unix timestamp as "2023-11-14 22:13:20 UTC"
+fn utc_date(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+    // Days since 1970-01-01 to a civil date, counting in 400-year eras from
+    // 0000-03-01 so leap days fall at the end of each year
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+    use crate::decode_transaction;
+    use crate::script::encode_push;
+    use crate::tests::SAMPLE_TX;
+
+    fn text(tx: &BitcoinTransaction) -> String {
+        explain(tx)
+            .iter()
+            .map(|explanation| format!("{}\n", explanation))
+            .collect()
+    }
+
+    #[test]
+    fn test_explain_sample() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let expected = "\
+version = 2: this transaction opts into BIP68 relative timelocks
+marker = 00: segwit marker, so a witness for each input follows the outputs
+flag = 01: segwit flag, always 1 so far
+inputcount = 1: spends 1 previous output
+inputs.0.txid = c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131: input 0 spends output 1 of the transaction with this txid
+inputs.0.scriptsig = 0 bytes: empty, the input is unlocked by its witness alone
+inputs.0.sequence = 0xfffffffd: signals RBF (BIP125), relative locktime disabled
+outputcount = 2: creates 2 outputs
+outputs.0.value_sats = 500000: output 0 pays 500,000 sats to a P2WPKH (native segwit v0) address bc1qshtcadu4hkwg5gd0alytdldwmaccx6qfh9fg9u
+outputs.1.value_sats = 1050700: output 1 pays 1,050,700 sats to a P2WPKH (native segwit v0) address bc1qss9tzewfcf246j33hysg4kqxlzwj2d0zn4wd90
+witness.0 = 2 items: input 0 is a native segwit v0 spend, its witness holding: signature, public key
+locktime = 918339: not valid in a block before height 918340
+";
+        assert_eq!(text(&tx), expected);
+    }
+
+    #[test]
+    fn test_explain_legacy() {
+        let p2pkh = [&[0x76, 0xa9, 0x14][..], &[0x11; 20], &[0x88, 0xac]].concat();
+        let script_sig = [&encode_push(&[0x30; 72])[..], &encode_push(&[0x02; 33])].concat();
+        let hex = TxBuilder::new()
+            .version(1)
+            .add_input(&"ab".repeat(32), 3)
+            .script_sig(&script_sig)
+            .sequence(0xfffffffe)
+            .add_input(&"cd".repeat(32), 0)
+            .script_sig(&script_sig)
+            .sequence(0x10)
+            .add_output_sats(25_000, &p2pkh)
+            .add_output_sats(0, &[0x6a, 0x04, 0xde, 0xad, 0xbe, 0xef])
+            .locktime(1_700_000_000)
+            .build_hex()
+            .unwrap();
+        let tx = decode_transaction(&hex).unwrap();
+        let expected = "\
+version = 1: the original version, BIP68 relative timelocks are not enforced
+inputcount = 2: spends 2 previous outputs
+inputs.0.txid = abababababababababababababababababababababababababababababababab: input 0 spends output 3 of the transaction with this txid
+inputs.0.scriptsig = 107 bytes: a signature and a public key, unlocking a P2PKH output
+inputs.0.sequence = 0xfffffffe: no RBF signal, enables the locktime, relative locktime disabled
+inputs.1.txid = cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd: input 1 spends output 0 of the transaction with this txid
+inputs.1.scriptsig = 107 bytes: a signature and a public key, unlocking a P2PKH output
+inputs.1.sequence = 0x00000010: signals RBF (BIP125), relative locktime not enforced below version 2
+outputcount = 2: creates 2 outputs
+outputs.0.value_sats = 25000: output 0 pays 25,000 sats to a P2PKH (legacy) address 12ZEw5Hcv1hTb6YUQJ69y1V7uhcoDz92PH
+outputs.1.value_sats = 0: output 1 is an OP_RETURN output carrying 4 bytes of data and can never be spent
+locktime = 1700000000: not valid in a block until the median time past is after 2023-11-14 22:13:20 UTC
+";
+        assert_eq!(text(&tx), expected);
+    }
+
+    #[test]
+    fn test_explain_coinbase_and_sequences() {
+        // BIP34 height 199999 (0x030d3f) then miner data
+        let hex = TxBuilder::new()
+            .add_input(&"00".repeat(32), 0xffffffff)
+            .script_sig(&[0x03, 0x3f, 0x0d, 0x03, 0x2f, 0x50])
+            .add_output_sats(625_000_000, &[&[0x51, 0x20][..], &[0x07; 32]].concat())
+            .locktime(100)
+            .build_hex()
+            .unwrap();
+        let tx = decode_transaction(&hex).unwrap();
+        let sentences: Vec<String> = explain(&tx).into_iter().map(|e| e.sentence).collect();
+        assert_eq!(
+            sentences[1],
+            "a single coinbase input, so this is the first transaction of its block"
+        );
+        assert_eq!(
+            sentences[2],
+            "input 0 spends the null outpoint, claiming the block subsidy and fees instead of a previous output"
+        );
+        assert_eq!(
+            sentences[3],
+            "coinbase data, starting with the block height 199999 (BIP34)"
+        );
+        assert_eq!(
+            sentences[4],
+            "final: no RBF signal, and no locktime of either kind"
+        );
+        assert_eq!(
+            sentences.last().unwrap(),
+            "not valid in a block before height 101, but not enforced since every input's sequence is final"
+        );
+
+        assert_eq!(
+            explain_sequence(144, 2),
+            "signals RBF (BIP125), relative locktime of 144 blocks"
+        );
+        assert_eq!(
+            explain_sequence(SEQUENCE_TYPE_FLAG | 10, 2),
+            "signals RBF (BIP125), relative locktime of 5,120 seconds"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use crate::BitcoinTransaction;
+
+// The transaction as dotted keys for text templating: `version`,
+// `input.0.txid`, `input.0.witness.1`, `output.1.value_btc`, `txid`, ...
+pub fn to_flat_map(tx: &BitcoinTransaction) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    let mut put = |key: String, value: String| {
+        map.insert(key, value);
+    };
+
+    if let Ok(txid) = tx.txid() {
+        put("txid".to_string(), txid);
+    }
+    put("version".to_string(), tx.version.clone());
+    if tx.is_segwit() {
+        put("marker".to_string(), tx.marker.clone());
+        put("flag".to_string(), tx.flag.clone());
+    }
+    put("inputcount".to_string(), tx.inputcount.clone());
+    put("outputcount".to_string(), tx.outputcount.clone());
+    put("locktime".to_string(), tx.locktime.clone());
+
+    for (i, input) in tx.iter_inputs().enumerate() {
+        put(format!("input.{}.txid", i), input.txid.clone());
+        put(format!("input.{}.vout", i), input.vout.clone());
+        put(
+            format!("input.{}.scriptsigsize", i),
+            input.scriptsigsize.clone(),
+        );
+        put(format!("input.{}.scriptsig", i), input.scriptsig.clone());
+        put(format!("input.{}.sequence", i), input.sequence.clone());
+        for (j, item) in tx.witness_items(i).into_iter().enumerate() {
+            put(format!("input.{}.witness.{}", i, j), item);
+        }
+    }
+
+    for (i, output) in tx.iter_outputs().enumerate() {
+        put(format!("output.{}.amount", i), output.amount.clone());
+        put(
+            format!("output.{}.value_sats", i),
+            output.value_sats.to_string(),
+        );
+        put(
+            format!("output.{}.value_btc", i),
+            format!(
+                "{}.{:08}",
+                output.value_sats / 100_000_000,
+                output.value_sats % 100_000_000
+            ),
+        );
+        put(
+            format!("output.{}.scriptpubkeysize", i),
+            output.scriptpubkeysize.clone(),
+        );
+        put(
+            format!("output.{}.scriptpubkey", i),
+            output.scriptpubkey.clone(),
+        );
+        put(format!("output.{}.is_dust", i), output.is_dust.to_string());
+    }
+
+    for (i, warning) in tx.warnings.iter().enumerate() {
+        put(format!("warning.{}", i), warning.clone());
+    }
+    for (i, hint) in tx.hints.iter().enumerate() {
+        put(format!("hint.{}", i), hint.clone());
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_flat_map_sample() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let map = to_flat_map(&tx);
+
+        assert_eq!(
+            map["txid"],
+            "04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842"
+        );
+        assert_eq!(map["output.0.value_btc"], "0.00500000");
+        assert_eq!(map["output.1.value_btc"], "0.01050700");
+        assert_eq!(map["input.0.vout"], "01000000");
+        assert_eq!(map["input.0.witness.1"].len(), 66);
+        assert!(!map.contains_key("warning.0"));
+    }
+}
@@ -0,0 +1,24 @@
+use sha2::{Digest, Sha256};
+
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+pub(crate) fn double_sha256(data: &[u8]) -> [u8; 32] {
+    sha256(&sha256(data))
+}
+
+// RIPEMD160(SHA256(data)), the P2SH/P2PKH commitment
+pub(crate) fn hash160(data: &[u8]) -> [u8; 20] {
+    ripemd::Ripemd160::digest(sha256(data)).into()
+}
+
+// BIP340 tagged hash: SHA256(SHA256(tag) || SHA256(tag) || data)
+pub(crate) fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag = sha256(tag.as_bytes());
+    let mut engine = Sha256::new();
+    engine.update(tag);
+    engine.update(tag);
+    engine.update(data);
+    engine.finalize().into()
+}
@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::hashes::{hash160, sha256};
+use crate::script::{
+    instructions, is_witness_program, to_asm, Instruction, OP_CHECKLOCKTIMEVERIFY,
+    OP_CHECKMULTISIG, OP_CHECKMULTISIGVERIFY, OP_CHECKSEQUENCEVERIFY, OP_DUP, OP_EQUAL,
+    OP_EQUALVERIFY, OP_HASH256, OP_IF, OP_NOTIF, OP_RIPEMD160,
+};
+use crate::signature::parse_der_signature;
+use crate::BitcoinTransaction;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InnerScriptSource {
+    // Last push of a P2SH scriptSig
+    RedeemScript,
+    // Last witness item of a P2WSH spend
+    WitnessScript,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InnerScript {
+    pub source: InnerScriptSource,
+    pub hex: String,
+    pub asm: String,
+    pub classification: ScriptType,
+    // Spending conditions spotted in the script: multisig, cltv, csv,
+    // hashlock, htlc
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    // The witness script under a P2SH-P2WSH redeem script
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inner_script: Option<Box<InnerScript>>,
+}
+
+// The redeem or witness script revealed by an input. With the prevout
+// scriptPubKey the script is checked against its P2SH/P2WSH hash; without it
+// the last push or witness item is used when it parses as a script and isn't
+// shaped like a signature, public key or taproot control block.
+pub fn inner_script(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_pubkey: Option<&[u8]>,
+) -> Option<InnerScript> {
+    let input = tx.inputs.get(input_index)?;
+    let script_sig = hex::decode(&input.scriptsig).ok()?;
+    let witness: Vec<Vec<u8>> = tx
+        .witness_items(input_index)
+        .iter()
+        .filter_map(|item| hex::decode(item).ok())
+        .collect();
+
+    let classified = script_pubkey.map(|script| {
+        (
+            script,
+            classify_script(script, &[], &ClassifyOptions::default()),
+        )
+    });
+    match classified {
+        Some((script_pubkey, ScriptType::P2sh { .. })) => {
+            let redeem = last_push(&script_sig)?;
+            (hash160(&redeem) == script_pubkey[2..22]).then(|| redeem_script(&redeem, &witness))
+        }
+        Some((script_pubkey, ScriptType::P2wsh { .. })) => {
+            let script = witness.last()?;
+            (sha256(script) == script_pubkey[2..])
+                .then(|| describe(InnerScriptSource::WitnessScript, script))
+        }
+        Some(_) => None,
+        None => {
+            if let Some(redeem) = last_push(&script_sig).filter(|s| looks_like_script(s)) {
+                return Some(redeem_script(&redeem, &witness));
+            }
+            let script = witness.last()?;
+            (script_sig.is_empty() && looks_like_script(script))
+                .then(|| describe(InnerScriptSource::WitnessScript, script))
+        }
+    }
+}
+
+// A P2SH redeem script, following a P2SH-P2WSH program down to the witness
+// script it commits to.
+fn redeem_script(redeem: &[u8], witness: &[Vec<u8>]) -> InnerScript {
+    let mut inner = describe(InnerScriptSource::RedeemScript, redeem);
+    if let [0x00, 0x20, hash @ ..] = redeem {
+        inner.inner_script = witness
+            .last()
+            .filter(|script| sha256(script) == hash)
+            .map(|script| Box::new(describe(InnerScriptSource::WitnessScript, script)));
+    }
+    inner
+}
+
+fn describe(source: InnerScriptSource, script: &[u8]) -> InnerScript {
+    InnerScript {
+        source,
+        hex: hex::encode(script),
+        asm: to_asm(script),
+        classification: classify_script(script, &[], &ClassifyOptions::default()),
+        patterns: patterns(script),
+        inner_script: None,
+    }
+}
+
+// The redeem script of a P2SH spend: the last push of a push-only
+// scriptSig, when it parses as a script rather than a signature or key.
+pub fn extract_redeem_script(script_sig_hex: &str) -> Option<String> {
+    let script_sig = hex::decode(script_sig_hex).ok()?;
+    last_push(&script_sig)
+        .filter(|redeem| looks_like_script(redeem))
+        .map(hex::encode)
+}
+
+// Classifies a redeem script, with the input's last witness item as the
+// witness script under P2SH-P2WSH
+pub(crate) fn classify_redeem_script(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    redeem_hex: &str,
+) -> ScriptType {
+    let redeem = hex::decode(redeem_hex).unwrap_or_default();
+    let revealed: Vec<Vec<u8>> = tx
+        .witness_items(input_index)
+        .last()
+        .and_then(|script| hex::decode(script).ok())
+        .into_iter()
+        .collect();
+    classify_script(&redeem, &revealed, &ClassifyOptions::default())
+}
+
+// Last push of a push-only scriptSig
+pub(crate) fn last_push(script_sig: &[u8]) -> Option<Vec<u8>> {
+    let mut last = None;
+    for instruction in instructions(script_sig) {
+        match instruction.ok()? {
+            (_, Instruction::Push { data, .. }) => last = Some(data),
+            (_, op) if op.is_push() => last = None,
+            _ => return None,
+        }
+    }
+    last.filter(|data| !data.is_empty()).map(<[u8]>::to_vec)
+}
+
+pub(crate) fn looks_like_script(data: &[u8]) -> bool {
+    let pubkey = matches!(data, [0x02 | 0x03, ..] if data.len() == 33)
+        || matches!(data, [0x04, ..] if data.len() == 65);
+    let control_block =
+        data.len() >= 33 && (data.len() - 33).is_multiple_of(32) && data[0] & 0xfe == 0xc0;
+    if data.is_empty() || pubkey || control_block || parse_der_signature(data).is_some() {
+        return false;
+    }
+
+    let Ok(parsed) = instructions(data).collect::<Result<Vec<_>, _>>() else {
+        return false;
+    };
+    is_witness_program(data) || parsed.iter().any(|(_, instruction)| !instruction.is_push())
+}
+
+fn patterns(script: &[u8]) -> Vec<String> {
+    let ops: Vec<Instruction> = instructions(script)
+        .map_while(Result::ok)
+        .map(|(_, instruction)| instruction)
+        .collect();
+    let has = |opcodes: &[u8]| {
+        ops.iter()
+            .any(|op| matches!(op, Instruction::Op(o) if opcodes.contains(o)))
+    };
+
+    // <hash op> <digest> OP_EQUAL[VERIFY], not preceded by OP_DUP (that's a
+    // public key hash check)
+    let hashlock = ops.windows(3).enumerate().any(|(i, window)| {
+        matches!(
+            window,
+            [
+                Instruction::Op(OP_RIPEMD160..=OP_HASH256),
+                Instruction::Push { .. },
+                Instruction::Op(OP_EQUAL | OP_EQUALVERIFY)
+            ]
+        ) && (i == 0 || ops[i - 1] != Instruction::Op(OP_DUP))
+    });
+    let cltv = has(&[OP_CHECKLOCKTIMEVERIFY]);
+    let csv = has(&[OP_CHECKSEQUENCEVERIFY]);
+
+    let mut found = Vec::new();
+    if has(&[OP_CHECKMULTISIG, OP_CHECKMULTISIGVERIFY]) {
+        found.push("multisig");
+    }
+    if cltv {
+        found.push("cltv");
+    }
+    if csv {
+        found.push("csv");
+    }
+    if hashlock {
+        found.push("hashlock");
+    }
+    if hashlock && (cltv || csv) && has(&[OP_IF, OP_NOTIF]) {
+        found.push("htlc");
+    }
+    found.into_iter().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    // P2SH-P2WPKH spend: scriptSig pushes the 0014{keyhash} redeem script
+    const P2SH_P2WPKH_TX: &str = "01000000000101db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a5477010000001716001479091972186c449eb1ded22b78e40d009bdf0089feffffff01b8b4eb0b000000001976a914a457b684d7f0d539a46a45bbc043f35b59d0d96388ac0247304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d09012103ad1d8e89212f0b92c74d23bb710c00662ad1470198ac48c43f7d6f93a2a2687392040000";
+    const P2SH_P2WPKH_PREVOUT: &str = "a9144733f37cf4db86fbc2efed2500b4f4e49f31202387";
+
+    // P2WSH 2-of-2 spend: OP_0 <sig> <sig> <witness script>
+    const P2WSH_TX: &str = "01000000000101db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a54770100000000ffffffff01b8b4eb0b000000001976a914a457b684d7f0d539a46a45bbc043f35b59d0d96388ac040047304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d090147304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901475221026dccc749adc2a9d0d89497ac511f760f45c47dc5ed9cf352a58ac706453880ae2103ad1d8e89212f0b92c74d23bb710c00662ad1470198ac48c43f7d6f93a2a2687352ae00000000";
+    const P2WSH_WITNESS_SCRIPT: &str = "5221026dccc749adc2a9d0d89497ac511f760f45c47dc5ed9cf352a58ac706453880ae2103ad1d8e89212f0b92c74d23bb710c00662ad1470198ac48c43f7d6f93a2a2687352ae";
+
+    #[test]
+    fn test_inner_script_p2sh_p2wpkh() {
+        let tx = decode_transaction(P2SH_P2WPKH_TX).unwrap();
+        let inner = tx.inputs[0].inner_script.as_ref().unwrap();
+        assert_eq!(inner.source, InnerScriptSource::RedeemScript);
+        assert_eq!(inner.classification, ScriptType::P2wpkh);
+        assert_eq!(inner.asm, "0 79091972186c449eb1ded22b78e40d009bdf0089");
+        assert!(inner.inner_script.is_none());
+
+        let prevout = hex::decode(P2SH_P2WPKH_PREVOUT).unwrap();
+        assert_eq!(inner_script(&tx, 0, Some(&prevout)).as_ref(), Some(inner));
+        // Wrong hash: the prevout doesn't commit to this redeem script
+        let other = hex::decode(format!("a914{}87", "00".repeat(20))).unwrap();
+        assert_eq!(inner_script(&tx, 0, Some(&other)), None);
+    }
+
+    #[test]
+    fn test_redeem_script_p2sh_p2wpkh() {
+        let tx = decode_transaction(P2SH_P2WPKH_TX).unwrap();
+        let input = &tx.inputs[0];
+        assert_eq!(
+            input.redeem_script.as_deref(),
+            Some("001479091972186c449eb1ded22b78e40d009bdf0089")
+        );
+        assert_eq!(input.redeem_script_type, Some(ScriptType::P2wpkh));
+
+        // P2PKH: the last push is a public key, not a redeem script
+        let p2pkh_sig = format!("47{}21{}", "30".repeat(71), "02".repeat(33));
+        assert_eq!(extract_redeem_script(&p2pkh_sig), None);
+        assert_eq!(extract_redeem_script(""), None);
+
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(tx.inputs[0].redeem_script, None);
+        assert_eq!(tx.inputs[0].redeem_script_type, None);
+    }
+
+    #[test]
+    fn test_inner_script_p2wsh_multisig() {
+        let tx = decode_transaction(P2WSH_TX).unwrap();
+        let inner = tx.inputs[0].inner_script.as_ref().unwrap();
+        assert_eq!(inner.source, InnerScriptSource::WitnessScript);
+        assert_eq!(inner.hex, P2WSH_WITNESS_SCRIPT);
+        assert_eq!(
+            inner.classification,
+            ScriptType::Multisig {
+                required: 2,
+                keys: 2
+            }
+        );
+        assert_eq!(inner.patterns, vec!["multisig"]);
+        assert!(inner.asm.ends_with(" 2 OP_CHECKMULTISIG"));
+
+        let script = hex::decode(P2WSH_WITNESS_SCRIPT).unwrap();
+        let prevout = [&[0x00, 0x20][..], &sha256(&script)].concat();
+        assert_eq!(inner_script(&tx, 0, Some(&prevout)).as_ref(), Some(inner));
+    }
+
+    #[test]
+    fn test_inner_script_p2sh_p2wsh() {
+        let mut tx = decode_transaction(P2WSH_TX).unwrap();
+        let script = hex::decode(P2WSH_WITNESS_SCRIPT).unwrap();
+        let program = [&[0x00, 0x20][..], &sha256(&script)].concat();
+        tx.inputs[0].scriptsig = format!("22{}", hex::encode(&program));
+
+        let inner = inner_script(&tx, 0, None).unwrap();
+        assert_eq!(inner.source, InnerScriptSource::RedeemScript);
+        let witness_script = inner.inner_script.unwrap();
+        assert_eq!(witness_script.hex, P2WSH_WITNESS_SCRIPT);
+    }
+
+    #[test]
+    fn test_inner_script_none_for_p2wpkh() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(tx.inputs[0].inner_script, None);
+    }
+
+    #[test]
+    fn test_patterns_htlc() {
+        // OP_IF OP_SHA256 <32> OP_EQUALVERIFY <key> OP_ELSE <locktime> OP_CLTV
+        // OP_DROP <key> OP_ENDIF OP_CHECKSIG
+        let key = format!("21{}", "02".repeat(33));
+        let script = hex::decode(format!(
+            "63a820{}88{}6703a08601b175{}68ac",
+            "11".repeat(32),
+            key,
+            key
+        ))
+        .unwrap();
+        assert_eq!(patterns(&script), vec!["cltv", "hashlock", "htlc"]);
+
+        let p2pkh = hex::decode("76a91485d78eb795bd9c8a21afefc8b6fdaedf7183680988ac").unwrap();
+        assert!(patterns(&p2pkh).is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::inner::last_push;
+use crate::script::is_witness_program;
+use crate::taproot::{parse_taproot_witness, TaprootSpend};
+use crate::witness::Witness;
+use crate::BitcoinTransaction;
+
+const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum InputKind {
+    // No witness
+    Legacy,
+    // A scriptSig pushing a witness program, plus a witness
+    P2shWrappedSegwit,
+    // Empty scriptSig, P2WPKH or P2WSH witness
+    NativeSegwitV0,
+    // Empty scriptSig, key path or tapscript witness
+    Taproot,
+    // A witness that fits none of the above, or a future witness version
+    #[default]
+    Unknown,
+}
+
+// What kind of spend an input is, and its witness version when that can be
+// told. With the prevout scriptPubKey the version is read from it (or from
+// the redeem script for P2SH); without it, a native spend's version is
+// guessed from the shape of its witness.
+pub fn input_kind(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_pubkey: Option<&[u8]>,
+) -> (InputKind, Option<u8>) {
+    let Some(input) = tx.inputs.get(input_index) else {
+        return (InputKind::Unknown, None);
+    };
+    let script_sig = hex::decode(&input.scriptsig).unwrap_or_default();
+    let witness = Witness::from_tx(tx, input_index);
+
+    let redeem_version = || last_push(&script_sig).and_then(|redeem| witness_version(&redeem));
+    if let Some(script_pubkey) = script_pubkey {
+        let script_type = classify_script(script_pubkey, &[], &ClassifyOptions::default());
+        return match (witness_version(script_pubkey), script_type) {
+            (Some(version), _) => (native_kind(version), Some(version)),
+            (None, ScriptType::P2sh { .. }) => match redeem_version() {
+                Some(version) => (InputKind::P2shWrappedSegwit, Some(version)),
+                None => (InputKind::Legacy, None),
+            },
+            (None, _) => (InputKind::Legacy, None),
+        };
+    }
+
+    if witness.is_empty() {
+        return (InputKind::Legacy, None);
+    }
+    if !script_sig.is_empty() {
+        return match redeem_version() {
+            Some(version) => (InputKind::P2shWrappedSegwit, Some(version)),
+            None => (InputKind::Unknown, None),
+        };
+    }
+
+    match witness.items() {
+        // P2WPKH: <signature> <compressed pubkey>
+        [_, key] if key.len() == 33 && matches!(key[0], 0x02 | 0x03) => {
+            (InputKind::NativeSegwitV0, Some(0))
+        }
+        _ => match parse_taproot_witness(&witness) {
+            TaprootSpend::KeyPath { signature, .. } if matches!(signature.len(), 128 | 130) => {
+                (InputKind::Taproot, Some(1))
+            }
+            TaprootSpend::ScriptPath { control_block, .. }
+                if control_block.leaf_version == TAPSCRIPT_LEAF_VERSION =>
+            {
+                (InputKind::Taproot, Some(1))
+            }
+            // P2WSH: anything ending in a witness script
+            _ if witness.len() > 1 => (InputKind::NativeSegwitV0, Some(0)),
+            _ => (InputKind::Unknown, None),
+        },
+    }
+}
+
+// How many inputs there are of each kind
+pub fn input_kind_counts(tx: &BitcoinTransaction) -> BTreeMap<InputKind, usize> {
+    let mut counts = BTreeMap::new();
+    for input in &tx.inputs {
+        *counts.entry(input.input_kind).or_default() += 1;
+    }
+    counts
+}
+
+fn witness_version(script: &[u8]) -> Option<u8> {
+    if !is_witness_program(script) {
+        return None;
+    }
+    match script[0] {
+        0x00 => Some(0),
+        op => Some(op - 0x50),
+    }
+}
+
+fn native_kind(version: u8) -> InputKind {
+    match version {
+        0 => InputKind::NativeSegwitV0,
+        1 => InputKind::Taproot,
+        _ => InputKind::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_legacy() {
+        // Block 170
+        let tx = decode_transaction("0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000").unwrap();
+        assert_eq!(tx.inputs[0].input_kind, InputKind::Legacy);
+        assert_eq!(tx.inputs[0].witness_version, None);
+        assert_eq!(
+            tx.input_kind_counts,
+            BTreeMap::from([(InputKind::Legacy, 1)])
+        );
+    }
+
+    #[test]
+    fn test_native_segwit() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(tx.inputs[0].input_kind, InputKind::NativeSegwitV0);
+        assert_eq!(tx.inputs[0].witness_version, Some(0));
+
+        // The prevout says otherwise
+        let p2tr = hex::decode(format!("5120{}", "11".repeat(32))).unwrap();
+        assert_eq!(
+            input_kind(&tx, 0, Some(&p2tr)),
+            (InputKind::Taproot, Some(1))
+        );
+    }
+
+    #[test]
+    fn test_nested_segwit() {
+        // P2SH-P2WPKH
+        let tx = decode_transaction("0200000000010166c3d39490dc827a2594c7b17b7d37445e1f4b372179649cd2ce4475e3641bbb0100000017160014e69aa750e9bff1aca1e32e57328b641b611fc817fdffffff01e87c5d010000000017a914f3890da1b99e44cd3d52f7bcea6a1351658ea7be87024830450221009eb97597953dc288de30060ba02d4e91b2bde1af2ecf679c7f5ab5989549aa8002202a98f8c3bd1a5a31c0d72950dd6e2e3870c6c5819a6c3db740e91ebbbc5ef4800121023f3d3b8e74b807e32217dea2c75c8d0bd46b8665b3a2d9b3cb310959de52a09bc9d20700").unwrap();
+        assert_eq!(tx.inputs[0].input_kind, InputKind::P2shWrappedSegwit);
+        assert_eq!(tx.inputs[0].witness_version, Some(0));
+
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["inputs"][0]["input_kind"], "p2sh-wrapped-segwit");
+        assert_eq!(json["input_kind_counts"]["p2sh-wrapped-segwit"], 1);
+    }
+
+    #[test]
+    fn test_taproot_key_path_shape() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.witness[0] = crate::WitnessStack::new(vec![crate::WitnessItem::new(&[0x11; 64])]);
+        assert_eq!(input_kind(&tx, 0, None), (InputKind::Taproot, Some(1)));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::error::DecodeError;
+use crate::{
+    encode_transaction, parse_transaction_traced, BitcoinTransaction, CompactSize, DecodeLimits,
+};
+
+impl BitcoinTransaction {
+    // Reads back the decoder's JSON, with the witness in either shape. Every
+    // raw hex field is checked against its wire format first, so what comes
+    // back always re-encodes; derived fields (value_sats, descriptors, input
+    // kinds, warnings, ...) are then recomputed from the raw ones rather
+    // than trusted, for the network the JSON names.
+    pub fn from_json(json: &str) -> Result<Self, DecodeError> {
+        let tx: BitcoinTransaction =
+            serde_json::from_str(json).map_err(|e| DecodeError::InvalidJson(e.to_string()))?;
+        check_fields(&tx)?;
+
+        let bytes = hex::decode(encode_transaction(&tx)?)
+            .map_err(|e| DecodeError::InvalidHex(e.to_string()))?;
+        let mut parsed =
+            parse_transaction_traced(&bytes, &DecodeLimits::default(), &mut Default::default())?;
+        parsed.set_network(tx.network);
+        Ok(parsed)
+    }
+}
+
+fn check_fields(tx: &BitcoinTransaction) -> Result<(), DecodeError> {
+    fixed("version", &tx.version, 4)?;
+    fixed("locktime", &tx.locktime, 4)?;
+    match (tx.marker.as_str(), tx.flag.as_str()) {
+        ("", "") => {}
+        ("00", flag) => {
+            if fixed("flag", flag, 1)? == [0] {
+                return Err(invalid("flag", "must be nonzero".to_string()));
+            }
+        }
+        _ => return Err(invalid("marker", "must be 00 or empty".to_string())),
+    }
+
+    count("inputcount", &tx.inputcount, tx.inputs.len())?;
+    for (i, input) in tx.inputs.iter().enumerate() {
+        fixed(&format!("inputs[{}].txid", i), &input.txid, 32)?;
+        fixed(&format!("inputs[{}].vout", i), &input.vout, 4)?;
+        script(
+            &format!("inputs[{}].scriptsig", i),
+            &input.scriptsigsize,
+            &input.scriptsig,
+        )?;
+        fixed(&format!("inputs[{}].sequence", i), &input.sequence, 4)?;
+    }
+
+    count("outputcount", &tx.outputcount, tx.outputs.len())?;
+    for (i, output) in tx.outputs.iter().enumerate() {
+        fixed(&format!("outputs[{}].amount", i), &output.amount, 8)?;
+        script(
+            &format!("outputs[{}].scriptpubkey", i),
+            &output.scriptpubkeysize,
+            &output.scriptpubkey,
+        )?;
+    }
+
+    if !tx.is_segwit() {
+        if !tx.witness.is_empty() {
+            return Err(invalid(
+                "witness",
+                "present without the segwit marker and flag".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+    if tx.witness.len() != tx.inputs.len() {
+        return Err(invalid(
+            "witness",
+            format!("{} stacks for {} inputs", tx.witness.len(), tx.inputs.len()),
+        ));
+    }
+    for (i, stack) in tx.witness.iter().enumerate() {
+        count(
+            &format!("witness[{}].stackitems", i),
+            &stack.stackitems,
+            stack.items.len(),
+        )?;
+        for (j, item) in stack.items.iter().enumerate() {
+            script(
+                &format!("witness[{}][{}].item", i, j),
+                &item.size,
+                &item.item,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn invalid(field: &str, reason: String) -> DecodeError {
+    DecodeError::InvalidField {
+        field: field.to_string(),
+        reason,
+    }
+}
+
+fn bytes(field: &str, value: &str) -> Result<Vec<u8>, DecodeError> {
+    hex::decode(value).map_err(|e| invalid(field, format!("invalid hex: {}", e)))
+}
+
+// Hex of exactly `len` bytes
+fn fixed(field: &str, value: &str, len: usize) -> Result<Vec<u8>, DecodeError> {
+    let decoded = bytes(field, value)?;
+    if decoded.len() != len {
+        return Err(invalid(
+            field,
+            format!("is {} bytes, must be {}", decoded.len(), len),
+        ));
+    }
+    Ok(decoded)
+}
+
+// A complete CompactSize and nothing after it
+fn compact_size(field: &str, value: &str) -> Result<u64, DecodeError> {
+    let decoded = bytes(field, value)?;
+    match CompactSize::read(&decoded, 0) {
+        Ok((size, used)) if used == decoded.len() => Ok(size.value),
+        _ => Err(invalid(field, format!("{:?} is not a CompactSize", value))),
+    }
+}
+
+fn count(field: &str, value: &str, actual: usize) -> Result<(), DecodeError> {
+    let declared = compact_size(field, value)?;
+    if declared != actual as u64 {
+        return Err(invalid(
+            field,
+            format!("says {} but there are {}", declared, actual),
+        ));
+    }
+    Ok(())
+}
+
+// A script (or witness item) and the size field before it
+fn script(field: &str, size: &str, value: &str) -> Result<(), DecodeError> {
+    let declared = compact_size(&format!("{} size", field), size)?;
+    let decoded = bytes(field, value)?;
+    if declared != decoded.len() as u64 {
+        return Err(invalid(
+            field,
+            format!("is {} bytes, its size says {}", decoded.len(), declared),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SAMPLE_TX;
+    use crate::{btc_tx_decoder, decode_transaction, decode_transaction_for_network, Network};
+
+    #[test]
+    fn test_round_trip() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        for json in [
+            btc_tx_decoder(SAMPLE_TX).unwrap(),
+            serde_json::to_string(&tx).unwrap(),
+        ] {
+            let parsed = BitcoinTransaction::from_json(&json).unwrap();
+            assert_eq!(parsed, tx);
+            assert_eq!(encode_transaction(&parsed).unwrap(), SAMPLE_TX);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_network() {
+        let tx = decode_transaction_for_network(SAMPLE_TX, Network::Testnet4).unwrap();
+        let parsed = BitcoinTransaction::from_json(&serde_json::to_string(&tx).unwrap()).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.network, Network::Testnet4);
+        assert!(parsed.outputs[0]
+            .address
+            .as_deref()
+            .is_some_and(|address| address.starts_with("tb1q")));
+    }
+
+    #[test]
+    fn test_derived_fields_recomputed() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.outputs[0].amount = hex::encode(1_000u64.to_le_bytes());
+        let parsed = BitcoinTransaction::from_json(&serde_json::to_string(&tx).unwrap()).unwrap();
+        assert_eq!(parsed.outputs[0].value_sats, 1_000);
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        let json = |edit: fn(&mut BitcoinTransaction)| {
+            let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+            edit(&mut tx);
+            BitcoinTransaction::from_json(&serde_json::to_string(&tx).unwrap())
+        };
+
+        assert_eq!(
+            json(|tx| tx.version = "020000".to_string()),
+            Err(DecodeError::InvalidField {
+                field: "version".to_string(),
+                reason: "is 3 bytes, must be 4".to_string()
+            })
+        );
+        assert_eq!(
+            json(|tx| tx.inputs[0].scriptsigsize = "02".to_string()),
+            Err(DecodeError::InvalidField {
+                field: "inputs[0].scriptsig".to_string(),
+                reason: "is 0 bytes, its size says 2".to_string()
+            })
+        );
+        assert!(matches!(
+            json(|tx| tx.outputcount = "fd".to_string()),
+            Err(DecodeError::InvalidField { field, .. }) if field == "outputcount"
+        ));
+        assert!(matches!(
+            json(|tx| tx.witness[0].items[1].item.truncate(2)),
+            Err(DecodeError::InvalidField { field, .. }) if field == "witness[0][1].item"
+        ));
+        assert!(matches!(
+            BitcoinTransaction::from_json("[]"),
+            Err(DecodeError::InvalidJson(_))
+        ));
+    }
+}
@@ -0,0 +1,1152 @@
+// Without the default `std` feature only the wire-format core is built:
+// wire, compact_size, limits and error, which need nothing beyond `alloc`.
+// no_std_check/ builds that core for a target without std.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// The cdylib needs a panic handler and allocator, which hosted targets get
+// from std. Nothing in the core modules can name it: they still only see
+// the no_std prelude.
+#[cfg(all(not(feature = "std"), not(target_os = "none")))]
+extern crate std;
+
+#[cfg(feature = "std")]
+pub mod address;
+#[cfg(feature = "std")]
+pub mod annotated;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod change;
+#[cfg(feature = "std")]
+pub mod classify;
+#[cfg(feature = "std")]
+mod compact;
+pub mod compact_size;
+#[cfg(feature = "std")]
+pub mod concat;
+#[cfg(feature = "std")]
+pub mod consensus;
+#[cfg(feature = "std")]
+pub mod csv;
+#[cfg(feature = "std")]
+pub mod descriptor;
+#[cfg(feature = "std")]
+pub mod diff;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod explain;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+#[cfg(feature = "std")]
+pub mod flat;
+#[cfg(feature = "std")]
+mod hashes;
+#[cfg(feature = "std")]
+pub mod inner;
+#[cfg(feature = "std")]
+pub mod input_kind;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+pub mod lightning;
+pub mod limits;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
+pub mod malleability;
+#[cfg(feature = "std")]
+pub mod network;
+#[cfg(feature = "std")]
+pub mod outpoint;
+#[cfg(feature = "std")]
+pub mod pretty;
+#[cfg(feature = "std")]
+pub mod psbt;
+#[cfg(feature = "std")]
+pub mod pubkeys;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod reader;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod script_sig;
+#[cfg(feature = "std")]
+pub mod sighash;
+#[cfg(feature = "std")]
+pub mod signature;
+#[cfg(feature = "std")]
+pub mod spend;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod synthetic;
+#[cfg(feature = "std")]
+pub mod taproot;
+#[cfg(feature = "std")]
+pub mod trace;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "verify")]
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod wire;
+#[cfg(feature = "std")]
+pub mod witness;
+#[cfg(feature = "std")]
+pub mod witness_role;
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use classify::ScriptType;
+pub use compact_size::CompactSize;
+#[cfg(feature = "std")]
+use error::DecodeError;
+#[cfg(feature = "std")]
+use input_kind::InputKind;
+pub use limits::DecodeLimits;
+#[cfg(feature = "std")]
+pub use network::Network;
+#[cfg(feature = "std")]
+use trace::Tracer;
+#[cfg(feature = "std")]
+use wire::{RawInput, RawOutput, RawTransaction};
+#[cfg(feature = "std")]
+pub use witness::{WitnessItem, WitnessStack};
+
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxInput {
+    pub txid: String,
+    pub vout: String,
+    pub scriptsigsize: String,
+    pub scriptsig: String,
+    pub sequence: String,
+    // Decoded redeem/witness script, when the input reveals one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inner_script: Option<inner::InnerScript>,
+    // Legacy, nested or native segwit, see input_kind::input_kind
+    #[serde(default)]
+    pub input_kind: InputKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness_version: Option<u8>,
+    // P2SH redeem script (last scriptSig push) and its classification; a
+    // witness program here means nested segwit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redeem_script_type: Option<ScriptType>,
+    // Push-only scriptSig, see script_sig::analyze_script_sig
+    #[serde(default = "default_standard")]
+    pub standard: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+fn default_standard() -> bool {
+    true
+}
+
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxOutput {
+    pub amount: String,
+    // `amount` decoded to satoshis
+    #[serde(default)]
+    pub value_sats: u64,
+    pub scriptpubkeysize: String,
+    pub scriptpubkey: String,
+    // On BitcoinTransaction::network; None for scripts with no address
+    #[serde(default)]
+    pub address: Option<String>,
+    // Below the dust threshold at the default dust relay fee
+    #[serde(default)]
+    pub is_dust: bool,
+    // Output descriptor with checksum, see descriptor::descriptor_for_script
+    #[serde(default)]
+    pub descriptor: String,
+    // Opt-in change heuristics, see change::detect_change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_score: Option<f64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub likely_change: bool,
+}
+
+// Which wire format the transaction was decoded as
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Serialization {
+    Segwit,
+    #[default]
+    Legacy,
+}
+
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BitcoinTransaction {
+    pub version: String,
+    // See validate::version_meaning
+    #[serde(default)]
+    pub version_meaning: String,
+    pub marker: String,
+    pub flag: String,
+    #[serde(default)]
+    pub serialization: Serialization,
+    pub inputcount: String,
+    pub inputs: Vec<TxInput>,
+    pub outputcount: String,
+    pub outputs: Vec<TxOutput>,
+    pub witness: Vec<WitnessStack>,
+    pub locktime: String,
+    // A lenient decode stopped at a truncated script. The fields after it,
+    // the locktime included, are missing (empty), not zero; such a
+    // transaction can't be encoded and has no txid.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    // BIP125: some input's sequence is below 0xfffffffe
+    #[serde(default)]
+    pub rbf_signaled: bool,
+    #[serde(default)]
+    pub input_kind_counts: BTreeMap<InputKind, usize>,
+    // What addresses and descriptors were derived for, see set_network
+    #[serde(default)]
+    pub network: Network,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    // Heuristic Lightning patterns, see lightning::lightning_hints
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+impl BitcoinTransaction {
+    pub fn is_segwit(&self) -> bool {
+        !self.marker.is_empty()
+    }
+
+    // Re-derives every output's address and descriptor for `network`
+    pub fn set_network(&mut self, network: Network) {
+        self.network = network;
+        for output in &mut self.outputs {
+            let script = hex::decode(&output.scriptpubkey).unwrap_or_default();
+            output.address = address::address_on(&script, network);
+            output.descriptor = descriptor::descriptor_for_script_on(&script, network);
+        }
+    }
+
+    // Double-SHA256 of the transaction without witness data, byte-reversed
+    // as block explorers show it.
+    // Fails for a truncated transaction.
+    pub fn txid(&self) -> Result<String, DecodeError> {
+        let bytes = hex::decode(encode_transaction(&self.without_witness())?).unwrap_or_default();
+
+        let mut hash = hashes::double_sha256(&bytes);
+        hash.reverse();
+        Ok(hex::encode(hash))
+    }
+
+    // Serialized size without marker, flag and witness. For a truncated
+    // transaction, of the bytes that were there.
+    pub fn base_size(&self) -> usize {
+        serialize(&self.without_witness()).len() / 2
+    }
+
+    pub fn total_size(&self) -> usize {
+        serialize(self).len() / 2
+    }
+
+    // BIP141 weight: base size counts four times, witness data once
+    pub fn weight(&self) -> usize {
+        self.base_size() * 3 + self.total_size()
+    }
+
+    // Weight in virtual bytes, rounded up
+    pub fn vsize(&self) -> usize {
+        self.weight().div_ceil(4)
+    }
+
+    fn without_witness(&self) -> BitcoinTransaction {
+        let mut stripped = self.clone();
+        stripped.marker.clear();
+        stripped.flag.clear();
+        stripped.witness.clear();
+        stripped
+    }
+
+    pub fn iter_inputs(&self) -> impl Iterator<Item = &TxInput> {
+        self.inputs.iter()
+    }
+
+    pub fn iter_outputs(&self) -> impl Iterator<Item = &TxOutput> {
+        self.outputs.iter()
+    }
+
+    // Saturates rather than overflowing on nonsense amounts; lint reports
+    // those separately.
+    pub fn total_output_value(&self) -> u64 {
+        self.iter_outputs().fold(0u64, |total, output| {
+            total.saturating_add(output.value_sats)
+        })
+    }
+
+    // Hex of each witness item for the given input, in stack order.
+    pub fn witness_items(&self, input_index: usize) -> Vec<String> {
+        self.witness
+            .get(input_index)
+            .map(|stack| stack.items.iter().map(|item| item.item.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "std")]
+pub fn btc_tx_decoder(input: &str) -> Result<String, String> {
+    let tx = decode_transaction(input)?;
+
+    // Serialize to JSON, keeping the witness in its original shape
+    serde_json::to_string_pretty(&witness::legacy_json(&tx))
+        .map_err(|e| format!("JSON serialization error: {}", e))
+}
+
+// btc_tx_decoder with empty fields left out: a legacy transaction has no
+// marker, flag or witness keys, and there is no warnings key without
+// warnings
+#[cfg(feature = "std")]
+pub fn btc_tx_decoder_compact(input: &str) -> Result<String, String> {
+    let tx = decode_transaction(input)?;
+
+    serde_json::to_string_pretty(&compact::CompactTransaction::from(&tx))
+        .map_err(|e| format!("JSON serialization error: {}", e))
+}
+
+// btc_tx_decoder without segwit detection: everything after the version is
+// read as legacy, so 00 01 there is an empty input list and an output
+// count, never a marker and flag, and no witness is parsed.
+#[cfg(feature = "std")]
+pub fn btc_tx_decoder_legacy(input: &str) -> Result<String, String> {
+    let tx = decode_transaction_legacy(input)?;
+
+    serde_json::to_string_pretty(&witness::legacy_json(&tx))
+        .map_err(|e| format!("JSON serialization error: {}", e))
+}
+
+#[cfg(feature = "std")]
+pub fn decode_transaction_legacy(input: &str) -> Result<BitcoinTransaction, String> {
+    let bytes = hex::decode(normalize_hex(input)).map_err(|e| format!("Invalid hex: {}", e))?;
+    parse_transaction_legacy(&bytes)
+        .map(|(tx, _)| tx)
+        .map_err(plain_message)
+}
+
+// The legacy reading of `bytes`, with the number of bytes it took
+#[cfg(feature = "std")]
+pub(crate) fn parse_transaction_legacy(
+    bytes: &[u8],
+) -> Result<(BitcoinTransaction, usize), DecodeError> {
+    parse_body(
+        bytes,
+        false,
+        &DecodeLimits::default(),
+        &mut Tracer::default(),
+    )
+}
+
+// Like btc_tx_decoder, for untrusted input: counts over `limits` are rejected
+// before the parser loops over them.
+#[cfg(feature = "std")]
+pub fn btc_tx_decoder_with_limits(
+    input: &str,
+    limits: &DecodeLimits,
+) -> Result<String, DecodeError> {
+    let tx = decode_transaction_with_limits(input, limits)?;
+
+    serde_json::to_string_pretty(&tx)
+        .map_err(|e| DecodeError::InvalidTransaction(format!("JSON serialization error: {}", e)))
+}
+
+#[cfg(feature = "std")]
+pub fn decode_transaction_with_limits(
+    input: &str,
+    limits: &DecodeLimits,
+) -> Result<BitcoinTransaction, DecodeError> {
+    let bytes =
+        hex::decode(normalize_hex(input)).map_err(|e| DecodeError::InvalidHex(e.to_string()))?;
+    parse_transaction_traced(&bytes, limits, &mut Tracer::default())
+}
+
+// Decodes with addresses and descriptors for `network` instead of mainnet
+#[cfg(feature = "std")]
+pub fn decode_transaction_for_network(
+    input: &str,
+    network: Network,
+) -> Result<BitcoinTransaction, DecodeError> {
+    let mut tx = decode_transaction_with_limits(input, &DecodeLimits::default())?;
+    tx.set_network(network);
+    Ok(tx)
+}
+
+// decode_transaction_with_limits for validating modern transactions: the
+// signature and key encodings consensus tolerates in old ones (high-S,
+// non-canonical DER, ...) are errors rather than input warnings
+#[cfg(feature = "std")]
+pub fn decode_transaction_strict(
+    input: &str,
+    limits: &DecodeLimits,
+) -> Result<BitcoinTransaction, DecodeError> {
+    let tx = decode_transaction_with_limits(input, limits)?;
+    signature::check_strict_encoding(&tx)?;
+    Ok(tx)
+}
+
+#[cfg(feature = "std")]
+pub fn decode_transaction(input: &str) -> Result<BitcoinTransaction, String> {
+    let hex_input = normalize_hex(input);
+
+    // Convert hex string to bytes
+    let bytes = hex::decode(&hex_input).map_err(|e| format!("Invalid hex: {}", e))?;
+
+    parse_transaction(&bytes)
+}
+
+// Reads back the JSON btc_tx_decoder prints, or the struct's own serde
+// JSON, so a transaction can be decoded, edited and re-encoded with
+// encode_transaction. See BitcoinTransaction::from_json.
+#[cfg(feature = "std")]
+pub fn from_json(json: &str) -> Result<BitcoinTransaction, String> {
+    BitcoinTransaction::from_json(json).map_err(|e| e.to_string())
+}
+
+// Pasted hex: drops whitespace and a leading 0x. Either case is fine for
+// hex::decode.
+#[cfg(feature = "std")]
+pub(crate) fn normalize_hex(input: &str) -> String {
+    let hex: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    match hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")) {
+        Some(rest) => rest.to_string(),
+        None => hex,
+    }
+}
+
+#[cfg(feature = "std")]
+pub fn parse_transaction(bytes: &[u8]) -> Result<BitcoinTransaction, String> {
+    parse_transaction_traced(bytes, &DecodeLimits::default(), &mut Tracer::default())
+        .map_err(plain_message)
+}
+
+// Keeps the plain messages the String APIs have always returned
+#[cfg(feature = "std")]
+fn plain_message(e: DecodeError) -> String {
+    match e {
+        DecodeError::InvalidTransaction(message) => message,
+        e => e.to_string(),
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) const AMBIGUOUS_SERIALIZATION: &str =
+    "ambiguous serialization: also parses as a legacy transaction with no inputs, decoded as segwit";
+
+#[cfg(feature = "std")]
+pub(crate) fn parse_transaction_traced(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+    trace: &mut Tracer,
+) -> Result<BitcoinTransaction, DecodeError> {
+    if bytes.len() < 4 {
+        return Err(invalid("Input too short for version"));
+    }
+    if !wire::has_segwit_marker(bytes) {
+        return parse_body(bytes, false, limits, trace).map(|(tx, _)| tx);
+    }
+
+    // Parse it both ways, see wire::prefer_legacy
+    let mut segwit_trace = trace.fork();
+    let mut legacy_trace = trace.fork();
+    let segwit = parse_body(bytes, true, limits, &mut segwit_trace);
+    let legacy = parse_body(bytes, false, limits, &mut legacy_trace);
+
+    let ambiguous = wire::consumes(&segwit, bytes.len()) && wire::consumes(&legacy, bytes.len());
+    let (result, chosen_trace) = if wire::prefer_legacy(&segwit, &legacy, bytes.len()) {
+        (legacy, legacy_trace)
+    } else {
+        (segwit, segwit_trace)
+    };
+    trace.extend(chosen_trace);
+
+    let (mut tx, _) = result?;
+    if ambiguous {
+        tx.warnings.push(AMBIGUOUS_SERIALIZATION.to_string());
+    }
+    Ok(tx)
+}
+
+// The transaction at the start of `bytes`, with the number of bytes it
+// took. Whatever follows is left alone, so unlike parse_transaction_traced
+// this can't compare how much each reading consumes: a marker and flag are
+// taken as segwit whenever the segwit parse succeeds.
+#[cfg(feature = "std")]
+pub(crate) fn parse_transaction_prefix(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<(BitcoinTransaction, usize), DecodeError> {
+    let mut trace = Tracer::default();
+    if !wire::has_segwit_marker(bytes) {
+        return parse_body(bytes, false, limits, &mut trace);
+    }
+    let segwit = parse_body(bytes, true, limits, &mut trace);
+    if segwit.is_ok() {
+        return segwit;
+    }
+    parse_body(bytes, false, limits, &mut trace).or(segwit)
+}
+
+// Parses the transaction as segwit (marker, flag and witness) or legacy,
+// returning it with the number of bytes consumed.
+#[cfg(feature = "std")]
+fn parse_body(
+    bytes: &[u8],
+    is_segwit: bool,
+    limits: &DecodeLimits,
+    trace: &mut Tracer,
+) -> Result<(BitcoinTransaction, usize), DecodeError> {
+    let (raw, end) = wire::parse_body(bytes, is_segwit, limits, &mut |field, offset, len| {
+        trace.record(offset, len, || field.to_string())
+    })?;
+    Ok((from_raw(raw), end))
+}
+
+// The annotated transaction for what wire parsed
+#[cfg(feature = "std")]
+pub(crate) fn from_raw(raw: RawTransaction) -> BitcoinTransaction {
+    let (marker, flag, serialization) = match raw.flag {
+        Some(flag) => ("00".to_string(), hex::encode([flag]), Serialization::Segwit),
+        None => (String::new(), String::new(), Serialization::Legacy),
+    };
+    let witness = raw
+        .witness
+        .into_iter()
+        .map(|stack| WitnessStack {
+            stackitems: stack.stack_items.encoded,
+            items: stack
+                .items
+                .into_iter()
+                .map(|item| WitnessItem {
+                    size: item.size.encoded,
+                    item: hex::encode(item.data),
+                    role: None,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let mut tx = BitcoinTransaction {
+        version: hex::encode(raw.version.to_le_bytes()),
+        version_meaning: String::new(),
+        marker,
+        flag,
+        serialization,
+        inputcount: raw.input_count.encoded,
+        inputs: raw.inputs.into_iter().map(tx_input).collect(),
+        outputcount: raw
+            .output_count
+            .map(|count| count.encoded)
+            .unwrap_or_default(),
+        outputs: raw.outputs.into_iter().map(tx_output).collect(),
+        witness,
+        locktime: raw
+            .locktime
+            .map(|locktime| hex::encode(locktime.to_le_bytes()))
+            .unwrap_or_default(),
+        truncated: raw.locktime.is_none(),
+        rbf_signaled: false,
+        input_kind_counts: BTreeMap::new(),
+        network: Network::Mainnet,
+        warnings: Vec::new(),
+        hints: Vec::new(),
+    };
+    annotate(&mut tx);
+    // Truncated scripts first, they explain anything odd that follows
+    tx.warnings.splice(0..0, raw.warnings);
+    tx
+}
+
+#[cfg(feature = "std")]
+fn invalid(message: &str) -> DecodeError {
+    DecodeError::InvalidTransaction(message.to_string())
+}
+
+// Fill in the fields derived from the parsed transaction
+#[cfg(feature = "std")]
+pub(crate) fn annotate(tx: &mut BitcoinTransaction) {
+    for index in 0..tx.inputs.len() {
+        tx.inputs[index].inner_script = inner::inner_script(tx, index, None);
+        let (kind, version) = input_kind::input_kind(tx, index, None);
+        tx.inputs[index].input_kind = kind;
+        tx.inputs[index].witness_version = version;
+        witness_role::set_witness_roles(tx, index, None);
+        tx.inputs[index].redeem_script = inner::extract_redeem_script(&tx.inputs[index].scriptsig);
+        tx.inputs[index].redeem_script_type = tx.inputs[index]
+            .redeem_script
+            .as_deref()
+            .map(|redeem| inner::classify_redeem_script(tx, index, redeem));
+    }
+    script_sig::check_script_sigs(tx);
+    signature::check_signature_encodings(tx);
+    tx.input_kind_counts = input_kind::input_kind_counts(tx);
+    tx.rbf_signaled = tx
+        .inputs
+        .iter()
+        .any(|input| le_hex_to_u64(&input.sequence).is_some_and(|sequence| sequence < 0xfffffffe));
+    tx.version_meaning = validate::version_meaning(&tx.version).to_string();
+    tx.warnings = validate::check_segwit_consistency(tx);
+    tx.warnings.extend(validate::check_version(tx));
+    tx.warnings.extend(address::check_addresses(tx));
+    tx.warnings.extend(signature::check_sighash_types(tx));
+    tx.hints = lightning::lightning_hints(tx);
+}
+
+// Re-serialize a decoded transaction back to hex. Every field keeps its raw
+// encoding, so this is just the fields concatenated in wire order. A
+// truncated transaction is refused rather than encoded without its missing
+// fields.
+#[cfg(feature = "std")]
+pub fn encode_transaction(tx: &BitcoinTransaction) -> Result<String, DecodeError> {
+    if tx.truncated {
+        return Err(DecodeError::Truncated);
+    }
+    Ok(serialize(tx))
+}
+
+// The fields concatenated as they are
+#[cfg(feature = "std")]
+fn serialize(tx: &BitcoinTransaction) -> String {
+    let mut out = String::new();
+    out.push_str(&tx.version);
+    out.push_str(&tx.marker);
+    out.push_str(&tx.flag);
+    out.push_str(&tx.inputcount);
+    for input in &tx.inputs {
+        out.push_str(&input.txid);
+        out.push_str(&input.vout);
+        out.push_str(&input.scriptsigsize);
+        out.push_str(&input.scriptsig);
+        out.push_str(&input.sequence);
+    }
+    out.push_str(&tx.outputcount);
+    for output in &tx.outputs {
+        out.push_str(&output.amount);
+        out.push_str(&output.scriptpubkeysize);
+        out.push_str(&output.scriptpubkey);
+    }
+    for stack in &tx.witness {
+        out.push_str(&stack.stackitems);
+        for item in &stack.items {
+            out.push_str(&item.size);
+            out.push_str(&item.item);
+        }
+    }
+    out.push_str(&tx.locktime);
+    out
+}
+
+// The transaction hex without marker, flag and witness: the pre-segwit
+// serialization the txid is computed over. Legacy transactions come back
+// as they are.
+#[cfg(feature = "std")]
+pub fn strip_witness(tx: &BitcoinTransaction) -> Result<String, String> {
+    let stripped = encode_transaction(&tx.without_witness())?;
+    hex::decode(&stripped).map_err(|e| format!("Invalid hex in transaction fields: {}", e))?;
+    Ok(stripped)
+}
+
+// Decode a little-endian hex field (amount, vout, sequence, ...) as an integer.
+// An empty field is one a truncated decode never read, not zero.
+#[cfg(feature = "std")]
+pub(crate) fn le_hex_to_u64(field: &str) -> Option<u64> {
+    let bytes = hex::decode(field).ok()?;
+    if bytes.is_empty() || bytes.len() > 8 {
+        return None;
+    }
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(&bytes);
+    Some(u64::from_le_bytes(buf))
+}
+
+// For callers that report errors as strings
+#[cfg(feature = "std")]
+pub(crate) fn read_compact_size(bytes: &[u8], pos: usize) -> Result<(usize, usize), String> {
+    CompactSize::read(bytes, pos)
+        .map(|(compact_size, size)| (compact_size.len(), size))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "std")]
+fn tx_input(raw: RawInput) -> TxInput {
+    TxInput {
+        txid: hex::encode(raw.txid),
+        vout: hex::encode(raw.vout.to_le_bytes()),
+        scriptsigsize: raw.script_sig_len.encoded,
+        scriptsig: hex::encode(raw.script_sig),
+        sequence: raw
+            .sequence
+            .map(|sequence| hex::encode(sequence.to_le_bytes()))
+            .unwrap_or_default(),
+        inner_script: None,
+        input_kind: InputKind::Unknown,
+        witness_version: None,
+        redeem_script: None,
+        redeem_script_type: None,
+        standard: true,
+        warnings: Vec::new(),
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn parse_output(bytes: &[u8], pos: usize) -> Result<(TxOutput, usize), DecodeError> {
+    wire::parse_output(bytes, pos).map(|(raw, size)| (tx_output(raw), size))
+}
+
+#[cfg(feature = "std")]
+fn tx_output(raw: RawOutput) -> TxOutput {
+    let mut output = TxOutput {
+        amount: hex::encode(raw.value.to_le_bytes()),
+        value_sats: raw.value,
+        scriptpubkeysize: raw.script_pubkey_len.encoded,
+        scriptpubkey: hex::encode(&raw.script_pubkey),
+        address: address::address(&raw.script_pubkey),
+        is_dust: false,
+        change_score: None,
+        likely_change: false,
+        descriptor: descriptor::descriptor_for_script(&raw.script_pubkey),
+    };
+    output.is_dust = lint::is_dust(&output, lint::DUST_RELAY_FEE);
+    output
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(crate) use crate::wire::tests::SAMPLE_TX;
+
+    // No inputs and one output, so 00 01 follows the version
+    const ZERO_INPUT_LEGACY_TX: &str =
+        "0100000000011027000000000000160014841b80d2cc75f5345c482af96294d04fdd66b2b700000000";
+
+    // Segwit with one input and no outputs, or legacy with no inputs and one
+    // output
+    fn ambiguous_tx() -> String {
+        format!(
+            "01000000000101{}23{}{}00ffffffff000000000000",
+            "00".repeat(7),
+            "00".repeat(24),
+            "00000000"
+        )
+    }
+
+    // The transactions these tests decode, good and bad, for checking other
+    // decoders against decode_transaction
+    pub(crate) fn vectors() -> Vec<String> {
+        let witness_start = SAMPLE_TX.find("0247304402").unwrap();
+        vec![
+            SAMPLE_TX.to_string(),
+            ZERO_INPUT_LEGACY_TX.to_string(),
+            "010000000000ffffffff".to_string(),
+            SAMPLE_TX.replacen("02000000000101", "02000000000201", 1),
+            ambiguous_tx(),
+            format!("{}01ffffffffffffffffff0000", &SAMPLE_TX[..witness_start]),
+            "02000000fefeffffff".to_string(),
+            SAMPLE_TX[..70].to_string(),
+        ]
+    }
+
+    #[test]
+    fn test_btc_tx_decoder() {
+        let input = "0200000000010131811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c10100000000fdffffff0220a107000000000016001485d78eb795bd9c8a21afefc8b6fdaedf718368094c08100000000000160014840ab165c9c2555d4a31b9208ad806f89d2535e20247304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01210260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff43030e00";
+        let expected_output = json!({
+            "version": "02000000",
+            "version_meaning": "2 (BIP68)",
+            "marker": "00",
+            "flag": "01",
+            "serialization": "segwit",
+            "inputcount": "01",
+            "inputs": [
+                {
+                    "txid": "31811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c1",
+                    "vout": "01000000",
+                    "scriptsigsize": "00",
+                    "scriptsig": "",
+                    "sequence": "fdffffff",
+                    "input_kind": "native-segwit-v0",
+                    "witness_version": 0,
+                    "standard": true
+                }
+            ],
+            "outputcount": "02",
+            "outputs": [
+                {
+                    "amount": "20a1070000000000",
+                    "value_sats": 500000,
+                    "scriptpubkeysize": "16",
+                    "scriptpubkey": "001485d78eb795bd9c8a21afefc8b6fdaedf71836809",
+                    "address": "bc1qshtcadu4hkwg5gd0alytdldwmaccx6qfh9fg9u",
+                    "is_dust": false,
+                    "descriptor": "addr(bc1qshtcadu4hkwg5gd0alytdldwmaccx6qfh9fg9u)#04ktdfdp"
+                },
+                {
+                    "amount": "4c08100000000000",
+                    "value_sats": 1050700,
+                    "scriptpubkeysize": "16",
+                    "scriptpubkey": "0014840ab165c9c2555d4a31b9208ad806f89d2535e2",
+                    "address": "bc1qss9tzewfcf246j33hysg4kqxlzwj2d0zn4wd90",
+                    "is_dust": false,
+                    "descriptor": "addr(bc1qss9tzewfcf246j33hysg4kqxlzwj2d0zn4wd90)#96h4w6ya"
+                }
+            ],
+            "witness": [
+                {
+                    "stackitems": "02",
+                    "0": {
+                        "size": "47",
+                        "item": "304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01",
+                        "role": "signature"
+                    },
+                    "1": {
+                        "size": "21",
+                        "item": "0260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff",
+                        "role": "pubkey"
+                    }
+                }
+            ],
+            "locktime": "43030e00",
+            "rbf_signaled": true,
+            "input_kind_counts": { "native-segwit-v0": 1 },
+            "network": "mainnet",
+            "warnings": []
+        });
+        let result = btc_tx_decoder(input).unwrap();
+        let result_json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result_json, expected_output);
+    }
+    #[test]
+    fn test_btc_tx_decoder_invalid_hex() {
+        let input = "invalidhex";
+        let result = btc_tx_decoder(input);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hex_prefix_and_case() {
+        let canonical = btc_tx_decoder(SAMPLE_TX).unwrap();
+        let upper = SAMPLE_TX.to_uppercase();
+        assert_eq!(btc_tx_decoder(&upper).unwrap(), canonical);
+        assert_eq!(
+            btc_tx_decoder(&format!("0x{}", SAMPLE_TX)).unwrap(),
+            canonical
+        );
+        assert_eq!(
+            btc_tx_decoder(&format!(" 0X{}\n", upper)).unwrap(),
+            canonical
+        );
+        assert!(btc_tx_decoder("0x").is_err());
+    }
+
+    #[test]
+    fn test_encode_transaction_round_trip() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(encode_transaction(&tx).unwrap(), SAMPLE_TX);
+    }
+
+    #[test]
+    fn test_zero_input_legacy_with_marker_bytes() {
+        // No inputs and one output: the 00 01 after the version is an input
+        // count and output count, not the segwit marker
+        let hex = ZERO_INPUT_LEGACY_TX;
+        let tx = decode_transaction(hex).unwrap();
+        assert_eq!(tx.serialization, Serialization::Legacy);
+        assert!(tx.inputs.is_empty());
+        assert_eq!(tx.outputs[0].value_sats, 10_000);
+        assert!(tx.warnings.is_empty());
+        assert_eq!(encode_transaction(&tx).unwrap(), hex);
+    }
+
+    #[test]
+    fn test_segwit_flag() {
+        // 00 00 is a zero input count and a zero output count, never a
+        // marker and flag
+        let tx = decode_transaction("010000000000ffffffff").unwrap();
+        assert_eq!(tx.serialization, Serialization::Legacy);
+        assert!(tx.inputs.is_empty() && tx.outputs.is_empty());
+        assert_eq!(tx.locktime, "ffffffff");
+
+        // Any other nonzero flag is still segwit, with a warning
+        let hex = SAMPLE_TX.replacen("02000000000101", "02000000000201", 1);
+        let tx = decode_transaction(&hex).unwrap();
+        assert_eq!(tx.serialization, Serialization::Segwit);
+        assert_eq!(tx.flag, "02");
+        assert_eq!(
+            tx.warnings,
+            vec!["unknown segwit flag 0x02, only 0x01 is defined"]
+        );
+        assert_eq!(encode_transaction(&tx).unwrap(), hex);
+        assert!(decode_transaction(SAMPLE_TX).unwrap().warnings.is_empty());
+    }
+
+    #[test]
+    fn test_segwit_serialization() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(tx.serialization, Serialization::Segwit);
+        assert!(tx.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_ambiguous_serialization() {
+        // As segwit: one input with a 0x23 in its txid, no outputs, an empty
+        // witness. As legacy: no inputs and one output whose scriptPubKey
+        // length is that 0x23.
+        let hex = ambiguous_tx();
+        let tx = decode_transaction(&hex).unwrap();
+        assert_eq!(tx.serialization, Serialization::Segwit);
+        assert_eq!(tx.inputs.len(), 1);
+        assert!(tx.warnings.iter().any(|w| w.contains("ambiguous")));
+    }
+
+    #[test]
+    fn test_legacy_decoder() {
+        // The ambiguous transaction above, forced to legacy
+        let hex = ambiguous_tx();
+        let tx = decode_transaction_legacy(&hex).unwrap();
+        assert_eq!(tx.serialization, Serialization::Legacy);
+        assert!(tx.inputs.is_empty());
+        assert_eq!(tx.outputs.len(), 1);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&btc_tx_decoder_legacy(&hex).unwrap()).unwrap();
+        assert_eq!(json["marker"], "");
+        assert_eq!(json["flag"], "");
+        assert_eq!(json["witness"], json!([]));
+
+        // A segwit transaction doesn't parse as legacy
+        assert!(btc_tx_decoder_legacy(SAMPLE_TX).is_err());
+    }
+
+    #[test]
+    fn test_huge_witness_item_size() {
+        // The sample up to its witness, then one item claiming 2^64-1 bytes
+        let witness_start = SAMPLE_TX.find("0247304402").unwrap();
+        let hex = format!("{}01ffffffffffffffffff0000", &SAMPLE_TX[..witness_start]);
+        let bytes = hex::decode(&hex).unwrap();
+
+        let result =
+            parse_transaction_traced(&bytes, &DecodeLimits::default(), &mut Tracer::default());
+        assert_eq!(
+            result,
+            Err(DecodeError::LimitExceeded {
+                field: "witness item length",
+                count: u64::MAX,
+                limit: DecodeLimits::default().max_script_len
+            })
+        );
+        assert!(decode_transaction(&hex).is_err());
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        // Version, then 0xfffffffe inputs and nothing else
+        let hex = "02000000fefeffffff";
+        let result = decode_transaction_with_limits(hex, &DecodeLimits::default());
+        assert_eq!(
+            result,
+            Err(DecodeError::LimitExceeded {
+                field: "input count",
+                count: 0xfffffffe,
+                limit: 24_390
+            })
+        );
+
+        let limits = DecodeLimits {
+            max_outputs: 1,
+            ..DecodeLimits::default()
+        };
+        let result = btc_tx_decoder_with_limits(SAMPLE_TX, &limits);
+        assert!(matches!(
+            result,
+            Err(DecodeError::LimitExceeded {
+                field: "output count",
+                count: 2,
+                limit: 1
+            })
+        ));
+        assert!(btc_tx_decoder_with_limits(SAMPLE_TX, &DecodeLimits::default()).is_ok());
+    }
+
+    #[test]
+    fn test_lenient_truncation() {
+        // A legacy transaction cut off 10 bytes into its 22-byte scriptPubKey
+        let hex = builder::TxBuilder::new()
+            .add_input(&"ab".repeat(32), 0)
+            .add_output_sats(1_000, &hex::decode(&SAMPLE_TX[98..142]).unwrap())
+            .build_hex()
+            .unwrap();
+        let truncated = &hex[..hex.len() - 2 * (12 + 4)];
+
+        assert!(matches!(
+            decode_transaction_with_limits(truncated, &DecodeLimits::default()),
+            Err(DecodeError::UnexpectedEof {
+                needed: 22,
+                available: 10
+            })
+        ));
+
+        let limits = DecodeLimits {
+            lenient: true,
+            ..DecodeLimits::default()
+        };
+        let tx = decode_transaction_with_limits(truncated, &limits).unwrap();
+        assert_eq!(
+            tx.warnings,
+            vec!["scriptpubkey truncated: declared 22, available 10"]
+        );
+        assert_eq!(tx.outputs[0].scriptpubkeysize, "16");
+        assert_eq!(tx.outputs[0].scriptpubkey, SAMPLE_TX[98..118]);
+        // The locktime was never read, so it is absent rather than zero
+        assert!(tx.truncated);
+        assert!(tx.locktime.is_empty());
+        assert_eq!(encode_transaction(&tx), Err(DecodeError::Truncated));
+        assert_eq!(tx.txid(), Err(DecodeError::Truncated));
+
+        let raw = wire::decode_with_limits(&hex::decode(truncated).unwrap(), &limits).unwrap();
+        assert!(raw.is_truncated());
+        assert_eq!(raw.locktime, None);
+
+        // Cut 5 bytes into a 20-byte scriptSig: the RBF sequence after it is
+        // missing too, not read as final
+        let hex = builder::TxBuilder::new()
+            .add_input(&"ab".repeat(32), 0)
+            .script_sig(&[0x51; 20])
+            .sequence(0xfffffffd)
+            .add_output_sats(1_000, &[0x51])
+            .build_hex()
+            .unwrap();
+        let truncated = &hex[..2 * (4 + 1 + 36 + 1 + 5)];
+        let tx = decode_transaction_with_limits(truncated, &limits).unwrap();
+        assert!(tx.truncated);
+        assert_eq!(tx.inputs[0].scriptsig, "51".repeat(5));
+        assert!(tx.inputs[0].sequence.is_empty());
+        assert!(!tx.rbf_signaled);
+        let sequence = explain::explain(&tx)
+            .into_iter()
+            .find(|e| e.path == "inputs.0.sequence")
+            .unwrap();
+        assert_eq!(sequence.value, "missing");
+
+        let raw = wire::decode_with_limits(&hex::decode(truncated).unwrap(), &limits).unwrap();
+        assert_eq!(raw.inputs[0].sequence, None);
+    }
+
+    #[test]
+    fn test_from_json_round_trip() {
+        let json = btc_tx_decoder(SAMPLE_TX).unwrap();
+        let tx = from_json(&json).unwrap();
+        assert_eq!(encode_transaction(&tx).unwrap(), SAMPLE_TX);
+        assert_eq!(tx, decode_transaction(SAMPLE_TX).unwrap());
+
+        // The typed witness shape, after an edit
+        let mut edited = tx.clone();
+        edited.locktime = "00000000".to_string();
+        let tx = from_json(&serde_json::to_string(&edited).unwrap()).unwrap();
+        assert_eq!(
+            encode_transaction(&tx).unwrap(),
+            SAMPLE_TX.replace("43030e00", "00000000")
+        );
+
+        assert!(from_json("{\"version\": \"02000000\"}").is_err());
+    }
+
+    #[test]
+    fn test_txid() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(
+            tx.txid().unwrap(),
+            "04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842"
+        );
+    }
+
+    #[test]
+    fn test_strip_witness() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let stripped = strip_witness(&tx).unwrap();
+        assert_eq!(stripped.len() / 2, tx.base_size());
+
+        let mut hash = hashes::double_sha256(&hex::decode(&stripped).unwrap());
+        hash.reverse();
+        assert_eq!(hex::encode(hash), tx.txid().unwrap());
+
+        // Already stripped
+        let legacy = decode_transaction(&stripped).unwrap();
+        assert_eq!(strip_witness(&legacy).unwrap(), stripped);
+
+        let mut bad = tx.clone();
+        bad.locktime = "zz".to_string();
+        assert!(strip_witness(&bad).is_err());
+    }
+
+    #[test]
+    fn test_rbf_signaled() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(tx.rbf_signaled);
+
+        let final_sequence = SAMPLE_TX.replacen("fdffffff", "ffffffff", 1);
+        assert!(!decode_transaction(&final_sequence).unwrap().rbf_signaled);
+        // 0xfffffffe enables locktime but doesn't signal
+        let locktime_only = SAMPLE_TX.replacen("fdffffff", "feffffff", 1);
+        assert!(!decode_transaction(&locktime_only).unwrap().rbf_signaled);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(tx.total_size(), SAMPLE_TX.len() / 2);
+        assert_eq!(tx.base_size(), 113);
+        assert_eq!(tx.weight(), 113 * 3 + 222);
+    }
+
+    #[test]
+    fn test_total_output_value() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(tx.total_output_value(), 500_000 + 1_050_700);
+        assert_eq!(tx.iter_inputs().count(), 1);
+        assert_eq!(
+            tx.iter_outputs().filter(|o| o.value_sats > 600_000).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_decode_for_network() {
+        let tx = decode_transaction_for_network(SAMPLE_TX, Network::Testnet4).unwrap();
+        assert_eq!(tx.network, Network::Testnet4);
+        assert_eq!(
+            tx.outputs[0].address.as_deref(),
+            Some("tb1qshtcadu4hkwg5gd0alytdldwmaccx6qfarjm70")
+        );
+        assert!(tx.outputs[0].descriptor.starts_with("addr(tb1q"));
+
+        let json: serde_json::Value = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["network"], "testnet4");
+    }
+}
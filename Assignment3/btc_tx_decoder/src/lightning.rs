@@ -0,0 +1,283 @@
+use crate::inner::{InnerScript, InnerScriptSource};
+use crate::script::{
+    instructions, Instruction, OP_1, OP_16, OP_2, OP_CHECKLOCKTIMEVERIFY, OP_CHECKMULTISIG,
+    OP_CHECKSEQUENCEVERIFY, OP_CHECKSIG, OP_DROP, OP_DUP, OP_ELSE, OP_ENDIF, OP_EQUAL,
+    OP_EQUALVERIFY, OP_HASH160, OP_IF, OP_NOTIF, OP_SIZE, OP_SWAP,
+};
+use crate::{le_hex_to_u64, BitcoinTransaction};
+
+const ANCHOR_VALUE_SATS: u64 = 330;
+
+// One element of a script shape: a specific opcode, a push of a given length
+// (keys, hashes) or any number (delays and expiries).
+#[derive(Clone, Copy)]
+enum Shape {
+    Op(u8),
+    Push(usize),
+    Number,
+}
+
+use Shape::*;
+
+// BOLT 3 to_local, also the output of HTLC-success/timeout transactions:
+// OP_IF <revocationpubkey> OP_ELSE <to_self_delay> OP_CSV OP_DROP
+// <local_delayedpubkey> OP_ENDIF OP_CHECKSIG
+const TO_LOCAL: &[Shape] = &[
+    Op(OP_IF),
+    Push(33),
+    Op(OP_ELSE),
+    Number,
+    Op(OP_CHECKSEQUENCEVERIFY),
+    Op(OP_DROP),
+    Push(33),
+    Op(OP_ENDIF),
+    Op(OP_CHECKSIG),
+];
+
+const OFFERED_HTLC: &[Shape] = &[
+    Op(OP_DUP),
+    Op(OP_HASH160),
+    Push(20),
+    Op(OP_EQUAL),
+    Op(OP_IF),
+    Op(OP_CHECKSIG),
+    Op(OP_ELSE),
+    Push(33),
+    Op(OP_SWAP),
+    Op(OP_SIZE),
+    Push(1),
+    Op(OP_EQUAL),
+    Op(OP_NOTIF),
+    Op(OP_DROP),
+    Op(OP_2),
+    Op(OP_SWAP),
+    Push(33),
+    Op(OP_2),
+    Op(OP_CHECKMULTISIG),
+    Op(OP_ELSE),
+    Op(OP_HASH160),
+    Push(20),
+    Op(OP_EQUALVERIFY),
+    Op(OP_CHECKSIG),
+    Op(OP_ENDIF),
+    Op(OP_ENDIF),
+];
+
+const RECEIVED_HTLC: &[Shape] = &[
+    Op(OP_DUP),
+    Op(OP_HASH160),
+    Push(20),
+    Op(OP_EQUAL),
+    Op(OP_IF),
+    Op(OP_CHECKSIG),
+    Op(OP_ELSE),
+    Push(33),
+    Op(OP_SWAP),
+    Op(OP_SIZE),
+    Push(1),
+    Op(OP_EQUAL),
+    Op(OP_IF),
+    Op(OP_HASH160),
+    Push(20),
+    Op(OP_EQUALVERIFY),
+    Op(OP_2),
+    Op(OP_SWAP),
+    Push(33),
+    Op(OP_2),
+    Op(OP_CHECKMULTISIG),
+    Op(OP_ELSE),
+    Op(OP_DROP),
+    Number,
+    Op(OP_CHECKLOCKTIMEVERIFY),
+    Op(OP_DROP),
+    Op(OP_CHECKSIG),
+    Op(OP_ENDIF),
+    Op(OP_ENDIF),
+];
+
+// Lightning patterns spotted in the transaction. These are heuristics, so
+// they are reported as free-form hints rather than classifications.
+pub fn lightning_hints(tx: &BitcoinTransaction) -> Vec<String> {
+    let mut hints = Vec::new();
+
+    // Commitment transactions hide the obscured commitment number in the
+    // lower 24 bits of the sequence and locktime, flagged by 0x80 and 0x20 in
+    // the upper byte.
+    if let ([input], Some(locktime)) = (tx.inputs.as_slice(), le_hex_to_u64(&tx.locktime)) {
+        let sequence = le_hex_to_u64(&input.sequence).unwrap_or_default();
+        if sequence >> 24 == 0x80 && locktime >> 24 == 0x20 {
+            let obscured = (sequence & 0xff_ffff) << 24 | (locktime & 0xff_ffff);
+            hints.push(format!(
+                "commitment transaction: obscured commitment number {:#x}",
+                obscured
+            ));
+        }
+    }
+
+    for (index, output) in tx.outputs.iter().enumerate() {
+        if output.value_sats == ANCHOR_VALUE_SATS && is_p2wsh(&output.scriptpubkey) {
+            hints.push(format!("output {}: anchor output", index));
+        }
+    }
+
+    for (index, input) in tx.inputs.iter().enumerate() {
+        let Some(script) = witness_script(input.inner_script.as_ref()) else {
+            continue;
+        };
+        let Ok(script) = hex::decode(&script.hex) else {
+            continue;
+        };
+        // Everything on the stack below the witness script
+        let mut args = tx.witness_items(index);
+        args.pop();
+        let has_preimage = args.iter().any(|item| item.len() == 64);
+
+        if matches_shape(&script, TO_LOCAL) {
+            // The OP_IF argument: 1 for the revocation key, empty otherwise
+            let path = match args.last().map(String::as_str) {
+                Some("01") => "revocation",
+                _ => "delayed",
+            };
+            hints.push(format!("input {}: to_local script, {} path", index, path));
+        } else if matches_htlc_shape(&script, OFFERED_HTLC) {
+            let path = if has_preimage { "preimage" } else { "timeout" };
+            hints.push(format!(
+                "input {}: offered HTLC script, {} path",
+                index, path
+            ));
+        } else if matches_htlc_shape(&script, RECEIVED_HTLC) {
+            let path = if has_preimage { "success" } else { "timeout" };
+            hints.push(format!(
+                "input {}: received HTLC script, {} path",
+                index, path
+            ));
+        }
+    }
+
+    hints
+}
+
+fn is_p2wsh(script_pubkey: &str) -> bool {
+    script_pubkey.len() == 68 && script_pubkey.starts_with("0020")
+}
+
+fn witness_script(script: Option<&InnerScript>) -> Option<&InnerScript> {
+    let script = script?;
+    match script.source {
+        InnerScriptSource::WitnessScript => Some(script),
+        InnerScriptSource::RedeemScript => witness_script(script.inner_script.as_deref()),
+    }
+}
+
+fn matches_shape(script: &[u8], shape: &[Shape]) -> bool {
+    let Ok(parsed) = instructions(script).collect::<Result<Vec<_>, _>>() else {
+        return false;
+    };
+    parsed.len() == shape.len()
+        && parsed
+            .iter()
+            .zip(shape)
+            .all(
+                |((_, instruction), expected)| match (instruction, expected) {
+                    (Instruction::Op(op), Op(expected)) => op == expected,
+                    (Instruction::Push { data, .. }, Push(len)) => data.len() == *len,
+                    (Instruction::Push { .. }, Number) => true,
+                    (Instruction::Op(op), Number) => (OP_1..=OP_16).contains(op),
+                    _ => false,
+                },
+            )
+}
+
+// Anchor-output channels add `1 OP_CSV OP_DROP` before the final OP_ENDIF of
+// the HTLC scripts; match either variant.
+fn matches_htlc_shape(script: &[u8], shape: &[Shape]) -> bool {
+    let anchor_csv = [OP_1, OP_CHECKSEQUENCEVERIFY, OP_DROP, OP_ENDIF];
+    match script.strip_suffix(&anchor_csv) {
+        Some(rest) => matches_shape(&[rest, &[OP_ENDIF]].concat(), shape),
+        None => matches_shape(script, shape),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    fn key(byte: u8) -> String {
+        format!("21{}", format!("{:02x}", byte).repeat(33))
+    }
+
+    #[test]
+    fn test_commitment_transaction() {
+        // Shaped after BOLT 3's "simple commitment tx with no HTLCs": same
+        // funding outpoint, sequence, locktime and amounts, with placeholder
+        // output scripts and a 330 sat anchor added. Its obscuring factor is
+        // 0x2bb038521914, so the commitment number is 42.
+        let hex = format!(
+            "02000000000101bef67e4e2fb9ddeeb3461973cd4c62abb35050b1add772995b820b584a48848900000000\
+             0038b02b8003c0c62d0000000000160014{}54a56a0000000000220020{}4a01000000000000220020{}\
+             00\
+             3e195220",
+            "11".repeat(20),
+            "22".repeat(32),
+            "33".repeat(32),
+        );
+        let tx = decode_transaction(&hex).unwrap();
+        let hints = lightning_hints(&tx);
+        assert_eq!(
+            hints,
+            vec![
+                "commitment transaction: obscured commitment number 0x2bb03852193e",
+                "output 2: anchor output",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_local_revocation_spend() {
+        // Penalty spend: <revocation sig> 1 <to_local script>
+        let script = format!("63{}6702d002b275{}68ac", key(0x02), key(0x03));
+        let sig = format!("30{}01", "44".repeat(69));
+        let hex = format!(
+            "02000000000101{}000000000090000000011027000000000000160014{}0347{}0101{:02x}{}00000000",
+            "aa".repeat(32),
+            "11".repeat(20),
+            sig,
+            script.len() / 2,
+            script
+        );
+        let tx = decode_transaction(&hex).unwrap();
+        assert_eq!(
+            lightning_hints(&tx),
+            vec!["input 0: to_local script, revocation path"]
+        );
+    }
+
+    #[test]
+    fn test_offered_htlc_shape() {
+        let offered = format!(
+            "76a914{}8763ac67{}7c820120876475527c{}52ae67a914{}88ac6868",
+            "11".repeat(20),
+            key(0x02),
+            key(0x03),
+            "22".repeat(20)
+        );
+        let script = hex::decode(&offered).unwrap();
+        assert!(matches_htlc_shape(&script, OFFERED_HTLC));
+        assert!(!matches_htlc_shape(&script, RECEIVED_HTLC));
+
+        // The anchors variant
+        let anchored = format!("{}51b27568", &offered[..offered.len() - 2]);
+        assert!(matches_htlc_shape(
+            &hex::decode(anchored).unwrap(),
+            OFFERED_HTLC
+        ));
+    }
+
+    #[test]
+    fn test_no_hints_for_sample() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(lightning_hints(&tx).is_empty());
+    }
+}
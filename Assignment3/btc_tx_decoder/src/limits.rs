@@ -0,0 +1,161 @@
+use crate::error::DecodeError;
+
+// A block's weight limit; a transaction's stripped size times four may not
+// exceed it (CheckTransaction in Bitcoin Core).
+pub const MAX_BLOCK_WEIGHT: usize = 4_000_000;
+
+// The smallest serialized input (outpoint, empty scriptSig, sequence) and
+// output (amount, empty scriptPubKey), in non-witness bytes
+pub(crate) const MIN_INPUT_SIZE: usize = 41;
+pub(crate) const MIN_OUTPUT_SIZE: usize = 9;
+// A witness item is at least its length prefix
+pub(crate) const MIN_WITNESS_ITEM_SIZE: usize = 1;
+
+// Caps on the counts read from the wire, checked before the parser loops over
+// them. The defaults are the most that could fit in a block, so they never
+// reject a transaction consensus would accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    pub max_inputs: usize,
+    pub max_outputs: usize,
+    // Per input
+    pub max_witness_items: usize,
+    // Bytes in a scriptSig, scriptPubKey or witness item
+    pub max_script_len: usize,
+    // For forensics: a scriptSig or scriptPubKey that claims more bytes than
+    // are left is cut short with a warning instead of failing the decode.
+    // The transaction ends there, see wire::parse_body.
+    pub lenient: bool,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            max_inputs: MAX_BLOCK_WEIGHT / 4 / MIN_INPUT_SIZE,
+            max_outputs: MAX_BLOCK_WEIGHT / 4 / MIN_OUTPUT_SIZE,
+            // Witness bytes weigh one unit each, and an item is at least its
+            // one-byte length prefix
+            max_witness_items: MAX_BLOCK_WEIGHT,
+            // A witness item can fill a block on its own
+            max_script_len: MAX_BLOCK_WEIGHT,
+            lenient: false,
+        }
+    }
+}
+
+// Fails when a count read from the wire is over its limit
+pub(crate) fn check_limit(
+    field: &'static str,
+    count: u64,
+    limit: usize,
+) -> Result<(), DecodeError> {
+    if count > limit as u64 {
+        return Err(DecodeError::LimitExceeded {
+            field,
+            count,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+// Fails when `count` elements of at least `min_size` bytes each can't fit in
+// the `available` bytes left, so a bogus count is rejected before anything
+// is allocated or looped over.
+pub(crate) fn check_fits(
+    field: &'static str,
+    count: u64,
+    min_size: usize,
+    available: usize,
+) -> Result<(), DecodeError> {
+    if count.saturating_mul(min_size as u64) > available as u64 {
+        return Err(DecodeError::CountTooLarge {
+            field,
+            count,
+            available,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{decode_transaction_with_limits, BitcoinTransaction};
+
+    fn decode(hex: &str) -> Result<BitcoinTransaction, DecodeError> {
+        decode_transaction_with_limits(hex, &DecodeLimits::default())
+    }
+
+    #[test]
+    fn test_count_must_fit() {
+        // 1000 inputs claimed, one byte of data behind them
+        assert_eq!(
+            decode("02000000fde80300"),
+            Err(DecodeError::CountTooLarge {
+                field: "input count",
+                count: 1000,
+                available: 1
+            })
+        );
+
+        // One empty-scriptSig input, then 0xffff outputs
+        let input = format!("{}0000000000ffffffff", "11".repeat(32));
+        assert_eq!(
+            decode(&format!("0200000001{}fdffff00000000", input)),
+            Err(DecodeError::CountTooLarge {
+                field: "output count",
+                count: 0xffff,
+                available: 4
+            })
+        );
+
+        // A segwit input whose witness claims 10000 items. The txid is all
+        // ff so the legacy reading of 00 01 fails too.
+        let hex = format!(
+            "02000000000101{}0000000000ffffffff00fd102700000000",
+            "ff".repeat(32)
+        );
+        assert_eq!(
+            decode(&hex),
+            Err(DecodeError::CountTooLarge {
+                field: "witness item count",
+                count: 10_000,
+                available: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_script_length_limit() {
+        // An 0xff-prefixed scriptSig length
+        let hex = format!("0200000001{}00000000ff{}", "11".repeat(32), "ff".repeat(8));
+        assert_eq!(
+            decode(&hex),
+            Err(DecodeError::LimitExceeded {
+                field: "script length",
+                count: u64::MAX,
+                limit: MAX_BLOCK_WEIGHT
+            })
+        );
+
+        // A tighter limit rejects a length before the script is read
+        let hex = format!(
+            "0200000001{}00000000fd1027{}",
+            "11".repeat(32),
+            "00".repeat(10)
+        );
+        let limits = DecodeLimits {
+            max_script_len: 9_999,
+            ..DecodeLimits::default()
+        };
+        assert_eq!(
+            decode_transaction_with_limits(&hex, &limits),
+            Err(DecodeError::LimitExceeded {
+                field: "script length",
+                count: 10_000,
+                limit: 9_999
+            })
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::script::{is_witness_program, OP_RETURN};
+use crate::{le_hex_to_u64, BitcoinTransaction, TxOutput};
+
+// 21,000,000 BTC in satoshis
+pub const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
+pub const MAX_SCRIPT_SIG_SIZE: usize = 10_000;
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+pub const MAX_STANDARD_TX_SIZE: usize = 100_000;
+// Bitcoin Core's default -dustrelayfee, in sat/kB
+pub const DUST_RELAY_FEE: u64 = 3000;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "kind", content = "index", rename_all = "lowercase")]
+pub enum LintLocation {
+    Transaction,
+    Input(usize),
+    Output(usize),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    pub id: &'static str,
+    pub message: String,
+    pub location: LintLocation,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            LintLocation::Transaction => write!(f, "[{}] {}", self.id, self.message),
+            LintLocation::Input(i) => write!(f, "[{}] input {}: {}", self.id, i, self.message),
+            LintLocation::Output(i) => write!(f, "[{}] output {}: {}", self.id, i, self.message),
+        }
+    }
+}
+
+impl LintWarning {
+    fn new(id: &'static str, message: String, location: LintLocation) -> Self {
+        LintWarning {
+            id,
+            message,
+            location,
+        }
+    }
+}
+
+// Bitcoin Core's IsDust: the output is worth less than it would cost to spend
+// at the dust relay fee. The spend size is the output itself plus a typical
+// input spending it (148 bytes, or 67 vbytes for witness programs).
+// Unspendable outputs are never dust.
+pub fn is_dust(output: &TxOutput, dust_relay_fee_sat_per_kb: u64) -> bool {
+    dust_threshold(output, dust_relay_fee_sat_per_kb)
+        .is_some_and(|threshold| output.value_sats < threshold)
+}
+
+// The value below which is_dust holds, or None for an unspendable output
+pub fn dust_threshold(output: &TxOutput, dust_relay_fee_sat_per_kb: u64) -> Option<u64> {
+    let script = hex::decode(&output.scriptpubkey).unwrap_or_default();
+    if script.first() == Some(&OP_RETURN) || script.len() > MAX_SCRIPT_SIZE {
+        return None;
+    }
+
+    let output_size = 8 + output.scriptpubkeysize.len() / 2 + script.len();
+    let input_size = if is_witness_program(&script) {
+        32 + 4 + 1 + 107 / 4 + 4
+    } else {
+        32 + 4 + 1 + 107 + 4
+    };
+    let size = (output_size + input_size) as u64;
+    // CFeeRate::GetFee rounds up
+    Some((dust_relay_fee_sat_per_kb * size).div_ceil(1000))
+}
+
+// Flags transactions that decode fine but would be rejected by consensus or
+// standardness rules.
+pub fn lint(tx: &BitcoinTransaction) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+
+    if tx.inputs.is_empty() {
+        warnings.push(LintWarning::new(
+            "no-inputs",
+            "transaction has no inputs".to_string(),
+            LintLocation::Transaction,
+        ));
+    }
+    if tx.outputs.is_empty() {
+        warnings.push(LintWarning::new(
+            "no-outputs",
+            "transaction has no outputs".to_string(),
+            LintLocation::Transaction,
+        ));
+    }
+
+    // Duplicate outpoints
+    let mut seen = HashSet::new();
+    for (i, input) in tx.inputs.iter().enumerate() {
+        if !seen.insert((input.txid.as_str(), input.vout.as_str())) {
+            warnings.push(LintWarning::new(
+                "duplicate-input",
+                format!(
+                    "outpoint {}:{} is spent more than once",
+                    input.txid, input.vout
+                ),
+                LintLocation::Input(i),
+            ));
+        }
+    }
+
+    for (i, input) in tx.inputs.iter().enumerate() {
+        let size = input.scriptsig.len() / 2;
+        if size > MAX_SCRIPT_SIG_SIZE {
+            warnings.push(LintWarning::new(
+                "scriptsig-too-large",
+                format!(
+                    "scriptSig is {} bytes, limit is {}",
+                    size, MAX_SCRIPT_SIG_SIZE
+                ),
+                LintLocation::Input(i),
+            ));
+        }
+    }
+
+    let mut total: u64 = 0;
+    let mut total_overflow = false;
+    for (i, output) in tx.outputs.iter().enumerate() {
+        let Some(value) = le_hex_to_u64(&output.amount) else {
+            continue;
+        };
+        if value > MAX_MONEY {
+            warnings.push(LintWarning::new(
+                "output-value-too-large",
+                format!("output value {} sats exceeds 21,000,000 BTC", value),
+                LintLocation::Output(i),
+            ));
+        }
+        match total.checked_add(value) {
+            Some(sum) => total = sum,
+            None => total_overflow = true,
+        }
+        // Against the amount field, like the checks above
+        if let Some(threshold) =
+            dust_threshold(output, DUST_RELAY_FEE).filter(|&threshold| value < threshold)
+        {
+            warnings.push(LintWarning::new(
+                "dust-output",
+                format!(
+                    "output value {} sats is below the dust threshold of {} sats",
+                    value, threshold
+                ),
+                LintLocation::Output(i),
+            ));
+        }
+    }
+    if total_overflow || total > MAX_MONEY {
+        warnings.push(LintWarning::new(
+            "total-value-too-large",
+            "total output value exceeds 21,000,000 BTC".to_string(),
+            LintLocation::Transaction,
+        ));
+    }
+
+    let size = tx.total_size();
+    if size > MAX_STANDARD_TX_SIZE {
+        warnings.push(LintWarning::new(
+            "tx-too-large",
+            format!(
+                "transaction is {} bytes, standardness limit is {}",
+                size, MAX_STANDARD_TX_SIZE
+            ),
+            LintLocation::Transaction,
+        ));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    fn ids(warnings: &[LintWarning]) -> Vec<&'static str> {
+        warnings.iter().map(|w| w.id).collect()
+    }
+
+    fn amount_hex(sats: u64) -> String {
+        hex::encode(sats.to_le_bytes())
+    }
+
+    #[test]
+    fn test_lint_sample_is_clean() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(lint(&tx).is_empty());
+    }
+
+    #[test]
+    fn test_lint_duplicate_input() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.inputs.push(tx.inputs[0].clone());
+        let warnings = lint(&tx);
+        assert_eq!(ids(&warnings), vec!["duplicate-input"]);
+        assert_eq!(warnings[0].location, LintLocation::Input(1));
+    }
+
+    #[test]
+    fn test_lint_no_inputs_or_outputs() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.inputs.clear();
+        tx.outputs.clear();
+        assert_eq!(ids(&lint(&tx)), vec!["no-inputs", "no-outputs"]);
+    }
+
+    #[test]
+    fn test_lint_money_range() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.outputs[0].amount = amount_hex(MAX_MONEY + 1);
+        assert_eq!(
+            ids(&lint(&tx)),
+            vec!["output-value-too-large", "total-value-too-large"]
+        );
+
+        tx.outputs[0].amount = amount_hex(MAX_MONEY);
+        let warnings = lint(&tx);
+        assert_eq!(ids(&warnings), vec!["total-value-too-large"]);
+        assert_eq!(warnings[0].location, LintLocation::Transaction);
+    }
+
+    #[test]
+    fn test_lint_large_scriptsig() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.inputs[0].scriptsig = "51".repeat(MAX_SCRIPT_SIG_SIZE + 1);
+        let warnings = lint(&tx);
+        assert_eq!(ids(&warnings), vec!["scriptsig-too-large"]);
+        assert_eq!(warnings[0].location, LintLocation::Input(0));
+    }
+
+    #[test]
+    fn test_lint_oversized_transaction() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.outputs[1].scriptpubkey = "6a".repeat(MAX_STANDARD_TX_SIZE);
+        assert_eq!(ids(&lint(&tx)), vec!["tx-too-large"]);
+    }
+
+    #[test]
+    fn test_is_dust_boundaries() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+
+        // P2WPKH: (31 + 67) bytes at 3 sat/vB
+        let p2wpkh = &mut tx.outputs[0];
+        p2wpkh.value_sats = 293;
+        assert!(is_dust(p2wpkh, DUST_RELAY_FEE));
+        p2wpkh.value_sats = 294;
+        assert!(!is_dust(p2wpkh, DUST_RELAY_FEE));
+
+        // P2PKH: (34 + 148) bytes at 3 sat/vB
+        let p2pkh = &mut tx.outputs[1];
+        p2pkh.scriptpubkeysize = "19".to_string();
+        p2pkh.scriptpubkey = "76a91485d78eb795bd9c8a21afefc8b6fdaedf7183680988ac".to_string();
+        p2pkh.value_sats = 545;
+        assert!(is_dust(p2pkh, DUST_RELAY_FEE));
+        p2pkh.value_sats = 546;
+        assert!(!is_dust(p2pkh, DUST_RELAY_FEE));
+        assert!(is_dust(p2pkh, 10_000));
+
+        p2pkh.scriptpubkey = "6a0568656c6c6f".to_string();
+        p2pkh.value_sats = 0;
+        assert!(!is_dust(p2pkh, DUST_RELAY_FEE));
+    }
+
+    #[test]
+    fn test_lint_dust_output() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        // Dust for P2WPKH starts at 294 sats, not the P2PKH 546
+        tx.outputs[1].amount = amount_hex(300);
+        assert!(lint(&tx).is_empty());
+        tx.outputs[1].amount = amount_hex(293);
+        let warnings = lint(&tx);
+        assert_eq!(ids(&warnings), vec!["dust-output"]);
+        assert_eq!(warnings[0].location, LintLocation::Output(1));
+        assert_eq!(
+            warnings[0].message,
+            "output value 293 sats is below the dust threshold of 294 sats"
+        );
+
+        // OP_RETURN outputs are allowed to carry zero value
+        tx.outputs[1].amount = amount_hex(0);
+        tx.outputs[1].scriptpubkey = "6a0568656c6c6f".to_string();
+        assert!(lint(&tx).is_empty());
+    }
+}
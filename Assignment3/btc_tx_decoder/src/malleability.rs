@@ -0,0 +1,158 @@
+use crate::script::{instructions, is_minimal_push, Instruction, OP_0, OP_CHECKMULTISIG};
+use crate::signature::parse_der_signature;
+use crate::BitcoinTransaction;
+
+// Ways a third party could change this transaction's txid without
+// invalidating it. Only scriptSigs are considered: witness data is not part
+// of the txid.
+pub fn malleability_vectors(tx: &BitcoinTransaction) -> Vec<String> {
+    let mut vectors = Vec::new();
+
+    for (i, input) in tx.inputs.iter().enumerate() {
+        let script_sig = hex::decode(&input.scriptsig).unwrap_or_default();
+        let mut pushes = Vec::new();
+        let mut push_only = true;
+
+        for instruction in instructions(&script_sig) {
+            let Ok((offset, instruction)) = instruction else {
+                vectors.push(format!("input {}: scriptSig is malformed", i));
+                break;
+            };
+            match instruction {
+                Instruction::Push { opcode, data } => {
+                    if !is_minimal_push(opcode, data) {
+                        vectors.push(format!(
+                            "input {}: non-minimal push at scriptSig offset {}",
+                            i, offset
+                        ));
+                    }
+                    pushes.push((opcode, data));
+                }
+                Instruction::Op(_) if instruction.is_push() => {}
+                Instruction::Op(opcode) => {
+                    if push_only {
+                        vectors.push(format!(
+                            "input {}: scriptSig is not push-only (opcode 0x{:02x} at offset {})",
+                            i, opcode, offset
+                        ));
+                    }
+                    push_only = false;
+                }
+            }
+        }
+
+        let mut signatures = 0;
+        for (n, (_, data)) in pushes.iter().enumerate() {
+            if let Some(sig) = parse_der_signature(data) {
+                signatures += 1;
+                if !sig.is_low_s() {
+                    vectors.push(format!(
+                        "input {}: signature in push {} has a high S value",
+                        i, n
+                    ));
+                }
+            }
+        }
+
+        // CHECKMULTISIG pops one element more than it uses. The dummy is not
+        // signed, so it can be swapped for anything.
+        if let Some(&(OP_0, _)) = pushes.first() {
+            let redeem_is_multisig = pushes
+                .last()
+                .is_some_and(|(_, data)| data.last() == Some(&OP_CHECKMULTISIG));
+            let bare_multisig = signatures > 0 && signatures == pushes.len() - 1;
+            if redeem_is_multisig || bare_multisig {
+                vectors.push(format!(
+                    "input {}: CHECKMULTISIG dummy element is not covered by signatures",
+                    i
+                ));
+            }
+        }
+    }
+
+    vectors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+    use crate::decode_transaction;
+    use crate::script::{encode_push, OP_1};
+    use crate::tests::SAMPLE_TX;
+
+    // Block 170, spending a P2PK output with a low-S signature
+    const LEGACY_TX: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+    #[test]
+    fn test_malleability_clean() {
+        let tx = decode_transaction(LEGACY_TX).unwrap();
+        assert!(malleability_vectors(&tx).is_empty());
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(malleability_vectors(&tx).is_empty());
+    }
+
+    #[test]
+    fn test_malleability_high_s() {
+        let mut tx = decode_transaction(LEGACY_TX).unwrap();
+        // Same signature with s replaced by n - s
+        tx.inputs[0].scriptsigsize = "49".to_string();
+        tx.inputs[0].scriptsig = "48304502204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd41022100e7eadd137135f821b79f5b5322ed6f6137921779f39c5a19b7b03ce459a9243801".to_string();
+        assert_eq!(
+            malleability_vectors(&tx),
+            vec!["input 0: signature in push 0 has a high S value"]
+        );
+    }
+
+    // A transaction whose only input has `script_sig`
+    fn spending(script_sig: &[u8]) -> BitcoinTransaction {
+        TxBuilder::new()
+            .add_input(
+                "0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9",
+                0,
+            )
+            .script_sig(script_sig)
+            .add_output_sats(1_000, &[0x51])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_malleability_script_shape() {
+        let legacy = decode_transaction(LEGACY_TX).unwrap();
+        let sig = hex::decode(&legacy.inputs[0].scriptsig[2..]).unwrap();
+
+        // OP_PUSHDATA1 where a direct push would do, then OP_DUP
+        let tx = spending(&[&[0x4c, 0x47][..], &sig, &[0x76]].concat());
+        assert_eq!(
+            malleability_vectors(&tx),
+            vec![
+                "input 0: non-minimal push at scriptSig offset 0",
+                "input 0: scriptSig is not push-only (opcode 0x76 at offset 73)",
+            ]
+        );
+
+        // Bare 2-of-n multisig spend: OP_0 <sig> <sig>
+        let sample = decode_transaction(SAMPLE_TX).unwrap();
+        let other_sig = hex::decode(&sample.witness_items(0)[0]).unwrap();
+        let tx = spending(&[vec![OP_0], encode_push(&sig), encode_push(&other_sig)].concat());
+        assert_eq!(
+            malleability_vectors(&tx),
+            vec!["input 0: CHECKMULTISIG dummy element is not covered by signatures"]
+        );
+
+        // P2SH 1-of-1 multisig spend: OP_0 <sig> <OP_1 <pubkey> OP_1 OP_CHECKMULTISIG>
+        let pubkey = hex::decode(&sample.witness_items(0)[1]).unwrap();
+        let redeem = [
+            vec![OP_1],
+            encode_push(&pubkey),
+            vec![OP_1, OP_CHECKMULTISIG],
+        ]
+        .concat();
+        let tx = spending(&[vec![OP_0], encode_push(&other_sig), encode_push(&redeem)].concat());
+        assert_eq!(
+            malleability_vectors(&tx),
+            vec!["input 0: CHECKMULTISIG dummy element is not covered by signatures"]
+        );
+    }
+}
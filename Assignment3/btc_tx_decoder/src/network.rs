@@ -0,0 +1,96 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+// The chain a transaction is assumed to be on. The wire format is the same
+// everywhere; only addresses differ, by base58 version byte and bech32 HRP.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet3,
+    Testnet4,
+    Signet,
+    Regtest,
+    // Liquid mainnet, unconfidential addresses only. The transaction is
+    // still read in Bitcoin's format, Elements' isn't decoded
+    Liquid,
+}
+
+impl Network {
+    pub const ALL: [Network; 6] = [
+        Network::Mainnet,
+        Network::Testnet3,
+        Network::Testnet4,
+        Network::Signet,
+        Network::Regtest,
+        Network::Liquid,
+    ];
+
+    // Testnet4 and signet share testnet3's prefixes
+    pub fn bech32_hrp(self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet3 | Network::Testnet4 | Network::Signet => "tb",
+            Network::Regtest => "bcrt",
+            Network::Liquid => "ex",
+        }
+    }
+
+    pub fn p2pkh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet3 | Network::Testnet4 | Network::Signet | Network::Regtest => 0x6f,
+            Network::Liquid => 0x39,
+        }
+    }
+
+    pub fn p2sh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x05,
+            Network::Testnet3 | Network::Testnet4 | Network::Signet | Network::Regtest => 0xc4,
+            Network::Liquid => 0x27,
+        }
+    }
+
+    // The serialized name, e.g. "testnet4"
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet3 => "testnet3",
+            Network::Testnet4 => "testnet4",
+            Network::Signet => "signet",
+            Network::Regtest => "regtest",
+            Network::Liquid => "liquid",
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Also takes Bitcoin Core's -chain names: main, test and testnet for
+// testnet3, and liquidv1
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "mainnet" | "main" | "bitcoin" => Ok(Network::Mainnet),
+            "testnet3" | "testnet" | "test" => Ok(Network::Testnet3),
+            "testnet4" => Ok(Network::Testnet4),
+            "signet" => Ok(Network::Signet),
+            "regtest" => Ok(Network::Regtest),
+            "liquid" | "liquidv1" => Ok(Network::Liquid),
+            _ => Err(format!(
+                "unknown network: {} (expected mainnet, testnet3, testnet4, signet, regtest or liquid)",
+                s
+            )),
+        }
+    }
+}
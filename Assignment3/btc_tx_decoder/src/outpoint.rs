@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::error::DecodeError;
+use crate::{le_hex_to_u64, BitcoinTransaction};
+
+// A reference to a transaction output. The txid is in display order, as
+// block explorers show it, not the byte-reversed order of the wire.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OutPoint {
+    pub txid: String,
+    pub vout: u32,
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+// The outputs the transaction's inputs spend, in input order
+pub fn outpoints_spent(tx: &BitcoinTransaction) -> Vec<OutPoint> {
+    tx.iter_inputs()
+        .map(|input| {
+            let mut txid = hex::decode(&input.txid).unwrap_or_default();
+            txid.reverse();
+            OutPoint {
+                txid: hex::encode(txid),
+                vout: le_hex_to_u64(&input.vout).unwrap_or_default() as u32,
+            }
+        })
+        .collect()
+}
+
+// The outputs the transaction creates, in output order. Fails for a truncated transaction, which has no txid.
+pub fn outpoints_created(tx: &BitcoinTransaction) -> Result<Vec<OutPoint>, DecodeError> {
+    let txid = tx.txid()?;
+    Ok((0..tx.outputs.len() as u32)
+        .map(|vout| OutPoint {
+            txid: txid.clone(),
+            vout,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_outpoints() {
+        // As mempool.space shows the sample: it spends output 1 of
+        // c1368b8e...8131 and creates two outputs of 04f487fe...5842
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let spent: Vec<String> = outpoints_spent(&tx).iter().map(|o| o.to_string()).collect();
+        assert_eq!(
+            spent,
+            vec!["c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131:1"]
+        );
+
+        let txid = "04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842";
+        assert_eq!(
+            outpoints_created(&tx).unwrap(),
+            vec![
+                OutPoint {
+                    txid: txid.to_string(),
+                    vout: 0
+                },
+                OutPoint {
+                    txid: txid.to_string(),
+                    vout: 1
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::address::address_on;
+use crate::classify::{classify_script, ClassifyOptions};
+use crate::{le_hex_to_u64, BitcoinTransaction};
+
+// Bold cyan labels, then back to normal
+const LABEL_START: &str = "\x1b[1;36m";
+const LABEL_END: &str = "\x1b[0m";
+const LABEL_WIDTH: usize = 10;
+
+// A compact summary, one labelled line per field: txid, version, inputs as
+// prevout txid:vout, outputs as value and address, locktime.
+pub fn format_pretty(tx: &BitcoinTransaction) -> String {
+    format_pretty_with(tx, false)
+}
+
+// format_pretty with the labels in color, for terminals
+pub fn format_pretty_with(tx: &BitcoinTransaction, color: bool) -> String {
+    let mut lines = Vec::new();
+    let mut line = |label: String, value: String| {
+        let padded = format!("{:<width$}", label, width = LABEL_WIDTH);
+        if color {
+            lines.push(format!("{}{}{}{}", LABEL_START, padded, LABEL_END, value));
+        } else {
+            lines.push(format!("{}{}", padded, value));
+        }
+    };
+    let number = |field: &str| le_hex_to_u64(field).unwrap_or_default();
+
+    line(
+        "txid".to_string(),
+        tx.txid()
+            .unwrap_or_else(|_| "none, the transaction is truncated".to_string()),
+    );
+    line(
+        "version".to_string(),
+        (number(&tx.version) as u32 as i32).to_string(),
+    );
+    for (i, input) in tx.inputs.iter().enumerate() {
+        let mut txid = hex::decode(&input.txid).unwrap_or_default();
+        txid.reverse();
+        line(
+            format!("input {}", i),
+            format!("{}:{}", hex::encode(txid), number(&input.vout)),
+        );
+    }
+    for (i, output) in tx.outputs.iter().enumerate() {
+        let script = hex::decode(&output.scriptpubkey).unwrap_or_default();
+        let destination = address_on(&script, tx.network).unwrap_or_else(|| {
+            let script_type = classify_script(&script, &[], &ClassifyOptions::default());
+            format!("({})", script_type.label())
+        });
+        line(
+            format!("output {}", i),
+            format!(
+                "{}.{:08} BTC  {}",
+                output.value_sats / 100_000_000,
+                output.value_sats % 100_000_000,
+                destination
+            ),
+        );
+    }
+    line(
+        "locktime".to_string(),
+        if tx.truncated {
+            "missing, the transaction is truncated".to_string()
+        } else {
+            number(&tx.locktime).to_string()
+        },
+    );
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_format_pretty() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(
+            format_pretty(&tx),
+            "\
+txid      04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842
+version   2
+input 0   c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131:1
+output 0  0.00500000 BTC  bc1qshtcadu4hkwg5gd0alytdldwmaccx6qfh9fg9u
+output 1  0.01050700 BTC  bc1qss9tzewfcf246j33hysg4kqxlzwj2d0zn4wd90
+locktime  918339
+"
+        );
+
+        let colored = format_pretty_with(&tx, true);
+        assert!(colored.starts_with("\x1b[1;36mtxid      \x1b[0m04f487fe"));
+        assert_eq!(colored.matches(LABEL_START).count(), 6);
+    }
+}
@@ -0,0 +1,506 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+
+use crate::error::DecodeError;
+use crate::wire::has_segwit_marker;
+use crate::{
+    parse_output, parse_transaction, parse_transaction_legacy, read_compact_size,
+    BitcoinTransaction, TxOutput,
+};
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+const PSBT_MAGIC_HEX: &str = "70736274ff";
+const PSBT_MAGIC_BASE64: &str = "cHNidP8";
+
+// Global key types
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+
+// Input key types
+const PSBT_IN_NON_WITNESS_UTXO: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_REDEEM_SCRIPT: u8 = 0x04;
+const PSBT_IN_WITNESS_SCRIPT: u8 = 0x05;
+const PSBT_IN_BIP32_DERIVATION: u8 = 0x06;
+const PSBT_IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const PSBT_IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+
+// Output key types
+const PSBT_OUT_REDEEM_SCRIPT: u8 = 0x00;
+const PSBT_OUT_WITNESS_SCRIPT: u8 = 0x01;
+const PSBT_OUT_BIP32_DERIVATION: u8 = 0x02;
+
+// Raw (key, value) pairs of one PSBT map, in serialization order
+type KeyValueMap = Vec<(Vec<u8>, Vec<u8>)>;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PsbtKeyValue {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PartialSig {
+    pub pubkey: String,
+    pub signature: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Bip32Derivation {
+    pub pubkey: String,
+    pub fingerprint: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct PsbtInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_witness_utxo: Option<BitcoinTransaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_utxo: Option<TxOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub partial_sigs: Vec<PartialSig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sighash_type: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_script: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bip32_derivations: Vec<Bip32Derivation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_scriptsig: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_scriptwitness: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown: Vec<PsbtKeyValue>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct PsbtOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_script: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bip32_derivations: Vec<Bip32Derivation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown: Vec<PsbtKeyValue>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Psbt {
+    pub unsigned_tx: BitcoinTransaction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown: Vec<PsbtKeyValue>,
+    pub inputs: Vec<PsbtInput>,
+    pub outputs: Vec<PsbtOutput>,
+}
+
+// True when the input starts with the PSBT magic, either hex or base64 encoded.
+pub fn is_psbt(input: &str) -> bool {
+    let input = input.trim_start();
+    input.starts_with(PSBT_MAGIC_BASE64)
+        || input
+            .get(..PSBT_MAGIC_HEX.len())
+            .is_some_and(|magic| magic.eq_ignore_ascii_case(PSBT_MAGIC_HEX))
+}
+
+// Decode a PSBT given as hex or base64.
+pub fn decode_psbt(input: &str) -> Result<Psbt, DecodeError> {
+    let input: String = input.split_whitespace().collect();
+
+    let bytes = match hex::decode(&input) {
+        Ok(bytes) => bytes,
+        Err(_) => BASE64
+            .decode(&input)
+            .map_err(|e| DecodeError::InvalidPsbt(format!("neither hex nor base64 ({})", e)))?,
+    };
+
+    if !bytes.starts_with(PSBT_MAGIC) {
+        return Err(invalid("missing psbt magic bytes"));
+    }
+    let mut pos = PSBT_MAGIC.len();
+
+    // Global map
+    let (global, size) = read_map(&bytes, pos)?;
+    pos += size;
+
+    let mut unsigned_tx = None;
+    let mut version = None;
+    let mut unknown = Vec::new();
+    for (key, value) in &global {
+        match (key[0], key.len()) {
+            (PSBT_GLOBAL_UNSIGNED_TX, 1) => unsigned_tx = Some(parse_unsigned_tx(value)?),
+            (PSBT_GLOBAL_VERSION, 1) => version = Some(read_u32(value, "version")?),
+            _ => unknown.push(to_key_value(key, value)),
+        }
+    }
+    let unsigned_tx = unsigned_tx.ok_or_else(|| invalid("missing unsigned transaction"))?;
+
+    // One map per input, then one per output
+    let mut inputs = Vec::new();
+    for _ in 0..unsigned_tx.inputs.len() {
+        let (map, size) = read_map(&bytes, pos)?;
+        pos += size;
+        inputs.push(parse_input_map(&map)?);
+    }
+
+    let mut outputs = Vec::new();
+    for _ in 0..unsigned_tx.outputs.len() {
+        let (map, size) = read_map(&bytes, pos)?;
+        pos += size;
+        outputs.push(parse_output_map(&map)?);
+    }
+
+    Ok(Psbt {
+        unsigned_tx,
+        version,
+        unknown,
+        inputs,
+        outputs,
+    })
+}
+
+fn parse_input_map(map: &KeyValueMap) -> Result<PsbtInput, DecodeError> {
+    let mut input = PsbtInput::default();
+
+    for (key, value) in map {
+        let key_data = &key[1..];
+        match (key[0], key_data.is_empty()) {
+            (PSBT_IN_NON_WITNESS_UTXO, true) => {
+                // The whole previous transaction, witness and all
+                let tx = parse_transaction(value).map_err(DecodeError::InvalidTransaction)?;
+                if tx.total_size() != value.len() {
+                    return Err(invalid("trailing bytes after the non-witness utxo"));
+                }
+                input.non_witness_utxo = Some(tx);
+            }
+            (PSBT_IN_WITNESS_UTXO, true) => {
+                let (utxo, size) =
+                    parse_output(value, 0).map_err(|e| DecodeError::InvalidPsbt(e.to_string()))?;
+                if size != value.len() {
+                    return Err(invalid("malformed witness utxo"));
+                }
+                input.witness_utxo = Some(utxo);
+            }
+            (PSBT_IN_PARTIAL_SIG, false) => input.partial_sigs.push(PartialSig {
+                pubkey: hex::encode(key_data),
+                signature: hex::encode(value),
+            }),
+            (PSBT_IN_SIGHASH_TYPE, true) => {
+                input.sighash_type = Some(read_u32(value, "sighash type")?)
+            }
+            (PSBT_IN_REDEEM_SCRIPT, true) => input.redeem_script = Some(hex::encode(value)),
+            (PSBT_IN_WITNESS_SCRIPT, true) => input.witness_script = Some(hex::encode(value)),
+            (PSBT_IN_BIP32_DERIVATION, false) => input
+                .bip32_derivations
+                .push(parse_bip32_derivation(key_data, value)?),
+            (PSBT_IN_FINAL_SCRIPTSIG, true) => input.final_scriptsig = Some(hex::encode(value)),
+            (PSBT_IN_FINAL_SCRIPTWITNESS, true) => {
+                input.final_scriptwitness = Some(parse_witness_stack(value)?)
+            }
+            _ => input.unknown.push(to_key_value(key, value)),
+        }
+    }
+
+    Ok(input)
+}
+
+// BIP174: the unsigned transaction is in the non-witness serialization,
+// with empty scriptSigs
+fn parse_unsigned_tx(value: &[u8]) -> Result<BitcoinTransaction, DecodeError> {
+    let not_legacy = || invalid("unsigned transaction must use the non-witness serialization");
+    let (tx, size) = match parse_transaction_legacy(value) {
+        Ok(parsed) => parsed,
+        Err(_) if has_segwit_marker(value) => return Err(not_legacy()),
+        Err(e) => return Err(e),
+    };
+    if size != value.len() {
+        return Err(not_legacy());
+    }
+    if let Some(i) = tx
+        .inputs
+        .iter()
+        .position(|input| !input.scriptsig.is_empty())
+    {
+        return Err(invalid(&format!(
+            "unsigned transaction has a scriptSig in input {}",
+            i
+        )));
+    }
+    Ok(tx)
+}
+
+fn parse_output_map(map: &KeyValueMap) -> Result<PsbtOutput, DecodeError> {
+    let mut output = PsbtOutput::default();
+
+    for (key, value) in map {
+        let key_data = &key[1..];
+        match (key[0], key_data.is_empty()) {
+            (PSBT_OUT_REDEEM_SCRIPT, true) => output.redeem_script = Some(hex::encode(value)),
+            (PSBT_OUT_WITNESS_SCRIPT, true) => output.witness_script = Some(hex::encode(value)),
+            (PSBT_OUT_BIP32_DERIVATION, false) => output
+                .bip32_derivations
+                .push(parse_bip32_derivation(key_data, value)?),
+            _ => output.unknown.push(to_key_value(key, value)),
+        }
+    }
+
+    Ok(output)
+}
+
+// Value is a 4-byte master key fingerprint followed by the path as
+// little-endian u32 indices.
+fn parse_bip32_derivation(pubkey: &[u8], value: &[u8]) -> Result<Bip32Derivation, DecodeError> {
+    if value.len() < 4 || !value.len().is_multiple_of(4) {
+        return Err(invalid("malformed bip32 derivation"));
+    }
+
+    let mut path = String::from("m");
+    for index in value[4..].chunks(4) {
+        let index = u32::from_le_bytes([index[0], index[1], index[2], index[3]]);
+        if index >= 0x8000_0000 {
+            path.push_str(&format!("/{}'", index - 0x8000_0000));
+        } else {
+            path.push_str(&format!("/{}", index));
+        }
+    }
+
+    Ok(Bip32Derivation {
+        pubkey: hex::encode(pubkey),
+        fingerprint: hex::encode(&value[..4]),
+        path,
+    })
+}
+
+fn parse_witness_stack(value: &[u8]) -> Result<Vec<String>, DecodeError> {
+    let (count, mut pos) = read_compact_size(value, 0).map_err(DecodeError::InvalidPsbt)?;
+    let mut items = Vec::new();
+    for _ in 0..count {
+        let (len, size) = read_compact_size(value, pos).map_err(DecodeError::InvalidPsbt)?;
+        pos += size;
+        if len > value.len() - pos {
+            return Err(invalid("malformed final script witness"));
+        }
+        items.push(hex::encode(&value[pos..pos + len]));
+        pos += len;
+    }
+    Ok(items)
+}
+
+// Read a key-value map terminated by a zero-length key. Returns the raw pairs
+// and the number of bytes consumed, including the terminator. Keys are
+// unique within a map.
+fn read_map(bytes: &[u8], pos: usize) -> Result<(KeyValueMap, usize), DecodeError> {
+    let mut offset = pos;
+    let mut map = Vec::new();
+
+    loop {
+        let (key_len, len_size) =
+            read_compact_size(bytes, offset).map_err(DecodeError::InvalidPsbt)?;
+        offset += len_size;
+        if key_len == 0 {
+            break;
+        }
+        if key_len > bytes.len() - offset {
+            return Err(invalid("key too short"));
+        }
+        let key = bytes[offset..offset + key_len].to_vec();
+        offset += key_len;
+        if map.iter().any(|(existing, _)| *existing == key) {
+            return Err(invalid(&format!("duplicate key {}", hex::encode(&key))));
+        }
+
+        let (value_len, len_size) =
+            read_compact_size(bytes, offset).map_err(DecodeError::InvalidPsbt)?;
+        offset += len_size;
+        if value_len > bytes.len() - offset {
+            return Err(invalid("value too short"));
+        }
+        let value = bytes[offset..offset + value_len].to_vec();
+        offset += value_len;
+
+        map.push((key, value));
+    }
+
+    Ok((map, offset - pos))
+}
+
+fn read_u32(value: &[u8], field: &str) -> Result<u32, DecodeError> {
+    let bytes: [u8; 4] = value
+        .try_into()
+        .map_err(|_| invalid(&format!("{} must be 4 bytes", field)))?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn to_key_value(key: &[u8], value: &[u8]) -> PsbtKeyValue {
+    PsbtKeyValue {
+        key: hex::encode(key),
+        value: hex::encode(value),
+    }
+}
+
+fn invalid(message: &str) -> DecodeError {
+    DecodeError::InvalidPsbt(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP174 test vector: "PSBT with one P2PKH input. Outputs are empty"
+    const BIP174_ONE_P2PKH_INPUT: &str = "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA";
+
+    const UNSIGNED_TX: &str = "020000000131811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c10100000000fdffffff0120a107000000000016001485d78eb795bd9c8a21afefc8b6fdaedf7183680943030e00";
+    const WITNESS_UTXO: &str = "a0860100000000001600148d7a0a3461e3891723e5fdf8129caa0075060cff";
+    const PUBKEY: &str = "0260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff";
+    const SIGNATURE: &str = "304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01";
+
+    // psbt magic, global map with the unsigned tx, one input map with a
+    // witness utxo, a partial signature, a sighash type, a bip32 derivation
+    // and an unknown proprietary key, and an empty output map.
+    fn minimal_psbt() -> String {
+        format!(
+            "70736274ff0100{:02x}{}00\
+             0101{:02x}{}2202{}{:02x}{}\
+             01030401000000\
+             2206{}18d90c6a4f2c00008000000080000000800000000000000000\
+             02fc0102ab0000\
+             00",
+            UNSIGNED_TX.len() / 2,
+            UNSIGNED_TX,
+            WITNESS_UTXO.len() / 2,
+            WITNESS_UTXO,
+            PUBKEY,
+            SIGNATURE.len() / 2,
+            SIGNATURE,
+            PUBKEY,
+        )
+    }
+
+    #[test]
+    fn test_decode_psbt_bip174_vector() {
+        assert!(is_psbt(BIP174_ONE_P2PKH_INPUT));
+        let psbt = decode_psbt(BIP174_ONE_P2PKH_INPUT).unwrap();
+
+        assert_eq!(psbt.unsigned_tx.version, "02000000");
+        assert_eq!(
+            psbt.unsigned_tx.inputs[0].txid,
+            "268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf6"
+        );
+        assert_eq!(psbt.unsigned_tx.locktime, "b32e1300");
+        assert_eq!(psbt.inputs.len(), 1);
+        assert_eq!(psbt.outputs, vec![PsbtOutput::default(); 2]);
+
+        // The full previous transaction is carried for the legacy input
+        let prev = psbt.inputs[0].non_witness_utxo.as_ref().unwrap();
+        assert_eq!(prev.inputs.len(), 2);
+        assert_eq!(prev.outputs[0].amount, "00c2eb0b00000000");
+        assert!(psbt.inputs[0].witness_utxo.is_none());
+        assert!(psbt.inputs[0].unknown.is_empty());
+    }
+
+    #[test]
+    fn test_decode_psbt_named_keys() {
+        let psbt = decode_psbt(&minimal_psbt()).unwrap();
+        let input = &psbt.inputs[0];
+
+        let utxo = input.witness_utxo.as_ref().unwrap();
+        assert_eq!(utxo.amount, "a086010000000000");
+        assert_eq!(
+            utxo.scriptpubkey,
+            "00148d7a0a3461e3891723e5fdf8129caa0075060cff"
+        );
+        assert_eq!(
+            input.partial_sigs,
+            vec![PartialSig {
+                pubkey: PUBKEY.to_string(),
+                signature: SIGNATURE.to_string(),
+            }]
+        );
+        assert_eq!(input.sighash_type, Some(1));
+        assert_eq!(
+            input.bip32_derivations,
+            vec![Bip32Derivation {
+                pubkey: PUBKEY.to_string(),
+                fingerprint: "d90c6a4f".to_string(),
+                path: "m/44'/0'/0'/0/0".to_string(),
+            }]
+        );
+        assert_eq!(
+            input.unknown,
+            vec![PsbtKeyValue {
+                key: "fc01".to_string(),
+                value: "ab00".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_psbt_hex_and_base64_agree() {
+        let bytes = hex::decode(minimal_psbt()).unwrap();
+        let encoded = BASE64.encode(bytes);
+        assert!(is_psbt(&encoded));
+        assert!(is_psbt(&minimal_psbt()));
+        assert_eq!(
+            decode_psbt(&encoded).unwrap(),
+            decode_psbt(&minimal_psbt()).unwrap()
+        );
+    }
+
+    // A PSBT whose global map holds just `unsigned_tx`, with empty input and
+    // output maps
+    fn psbt_for(unsigned_tx: &str, maps: usize) -> String {
+        format!(
+            "70736274ff0100{}{}00{}",
+            hex::encode(crate::CompactSize::encode(unsigned_tx.len() as u64 / 2)),
+            unsigned_tx,
+            "00".repeat(maps)
+        )
+    }
+
+    #[test]
+    fn test_decode_psbt_unsigned_tx_rules() {
+        assert!(decode_psbt(&psbt_for(UNSIGNED_TX, 2)).is_ok());
+
+        // Witness serialization, with the witness still in it
+        assert_eq!(
+            decode_psbt(&psbt_for(crate::tests::SAMPLE_TX, 3)),
+            Err(invalid(
+                "unsigned transaction must use the non-witness serialization"
+            ))
+        );
+
+        // A scriptSig: OP_TRUE
+        let signed = UNSIGNED_TX.replacen("0100000000fdffffff", "010000000151fdffffff", 1);
+        assert_eq!(
+            decode_psbt(&psbt_for(&signed, 2)),
+            Err(invalid("unsigned transaction has a scriptSig in input 0"))
+        );
+    }
+
+    #[test]
+    fn test_decode_psbt_duplicate_key() {
+        let psbt = psbt_for(UNSIGNED_TX, 2);
+        let global = format!("0100{:02x}{}", UNSIGNED_TX.len() / 2, UNSIGNED_TX);
+        let duplicated = psbt.replacen(&global, &format!("{}{}", global, global), 1);
+        assert_eq!(decode_psbt(&duplicated), Err(invalid("duplicate key 00")));
+    }
+
+    #[test]
+    fn test_decode_psbt_bad_magic() {
+        assert!(!is_psbt("70736274fe0000"));
+        assert_eq!(
+            decode_psbt("70736274fe0000"),
+            Err(DecodeError::InvalidPsbt(
+                "missing psbt magic bytes".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use serde::Serialize;
+
+use crate::hashes::hash160;
+use crate::inner::InnerScriptSource;
+use crate::script::{instructions, is_witness_program, Instruction, ScriptContext};
+use crate::signature::parse_der_signature;
+use crate::BitcoinTransaction;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PubkeyFormat {
+    // 33 bytes, 02/03 prefix
+    Compressed,
+    // 65 bytes, 04 prefix
+    Uncompressed,
+    // 32 bytes, BIP340
+    XOnly,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    // Found where a standard script template puts a key
+    Likely,
+    // Only the length and prefix fit; could be any data
+    Possible,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FoundPubkey {
+    pub location: ScriptContext,
+    // Where within the script, e.g. "witness item 1", "p2pk output"
+    pub role: String,
+    pub pubkey: String,
+    pub format: PubkeyFormat,
+    // For matching against P2PKH/P2WPKH outputs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash160: Option<String>,
+    pub confidence: Confidence,
+}
+
+// Public keys in the transaction's scriptSigs, scriptPubKeys, witness items
+// and any redeem/witness script revealed by an input.
+pub fn find_pubkeys(tx: &BitcoinTransaction) -> Vec<FoundPubkey> {
+    let mut found = Vec::new();
+
+    for (index, input) in tx.inputs.iter().enumerate() {
+        let location = ScriptContext::InputScriptSig { index };
+        let script_sig = hex::decode(&input.scriptsig).unwrap_or_default();
+        let sig_pushes = pushes(&script_sig);
+        // P2PKH: <sig> <pubkey>
+        let p2pkh = sig_pushes.len() == 2 && parse_der_signature(sig_pushes[0]).is_some();
+        for (n, data) in sig_pushes.iter().enumerate() {
+            let role = format!("scriptSig push {}", n);
+            found.extend(recognize(location, role, data, p2pkh && n == 1));
+        }
+
+        let mut script = input.inner_script.as_ref();
+        while let Some(inner) = script {
+            let bytes = hex::decode(&inner.hex).unwrap_or_default();
+            let source = match inner.source {
+                InnerScriptSource::RedeemScript => "redeem script",
+                InnerScriptSource::WitnessScript => "witness script",
+            };
+            let multisig = inner.patterns.iter().any(|p| p == "multisig");
+            for (n, data) in pushes(&bytes).iter().enumerate() {
+                let role = format!("{} push {}", source, n);
+                found.extend(recognize(location, role, data, multisig));
+            }
+            script = inner.inner_script.as_deref();
+        }
+    }
+
+    for (index, output) in tx.outputs.iter().enumerate() {
+        let location = ScriptContext::OutputScriptPubkey { index };
+        let script = hex::decode(&output.scriptpubkey).unwrap_or_default();
+
+        if let [0x51, 0x20, key @ ..] = script.as_slice() {
+            if key.len() == 32 {
+                found.extend(recognize(location, "p2tr output".to_string(), key, true));
+                continue;
+            }
+        }
+        // Other witness programs hold hashes, not keys
+        if is_witness_program(&script) {
+            continue;
+        }
+
+        let key_pushes = pushes(&script);
+        let p2pk = matches!(script.last(), Some(0xac)) && key_pushes.len() == 1;
+        let multisig = matches!(script.last(), Some(0xae));
+        for (n, data) in key_pushes.iter().enumerate() {
+            let role = if p2pk {
+                "p2pk output".to_string()
+            } else if multisig {
+                format!("multisig output key {}", n)
+            } else {
+                format!("scriptPubKey push {}", n)
+            };
+            found.extend(recognize(location, role, data, p2pk || multisig));
+        }
+    }
+
+    for input in 0..tx.inputs.len() {
+        let items: Vec<Vec<u8>> = tx
+            .witness_items(input)
+            .iter()
+            .map(|item| hex::decode(item).unwrap_or_default())
+            .collect();
+        // P2WPKH: <sig> <pubkey>
+        let p2wpkh = items.len() == 2 && parse_der_signature(&items[0]).is_some();
+        for (item, data) in items.iter().enumerate() {
+            let location = ScriptContext::WitnessItem { input, item };
+            let role = format!("witness item {}", item);
+            found.extend(recognize(location, role, data, p2wpkh && item == 1));
+        }
+    }
+
+    found
+}
+
+fn recognize(
+    location: ScriptContext,
+    role: String,
+    data: &[u8],
+    expected_here: bool,
+) -> Option<FoundPubkey> {
+    let format = match (data.len(), data.first()) {
+        (33, Some(0x02 | 0x03)) => PubkeyFormat::Compressed,
+        (65, Some(0x04)) => PubkeyFormat::Uncompressed,
+        (32, _) => PubkeyFormat::XOnly,
+        _ => return None,
+    };
+    let hash160 = match format {
+        PubkeyFormat::XOnly => None,
+        _ => Some(hex::encode(hash160(data))),
+    };
+
+    Some(FoundPubkey {
+        location,
+        role,
+        pubkey: hex::encode(data),
+        format,
+        hash160,
+        confidence: if expected_here {
+            Confidence::Likely
+        } else {
+            Confidence::Possible
+        },
+    })
+}
+
+// Data pushes of a script, stopping at the first malformed push
+fn pushes(script: &[u8]) -> Vec<&[u8]> {
+    instructions(script)
+        .map_while(Result::ok)
+        .filter_map(|(_, instruction)| match instruction {
+            Instruction::Push { data, .. } => Some(data),
+            Instruction::Op(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_find_pubkeys_sample_witness() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let found = find_pubkeys(&tx);
+
+        assert_eq!(found.len(), 1);
+        let key = &found[0];
+        assert_eq!(
+            key.location,
+            ScriptContext::WitnessItem { input: 0, item: 1 }
+        );
+        assert_eq!(key.role, "witness item 1");
+        assert_eq!(key.format, PubkeyFormat::Compressed);
+        assert_eq!(key.confidence, Confidence::Likely);
+        assert_eq!(
+            key.hash160.as_deref(),
+            Some("d28d3a4ac0d0a10fa93a99d7ae953b8f56433e1e")
+        );
+    }
+
+    #[test]
+    fn test_find_pubkeys_p2pk_outputs() {
+        // Block 170: P2PK outputs with uncompressed keys
+        let tx = decode_transaction("0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000").unwrap();
+        let found = find_pubkeys(&tx);
+
+        assert_eq!(found.len(), 2);
+        assert!(found
+            .iter()
+            .all(|k| k.role == "p2pk output" && k.confidence == Confidence::Likely));
+        assert_eq!(found[0].format, PubkeyFormat::Uncompressed);
+        assert_eq!(
+            found[0].hash160.as_deref(),
+            Some("fc916f213a3d7f1369313d5fa30f6168f9446a2d")
+        );
+    }
+
+    #[test]
+    fn test_random_data_is_possible() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        // A 33-byte push with a key prefix in an OP_RETURN output
+        tx.outputs[1].scriptpubkey = format!("6a21{}", "03".repeat(33));
+        let found = find_pubkeys(&tx);
+        let op_return = found
+            .iter()
+            .find(|k| k.location == ScriptContext::OutputScriptPubkey { index: 1 })
+            .unwrap();
+        assert_eq!(op_return.confidence, Confidence::Possible);
+    }
+}
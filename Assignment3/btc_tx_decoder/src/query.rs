@@ -0,0 +1,84 @@
+use serde_json::Value;
+
+use crate::address::address_on;
+use crate::BitcoinTransaction;
+
+// One field of the transaction's JSON by a dotted path, array elements by
+// index: `inputs.0.sequence`, `outputs.1.value_sats`, `witness.0.items.1.item`.
+// `txid`, `size`, `vsize` and `weight` are computed on top of the JSON, and
+// `outputs.N.address` recomputed for the transaction's network. Strings come
+// back without quotes and anything else as compact JSON; None when nothing is
+// at the path.
+pub fn query(tx: &BitcoinTransaction, path: &str) -> Option<String> {
+    let mut value = serde_json::to_value(tx).ok()?;
+    // A truncated transaction has no txid, so nothing at that path
+    if let Ok(txid) = tx.txid() {
+        value["txid"] = txid.into();
+    }
+    value["size"] = tx.total_size().into();
+    value["vsize"] = tx.vsize().into();
+    value["weight"] = tx.weight().into();
+    if let Some(outputs) = value["outputs"].as_array_mut() {
+        for (output, tx_output) in outputs.iter_mut().zip(&tx.outputs) {
+            let script = hex::decode(&tx_output.scriptpubkey).unwrap_or_default();
+            let Some(output) = output.as_object_mut() else {
+                continue;
+            };
+            // A script with no address has nothing at the path, not null
+            match address_on(&script, tx.network) {
+                Some(address) => output.insert("address".to_string(), address.into()),
+                None => output.remove("address"),
+            };
+        }
+    }
+
+    let mut field = &value;
+    for key in path.split('.') {
+        field = match field {
+            Value::Object(map) => map.get(key)?,
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    match field {
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_query() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let q = |path| query(&tx, path);
+
+        assert_eq!(
+            q("txid").unwrap(),
+            "04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842"
+        );
+        assert_eq!(q("inputs.0.sequence").unwrap(), "fdffffff");
+        assert_eq!(q("outputs.1.value_sats").unwrap(), "1050700");
+        assert_eq!(
+            q("outputs.1.address").unwrap(),
+            "bc1qss9tzewfcf246j33hysg4kqxlzwj2d0zn4wd90"
+        );
+        assert_eq!(q("vsize").unwrap(), "141");
+        assert_eq!(q("rbf_signaled").unwrap(), "true");
+        assert_eq!(q("witness.0.items.1.size").unwrap(), "21");
+        assert_eq!(q("input_kind_counts").unwrap(), r#"{"native-segwit-v0":1}"#);
+    }
+
+    #[test]
+    fn test_query_missing() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert_eq!(query(&tx, "outputs.2.value_sats"), None);
+        assert_eq!(query(&tx, "inputs.first.txid"), None);
+        assert_eq!(query(&tx, "txid.0"), None);
+        assert_eq!(query(&tx, ""), None);
+    }
+}
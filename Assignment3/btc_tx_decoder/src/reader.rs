@@ -0,0 +1,200 @@
+use std::io::Read;
+
+use crate::error::DecodeError;
+use crate::limits::DecodeLimits;
+use crate::wire::{self, ByteSource};
+use crate::{from_raw, BitcoinTransaction, AMBIGUOUS_SERIALIZATION};
+
+// Decode one raw (binary, not hex) transaction straight from a reader,
+// pulling each field off the stream as it is parsed. Nothing past the
+// locktime is consumed, so back-to-back transactions can be read in turn.
+pub fn decode_from_reader<R: Read>(reader: &mut R) -> Result<BitcoinTransaction, DecodeError> {
+    decode_from_reader_with_limits(reader, &DecodeLimits::default())
+}
+
+// wire::parse_body drives the reads, so a transaction decodes here as it
+// does with decode_transaction, lenient limits included. What a stream can't
+// tell is whether more bytes follow the transaction: a marker and flag are
+// taken as segwit whenever that parse succeeds, as with
+// parse_transaction_prefix.
+pub fn decode_from_reader_with_limits<R: Read>(
+    reader: &mut R,
+    limits: &DecodeLimits,
+) -> Result<BitcoinTransaction, DecodeError> {
+    let mut source = StreamSource {
+        inner: reader,
+        buffer: Vec::new(),
+        ended: false,
+    };
+    // Version, then the marker and flag or the input and output counts;
+    // every transaction is longer than this
+    source.fill(6)?;
+    if !wire::has_segwit_marker(source.bytes()) {
+        let (raw, _) = wire::parse_body_from(&mut source, false, limits, &mut |_, _, _| {})?;
+        return Ok(from_raw(raw));
+    }
+
+    match wire::parse_body_from(&mut source, true, limits, &mut |_, _, _| {}) {
+        Ok((raw, end)) => {
+            // What was read also parses as a whole legacy transaction
+            let read = source.bytes();
+            let ambiguous = wire::consumes(
+                &wire::parse_body(read, false, limits, &mut |_, _, _| {}),
+                end,
+            );
+            let mut tx = from_raw(raw);
+            if ambiguous {
+                tx.warnings.push(AMBIGUOUS_SERIALIZATION.to_string());
+            }
+            Ok(tx)
+        }
+        Err(segwit) => {
+            // The legacy parse starts over on the bytes read so far
+            let (raw, end) = wire::parse_body_from(&mut source, false, limits, &mut |_, _, _| {})
+                .map_err(|_| segwit)?;
+            // The failed segwit parse may have read into whatever follows,
+            // and a stream can't be put back
+            if end < source.bytes().len() {
+                return Err(DecodeError::InvalidTransaction(
+                    "a legacy transaction with no inputs, but reading it as segwit went past its end"
+                        .to_string(),
+                ));
+            }
+            Ok(from_raw(raw))
+        }
+    }
+}
+
+// A reader as a ByteSource: every byte read is kept, so the parser can go
+// back over them, and nothing is read before the parser asks for it.
+struct StreamSource<R> {
+    inner: R,
+    buffer: Vec<u8>,
+    ended: bool,
+}
+
+impl<R: Read> ByteSource for StreamSource<R> {
+    // Reading through `take` grows the buffer as data arrives, so a bogus
+    // length can't force a huge allocation up front.
+    fn fill(&mut self, len: usize) -> Result<(), DecodeError> {
+        if self.ended || self.buffer.len() >= len {
+            return Ok(());
+        }
+        let wanted = len - self.buffer.len();
+        let read = (&mut self.inner)
+            .take(wanted as u64)
+            .read_to_end(&mut self.buffer)
+            .map_err(|e| DecodeError::Io(e.to_string()))?;
+        self.ended = read < wanted;
+        Ok(())
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn is_complete(&self) -> bool {
+        self.ended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_decode_from_reader() {
+        let bytes = hex::decode(SAMPLE_TX).unwrap();
+        let tx = decode_from_reader(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(tx, decode_transaction(SAMPLE_TX).unwrap());
+    }
+
+    #[test]
+    fn test_decode_from_reader_back_to_back() {
+        // Block 170's legacy spend between two copies of the segwit sample
+        let legacy = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+        let txs = [SAMPLE_TX, legacy, SAMPLE_TX];
+        let stream: Vec<u8> = txs.iter().flat_map(|tx| hex::decode(tx).unwrap()).collect();
+        let mut cursor = Cursor::new(stream);
+
+        for expected in txs {
+            let tx = decode_from_reader(&mut cursor).unwrap();
+            assert_eq!(tx, decode_transaction(expected).unwrap());
+        }
+        assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+        assert_eq!(
+            decode_from_reader(&mut cursor),
+            Err(DecodeError::InvalidTransaction(
+                "Input too short for version".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decode_from_reader_limits() {
+        // An output count of 2^64 - 1 is rejected as a u64, before it is
+        // ever used as a length
+        let mut bytes = hex::decode("0100000001").unwrap();
+        bytes.extend([0u8; 36]);
+        bytes.extend([0x00, 0xff, 0xff, 0xff, 0xff]);
+        bytes.extend([0xff; 9]);
+        assert!(matches!(
+            decode_from_reader(&mut Cursor::new(bytes)),
+            Err(DecodeError::LimitExceeded {
+                field: "output count",
+                count: u64::MAX,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_from_reader_lenient() {
+        // Cut off halfway through the first output's scriptPubKey
+        let bytes = hex::decode(&SAMPLE_TX[..120]).unwrap();
+        let limits = DecodeLimits {
+            lenient: true,
+            ..DecodeLimits::default()
+        };
+        let tx = decode_from_reader_with_limits(&mut Cursor::new(&bytes), &limits).unwrap();
+        assert!(tx.truncated);
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.outputs[0].scriptpubkey, "0014");
+        assert_eq!(
+            tx.warnings[0],
+            "scriptpubkey truncated: declared 22, available 2"
+        );
+        assert!(decode_from_reader(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn test_decode_from_reader_truncated() {
+        // Ends 10 bytes into the first input's txid
+        let bytes = hex::decode(&SAMPLE_TX[..34]).unwrap();
+        assert_eq!(
+            decode_from_reader(&mut Cursor::new(bytes)),
+            Err(DecodeError::InvalidTransaction(
+                "Invalid input: txid too short".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decode_from_reader_matches_decode_transaction() {
+        // The same transactions, and failures on the same input. The errors
+        // themselves can differ: only a slice knows up front that a count
+        // can't fit in what is left.
+        for hex in crate::tests::vectors() {
+            let bytes = hex::decode(&hex).unwrap();
+            assert_eq!(
+                decode_from_reader(&mut Cursor::new(bytes)).ok(),
+                crate::decode_transaction_with_limits(&hex, &DecodeLimits::default()).ok(),
+                "{}",
+                hex
+            );
+        }
+    }
+}
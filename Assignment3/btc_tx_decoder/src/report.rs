@@ -0,0 +1,162 @@
+use std::fmt::{self, Write};
+
+use crate::address::address_on;
+use crate::classify::{classify_script, ClassifyOptions};
+use crate::{le_hex_to_u64, BitcoinTransaction};
+
+// Hex longer than this many characters is cut down to its ends
+const MAX_HEX: usize = 40;
+
+// A plain-text summary for reading, not parsing: header, inputs, outputs,
+// then any warnings.
+pub fn report(tx: &BitcoinTransaction) -> String {
+    let mut out = String::new();
+    // Writing to a String can't fail
+    let _ = write_report(&mut out, tx);
+    out
+}
+
+impl fmt::Display for BitcoinTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_report(f, self)
+    }
+}
+
+fn write_report(out: &mut impl Write, tx: &BitcoinTransaction) -> fmt::Result {
+    let number = |field: &str| le_hex_to_u64(field).unwrap_or_default();
+
+    match tx.txid() {
+        Ok(txid) => writeln!(out, "Transaction {}", txid)?,
+        Err(_) => writeln!(out, "Transaction (truncated, no txid)")?,
+    }
+    writeln!(out, "  version   {}", number(&tx.version) as u32 as i32)?;
+    if tx.truncated {
+        writeln!(out, "  locktime  (missing)")?;
+    } else {
+        writeln!(out, "  locktime  {}", number(&tx.locktime))?;
+    }
+    writeln!(
+        out,
+        "  size      {} bytes, {} vbytes, {} WU{}",
+        tx.total_size(),
+        tx.vsize(),
+        tx.weight(),
+        if tx.is_segwit() { ", segwit" } else { "" }
+    )?;
+
+    writeln!(out)?;
+    writeln!(out, "Inputs ({})", tx.inputs.len())?;
+    for (i, input) in tx.inputs.iter().enumerate() {
+        let mut txid = hex::decode(&input.txid).unwrap_or_default();
+        txid.reverse();
+        let label = format!("#{}", i);
+        writeln!(
+            out,
+            "  {:<4}prevout    {}:{}",
+            label,
+            hex::encode(txid),
+            number(&input.vout)
+        )?;
+        writeln!(out, "      scriptSig  {}", abbreviate(&input.scriptsig))?;
+        match le_hex_to_u64(&input.sequence) {
+            Some(sequence) => writeln!(out, "      sequence   0x{:08x}", sequence)?,
+            None => writeln!(out, "      sequence   (missing)")?,
+        }
+        writeln!(out, "      witness    {} items", tx.witness_items(i).len())?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "Outputs ({})", tx.outputs.len())?;
+    for (i, output) in tx.outputs.iter().enumerate() {
+        let script = hex::decode(&output.scriptpubkey).unwrap_or_default();
+        let label = format!("#{}", i);
+        writeln!(
+            out,
+            "  {:<4}value      {}.{:08} BTC ({} sats)",
+            label,
+            output.value_sats / 100_000_000,
+            output.value_sats % 100_000_000,
+            output.value_sats
+        )?;
+        writeln!(
+            out,
+            "      type       {}",
+            classify_script(&script, &[], &ClassifyOptions::default()).label()
+        )?;
+        match address_on(&script, tx.network) {
+            Some(address) => writeln!(out, "      address    {}", address)?,
+            None => writeln!(out, "      script     {}", abbreviate(&output.scriptpubkey))?,
+        }
+    }
+
+    if !tx.warnings.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "Warnings")?;
+        for warning in &tx.warnings {
+            writeln!(out, "  - {}", warning)?;
+        }
+    }
+    Ok(())
+}
+
+// `0014…6809 (22 bytes)`
+fn abbreviate(hex: &str) -> String {
+    if hex.is_empty() {
+        return "(empty)".to_string();
+    }
+    let bytes = hex.len() / 2;
+    if hex.len() <= MAX_HEX {
+        return format!("{} ({} bytes)", hex, bytes);
+    }
+    format!(
+        "{}…{} ({} bytes)",
+        &hex[..MAX_HEX / 2],
+        &hex[hex.len() - 8..],
+        bytes
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_report_sample() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let expected = "\
+Transaction 04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842
+  version   2
+  locktime  918339
+  size      222 bytes, 141 vbytes, 561 WU, segwit
+
+Inputs (1)
+  #0  prevout    c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131:1
+      scriptSig  (empty)
+      sequence   0xfffffffd
+      witness    2 items
+
+Outputs (2)
+  #0  value      0.00500000 BTC (500000 sats)
+      type       p2wpkh
+      address    bc1qshtcadu4hkwg5gd0alytdldwmaccx6qfh9fg9u
+  #1  value      0.01050700 BTC (1050700 sats)
+      type       p2wpkh
+      address    bc1qss9tzewfcf246j33hysg4kqxlzwj2d0zn4wd90
+";
+        assert_eq!(report(&tx), expected);
+        assert_eq!(tx.to_string(), expected);
+    }
+
+    #[test]
+    fn test_abbreviate() {
+        assert_eq!(abbreviate(""), "(empty)");
+        assert_eq!(abbreviate("6a0401020304"), "6a0401020304 (6 bytes)");
+        let long = format!("48{}01", "30".repeat(70));
+        assert_eq!(
+            abbreviate(&long),
+            "48303030303030303030…30303001 (72 bytes)"
+        );
+    }
+}
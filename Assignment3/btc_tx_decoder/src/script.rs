@@ -0,0 +1,485 @@
+use serde::Serialize;
+
+use crate::BitcoinTransaction;
+
+pub const OP_0: u8 = 0x00;
+pub const OP_PUSHDATA1: u8 = 0x4c;
+pub const OP_PUSHDATA2: u8 = 0x4d;
+pub const OP_PUSHDATA4: u8 = 0x4e;
+pub const OP_1NEGATE: u8 = 0x4f;
+pub const OP_1: u8 = 0x51;
+pub const OP_2: u8 = 0x52;
+pub const OP_16: u8 = 0x60;
+pub const OP_IF: u8 = 0x63;
+pub const OP_NOTIF: u8 = 0x64;
+pub const OP_ELSE: u8 = 0x67;
+pub const OP_ENDIF: u8 = 0x68;
+pub const OP_RETURN: u8 = 0x6a;
+pub const OP_DROP: u8 = 0x75;
+pub const OP_DUP: u8 = 0x76;
+pub const OP_SWAP: u8 = 0x7c;
+pub const OP_SIZE: u8 = 0x82;
+pub const OP_EQUAL: u8 = 0x87;
+pub const OP_EQUALVERIFY: u8 = 0x88;
+pub const OP_RIPEMD160: u8 = 0xa6;
+pub const OP_HASH160: u8 = 0xa9;
+pub const OP_HASH256: u8 = 0xaa;
+pub const OP_CODESEPARATOR: u8 = 0xab;
+pub const OP_CHECKSIG: u8 = 0xac;
+pub const OP_CHECKMULTISIG: u8 = 0xae;
+pub const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+pub const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+pub const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    // A data push: OP_0, a direct push or one of the OP_PUSHDATAs
+    Push { opcode: u8, data: &'a [u8] },
+    // Any other opcode, including OP_1NEGATE and OP_1..OP_16
+    Op(u8),
+}
+
+impl Instruction<'_> {
+    // Push-only in the IsPushOnly sense: data pushes and the small integer
+    // opcodes.
+    pub fn is_push(&self) -> bool {
+        match self {
+            Instruction::Push { .. } => true,
+            Instruction::Op(opcode) => *opcode <= OP_16,
+        }
+    }
+}
+
+// Walks a script yielding (offset, instruction). A push running past the end
+// of the script yields an error and ends the iteration.
+pub struct Instructions<'a> {
+    script: &'a [u8],
+    pos: usize,
+}
+
+pub fn instructions(script: &[u8]) -> Instructions<'_> {
+    Instructions { script, pos: 0 }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<(usize, Instruction<'a>), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let script = self.script;
+        let offset = self.pos;
+        let opcode = *script.get(offset)?;
+
+        let (len_size, data_len) = match opcode {
+            0x00..=0x4b => (0, opcode as usize),
+            OP_PUSHDATA1 | OP_PUSHDATA2 | OP_PUSHDATA4 => {
+                let len_size = match opcode {
+                    OP_PUSHDATA1 => 1,
+                    OP_PUSHDATA2 => 2,
+                    _ => 4,
+                };
+                let Some(len_bytes) = script.get(offset + 1..offset + 1 + len_size) else {
+                    self.pos = script.len();
+                    return Some(Err(format!("truncated push length at offset {}", offset)));
+                };
+                let mut buf = [0u8; 4];
+                buf[..len_size].copy_from_slice(len_bytes);
+                (len_size, u32::from_le_bytes(buf) as usize)
+            }
+            _ => {
+                self.pos += 1;
+                return Some(Ok((offset, Instruction::Op(opcode))));
+            }
+        };
+
+        let start = offset + 1 + len_size;
+        if data_len > script.len() - start {
+            self.pos = script.len();
+            return Some(Err(format!("truncated push data at offset {}", offset)));
+        }
+        self.pos = start + data_len;
+        Some(Ok((
+            offset,
+            Instruction::Push {
+                opcode,
+                data: &script[start..start + data_len],
+            },
+        )))
+    }
+}
+
+// Whether a push used the smallest possible encoding for its data
+// (CheckMinimalPush in Bitcoin Core).
+pub fn is_minimal_push(opcode: u8, data: &[u8]) -> bool {
+    match data.len() {
+        0 => opcode == OP_0,
+        1 if (1..=16).contains(&data[0]) || data[0] == 0x81 => false,
+        len if len <= 75 => opcode as usize == len,
+        len if len <= 255 => opcode == OP_PUSHDATA1,
+        len if len <= 65535 => opcode == OP_PUSHDATA2,
+        _ => true,
+    }
+}
+
+// The data push a script would use for `data`, as raw bytes. Data pushes
+// only, so the small integer opcodes are never substituted.
+pub fn encode_push(data: &[u8]) -> Vec<u8> {
+    let mut out = match data.len() {
+        len @ 0..=75 => vec![len as u8],
+        len @ 76..=255 => vec![OP_PUSHDATA1, len as u8],
+        len @ 256..=65535 => {
+            let mut out = vec![OP_PUSHDATA2];
+            out.extend_from_slice(&(len as u16).to_le_bytes());
+            out
+        }
+        len => {
+            let mut out = vec![OP_PUSHDATA4];
+            out.extend_from_slice(&(len as u32).to_le_bytes());
+            out
+        }
+    };
+    out.extend_from_slice(data);
+    out
+}
+
+// A script element with its data copied out, for callers that want to keep
+// the parsed script around. Small integers are ops, like in Instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptElement {
+    Op(u8, &'static str),
+    Data(Vec<u8>),
+}
+
+// `script_hex` split into elements. Unlike `instructions`, a push running
+// past the end of the script fails the whole parse.
+pub fn parse_script(script_hex: &str) -> Result<Vec<ScriptElement>, String> {
+    let script = hex::decode(script_hex).map_err(|e| format!("invalid script hex: {}", e))?;
+    instructions(&script)
+        .map(|instruction| {
+            instruction.map(|(_, instruction)| match instruction {
+                Instruction::Push { data, .. } => ScriptElement::Data(data.to_vec()),
+                Instruction::Op(opcode) => ScriptElement::Op(opcode, opcode_name(opcode)),
+            })
+        })
+        .collect()
+}
+
+// Bitcoin Core style disassembly: pushes as hex, small integers as numbers
+// and everything else by opcode name. A malformed push ends with "[error]".
+pub fn to_asm(script: &[u8]) -> String {
+    let mut parts = Vec::new();
+    for instruction in instructions(script) {
+        match instruction {
+            Ok((_, Instruction::Push { data: [], .. })) => parts.push("0".to_string()),
+            Ok((_, Instruction::Push { data, .. })) => parts.push(hex::encode(data)),
+            Ok((_, Instruction::Op(OP_1NEGATE))) => parts.push("-1".to_string()),
+            Ok((_, Instruction::Op(op))) if (OP_1..=OP_16).contains(&op) => {
+                parts.push((op - OP_1 + 1).to_string())
+            }
+            Ok((_, Instruction::Op(op))) => parts.push(opcode_name(op).to_string()),
+            Err(_) => parts.push("[error]".to_string()),
+        }
+    }
+    parts.join(" ")
+}
+
+// Name of a non-push opcode
+pub fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x4f => "OP_1NEGATE",
+        0x50 => "OP_RESERVED",
+        0x51 => "OP_1",
+        0x52 => "OP_2",
+        0x53 => "OP_3",
+        0x54 => "OP_4",
+        0x55 => "OP_5",
+        0x56 => "OP_6",
+        0x57 => "OP_7",
+        0x58 => "OP_8",
+        0x59 => "OP_9",
+        0x5a => "OP_10",
+        0x5b => "OP_11",
+        0x5c => "OP_12",
+        0x5d => "OP_13",
+        0x5e => "OP_14",
+        0x5f => "OP_15",
+        0x60 => "OP_16",
+        0x61 => "OP_NOP",
+        0x62 => "OP_VER",
+        0x63 => "OP_IF",
+        0x64 => "OP_NOTIF",
+        0x65 => "OP_VERIF",
+        0x66 => "OP_VERNOTIF",
+        0x67 => "OP_ELSE",
+        0x68 => "OP_ENDIF",
+        0x69 => "OP_VERIFY",
+        0x6a => "OP_RETURN",
+        0x6b => "OP_TOALTSTACK",
+        0x6c => "OP_FROMALTSTACK",
+        0x6d => "OP_2DROP",
+        0x6e => "OP_2DUP",
+        0x6f => "OP_3DUP",
+        0x70 => "OP_2OVER",
+        0x71 => "OP_2ROT",
+        0x72 => "OP_2SWAP",
+        0x73 => "OP_IFDUP",
+        0x74 => "OP_DEPTH",
+        0x75 => "OP_DROP",
+        0x76 => "OP_DUP",
+        0x77 => "OP_NIP",
+        0x78 => "OP_OVER",
+        0x79 => "OP_PICK",
+        0x7a => "OP_ROLL",
+        0x7b => "OP_ROT",
+        0x7c => "OP_SWAP",
+        0x7d => "OP_TUCK",
+        0x7e => "OP_CAT",
+        0x7f => "OP_SUBSTR",
+        0x80 => "OP_LEFT",
+        0x81 => "OP_RIGHT",
+        0x82 => "OP_SIZE",
+        0x83 => "OP_INVERT",
+        0x84 => "OP_AND",
+        0x85 => "OP_OR",
+        0x86 => "OP_XOR",
+        0x87 => "OP_EQUAL",
+        0x88 => "OP_EQUALVERIFY",
+        0x89 => "OP_RESERVED1",
+        0x8a => "OP_RESERVED2",
+        0x8b => "OP_1ADD",
+        0x8c => "OP_1SUB",
+        0x8d => "OP_2MUL",
+        0x8e => "OP_2DIV",
+        0x8f => "OP_NEGATE",
+        0x90 => "OP_ABS",
+        0x91 => "OP_NOT",
+        0x92 => "OP_0NOTEQUAL",
+        0x93 => "OP_ADD",
+        0x94 => "OP_SUB",
+        0x95 => "OP_MUL",
+        0x96 => "OP_DIV",
+        0x97 => "OP_MOD",
+        0x98 => "OP_LSHIFT",
+        0x99 => "OP_RSHIFT",
+        0x9a => "OP_BOOLAND",
+        0x9b => "OP_BOOLOR",
+        0x9c => "OP_NUMEQUAL",
+        0x9d => "OP_NUMEQUALVERIFY",
+        0x9e => "OP_NUMNOTEQUAL",
+        0x9f => "OP_LESSTHAN",
+        0xa0 => "OP_GREATERTHAN",
+        0xa1 => "OP_LESSTHANOREQUAL",
+        0xa2 => "OP_GREATERTHANOREQUAL",
+        0xa3 => "OP_MIN",
+        0xa4 => "OP_MAX",
+        0xa5 => "OP_WITHIN",
+        0xa6 => "OP_RIPEMD160",
+        0xa7 => "OP_SHA1",
+        0xa8 => "OP_SHA256",
+        0xa9 => "OP_HASH160",
+        0xaa => "OP_HASH256",
+        0xab => "OP_CODESEPARATOR",
+        0xac => "OP_CHECKSIG",
+        0xad => "OP_CHECKSIGVERIFY",
+        0xae => "OP_CHECKMULTISIG",
+        0xaf => "OP_CHECKMULTISIGVERIFY",
+        0xb0 => "OP_NOP1",
+        0xb1 => "OP_CHECKLOCKTIMEVERIFY",
+        0xb2 => "OP_CHECKSEQUENCEVERIFY",
+        0xb3 => "OP_NOP4",
+        0xb4 => "OP_NOP5",
+        0xb5 => "OP_NOP6",
+        0xb6 => "OP_NOP7",
+        0xb7 => "OP_NOP8",
+        0xb8 => "OP_NOP9",
+        0xb9 => "OP_NOP10",
+        0xba => "OP_CHECKSIGADD",
+        _ => "OP_UNKNOWN",
+    }
+}
+
+// Where a script was found in the transaction
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptContext {
+    InputScriptSig { index: usize },
+    OutputScriptPubkey { index: usize },
+    WitnessItem { input: usize, item: usize },
+}
+
+// A witness program is a version opcode (OP_0 or OP_1..OP_16) followed by a
+// single 2 to 40 byte push.
+pub fn is_witness_program(script: &[u8]) -> bool {
+    if script.len() < 4 || script.len() > 42 {
+        return false;
+    }
+    let version_ok = script[0] == 0x00 || (0x51..=0x60).contains(&script[0]);
+    version_ok && script[1] as usize == script.len() - 2
+}
+
+// Every script in the transaction in serialization order: scriptSigs, then
+// scriptPubKeys, then witness items.
+pub fn all_scripts(tx: &BitcoinTransaction) -> impl Iterator<Item = (ScriptContext, Vec<u8>)> + '_ {
+    let script_sigs = tx.inputs.iter().enumerate().map(|(index, input)| {
+        (
+            ScriptContext::InputScriptSig { index },
+            hex::decode(&input.scriptsig).unwrap_or_default(),
+        )
+    });
+
+    let script_pubkeys = tx.outputs.iter().enumerate().map(|(index, output)| {
+        (
+            ScriptContext::OutputScriptPubkey { index },
+            hex::decode(&output.scriptpubkey).unwrap_or_default(),
+        )
+    });
+
+    let witness_items = (0..tx.inputs.len()).flat_map(move |input| {
+        tx.witness_items(input)
+            .into_iter()
+            .enumerate()
+            .map(move |(item, data)| {
+                (
+                    ScriptContext::WitnessItem { input, item },
+                    hex::decode(data).unwrap_or_default(),
+                )
+            })
+    });
+
+    script_sigs.chain(script_pubkeys).chain(witness_items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_all_scripts_sample() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let scripts: Vec<_> = all_scripts(&tx).collect();
+
+        let contexts: Vec<_> = scripts.iter().map(|(context, _)| *context).collect();
+        assert_eq!(
+            contexts,
+            vec![
+                ScriptContext::InputScriptSig { index: 0 },
+                ScriptContext::OutputScriptPubkey { index: 0 },
+                ScriptContext::OutputScriptPubkey { index: 1 },
+                ScriptContext::WitnessItem { input: 0, item: 0 },
+                ScriptContext::WitnessItem { input: 0, item: 1 },
+            ]
+        );
+
+        assert!(scripts[0].1.is_empty());
+        assert_eq!(
+            hex::encode(&scripts[1].1),
+            "001485d78eb795bd9c8a21afefc8b6fdaedf71836809"
+        );
+        assert_eq!(scripts[4].1.len(), 33);
+    }
+
+    #[test]
+    fn test_instructions() {
+        // OP_0, push 2 bytes, OP_PUSHDATA1 1 byte, OP_CHECKMULTISIG
+        let script = hex::decode("0002aabb4c01ccae").unwrap();
+        let parsed: Vec<_> = instructions(&script).collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                (
+                    0,
+                    Instruction::Push {
+                        opcode: 0x00,
+                        data: &[]
+                    }
+                ),
+                (
+                    1,
+                    Instruction::Push {
+                        opcode: 0x02,
+                        data: &[0xaa, 0xbb]
+                    }
+                ),
+                (
+                    4,
+                    Instruction::Push {
+                        opcode: OP_PUSHDATA1,
+                        data: &[0xcc]
+                    }
+                ),
+                (7, Instruction::Op(OP_CHECKMULTISIG)),
+            ]
+        );
+
+        let truncated = hex::decode("03aabb").unwrap();
+        let mut iter = instructions(&truncated);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        // P2PKH scriptSig: a signature and a pubkey
+        let (sig, pubkey) = ("30".repeat(71), "02".repeat(33));
+        assert_eq!(
+            parse_script(&format!("47{}21{}", sig, pubkey)).unwrap(),
+            vec![
+                ScriptElement::Data(hex::decode(&sig).unwrap()),
+                ScriptElement::Data(hex::decode(&pubkey).unwrap()),
+            ]
+        );
+
+        assert_eq!(
+            parse_script("6a4c0568656c6c6f").unwrap(),
+            vec![
+                ScriptElement::Op(0x6a, "OP_RETURN"),
+                ScriptElement::Data(b"hello".to_vec()),
+            ]
+        );
+        assert_eq!(
+            parse_script("00514e01000000ffbb").unwrap(),
+            vec![
+                ScriptElement::Data(Vec::new()),
+                ScriptElement::Op(OP_1, "OP_1"),
+                ScriptElement::Data(vec![0xff]),
+                ScriptElement::Op(0xbb, "OP_UNKNOWN"),
+            ]
+        );
+
+        assert!(parse_script("4d0100").is_err());
+        assert!(parse_script("zz").is_err());
+    }
+
+    #[test]
+    fn test_to_asm() {
+        assert_eq!(
+            to_asm(&hex::decode("76a91485d78eb795bd9c8a21afefc8b6fdaedf7183680988ac").unwrap()),
+            "OP_DUP OP_HASH160 85d78eb795bd9c8a21afefc8b6fdaedf71836809 OP_EQUALVERIFY OP_CHECKSIG"
+        );
+        assert_eq!(
+            to_asm(&hex::decode("004f5160b1").unwrap()),
+            "0 -1 1 16 OP_CHECKLOCKTIMEVERIFY"
+        );
+        assert_eq!(to_asm(&hex::decode("5103aabb").unwrap()), "1 [error]");
+    }
+
+    #[test]
+    fn test_is_minimal_push() {
+        assert!(is_minimal_push(0x00, &[]));
+        assert!(is_minimal_push(0x01, &[0x17]));
+        assert!(!is_minimal_push(0x01, &[0x05]));
+        assert!(!is_minimal_push(OP_PUSHDATA1, &[0xcc]));
+        assert!(is_minimal_push(OP_PUSHDATA1, &[0u8; 76]));
+    }
+
+    #[test]
+    fn test_is_witness_program() {
+        assert!(is_witness_program(
+            &hex::decode("001485d78eb795bd9c8a21afefc8b6fdaedf71836809").unwrap()
+        ));
+        assert!(!is_witness_program(
+            &hex::decode("76a91485d78eb795bd9c8a21afefc8b6fdaedf7183680988ac").unwrap()
+        ));
+        assert!(!is_witness_program(&hex::decode("0001aa").unwrap()));
+    }
+}
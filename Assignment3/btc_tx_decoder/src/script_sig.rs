@@ -0,0 +1,155 @@
+use serde::Serialize;
+
+use crate::consensus::is_coinbase;
+use crate::script::{instructions, opcode_name, Instruction, OP_16};
+use crate::BitcoinTransaction;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NonPushOp {
+    pub offset: usize,
+    pub opcode: &'static str,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptSigAnalysis {
+    // Only data pushes and small integers, as relay policy requires
+    pub push_only: bool,
+    // Hex of each data push in order; in a P2SH spend the last one is the
+    // redeem script
+    pub pushes: Vec<String>,
+    pub non_push_ops: Vec<NonPushOp>,
+    // Set when a push runs past the end of the script
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ScriptSigAnalysis {
+    pub fn last_push(&self) -> Option<&str> {
+        self.pushes.last().map(String::as_str)
+    }
+
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self
+            .non_push_ops
+            .iter()
+            .map(|op| {
+                format!(
+                    "scriptSig is not push-only: {} at offset {}",
+                    op.opcode, op.offset
+                )
+            })
+            .collect();
+        if let Some(error) = &self.error {
+            warnings.push(format!("malformed scriptSig: {}", error));
+        }
+        warnings
+    }
+}
+
+// Walks a scriptSig's opcodes. A malformed script is analyzed up to the
+// bad push rather than rejected.
+pub fn analyze_script_sig(script: &[u8]) -> ScriptSigAnalysis {
+    let mut analysis = ScriptSigAnalysis::default();
+    for instruction in instructions(script) {
+        match instruction {
+            Ok((_, Instruction::Push { data, .. })) => analysis.pushes.push(hex::encode(data)),
+            Ok((offset, Instruction::Op(opcode))) if opcode > OP_16 => {
+                analysis.non_push_ops.push(NonPushOp {
+                    offset,
+                    opcode: opcode_name(opcode),
+                })
+            }
+            // OP_1NEGATE and the small integers
+            Ok(_) => {}
+            Err(e) => analysis.error = Some(e),
+        }
+    }
+    analysis.push_only = analysis.non_push_ops.is_empty() && analysis.error.is_none();
+    analysis
+}
+
+// Sets `standard` and `warnings` on every input. A coinbase scriptSig is
+// exempt: it is arbitrary data, not a script that gets run.
+pub(crate) fn check_script_sigs(tx: &mut BitcoinTransaction) {
+    let coinbase = is_coinbase(tx);
+    for input in &mut tx.inputs {
+        let warnings = if coinbase {
+            Vec::new()
+        } else {
+            analyze_script_sig(&hex::decode(&input.scriptsig).unwrap_or_default()).warnings()
+        };
+        input.standard = warnings.is_empty();
+        input.warnings = warnings;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::tests::SAMPLE_TX;
+
+    fn analyze(hex_str: &str) -> ScriptSigAnalysis {
+        analyze_script_sig(&hex::decode(hex_str).unwrap())
+    }
+
+    #[test]
+    fn test_p2pkh_script_sig() {
+        let signature = format!("30{}01", "44".repeat(70));
+        let key = format!("02{}", "11".repeat(32));
+        let analysis = analyze(&format!("48{}21{}", signature, key));
+        assert!(analysis.push_only);
+        assert_eq!(analysis.pushes, vec![signature, key.clone()]);
+        assert_eq!(analysis.last_push(), Some(key.as_str()));
+        assert!(analysis.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_p2sh_script_sig() {
+        // OP_0 <sig> OP_1 <redeem script>: OP_1 is a push too
+        let redeem = format!("5121{}51ae", "02".repeat(33));
+        let analysis = analyze(&format!("000130514c{:02x}{}", redeem.len() / 2, redeem));
+        assert!(analysis.push_only);
+        assert_eq!(
+            analysis.pushes,
+            vec!["".to_string(), "30".to_string(), redeem.clone()]
+        );
+        assert_eq!(analysis.last_push(), Some(redeem.as_str()));
+    }
+
+    #[test]
+    fn test_non_push_script_sig() {
+        let analysis = analyze("0130760102");
+        assert!(!analysis.push_only);
+        assert_eq!(
+            analysis.non_push_ops,
+            vec![NonPushOp {
+                offset: 2,
+                opcode: "OP_DUP"
+            }]
+        );
+        assert_eq!(
+            analysis.warnings(),
+            vec!["scriptSig is not push-only: OP_DUP at offset 2"]
+        );
+
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(tx.inputs[0].standard);
+        tx.inputs[0].scriptsig = "0130760102".to_string();
+        check_script_sigs(&mut tx);
+        assert!(!tx.inputs[0].standard);
+        assert_eq!(tx.inputs[0].warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_truncated_script_sig() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.inputs[0].scriptsig = "013001304c05aa".to_string();
+        check_script_sigs(&mut tx);
+        assert!(!tx.inputs[0].standard);
+        assert_eq!(
+            tx.inputs[0].warnings,
+            vec!["malformed scriptSig: truncated push data at offset 4"]
+        );
+    }
+}
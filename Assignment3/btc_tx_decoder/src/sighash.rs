@@ -0,0 +1,859 @@
+use std::fmt;
+
+use crate::consensus::PrevOut;
+use crate::error::DecodeError;
+use crate::hashes::{double_sha256, sha256, tagged_hash};
+use crate::script::OP_CODESEPARATOR;
+use crate::{encode_transaction, BitcoinTransaction, CompactSize, TxOutput};
+
+pub const SIGHASH_ALL: u32 = 0x01;
+pub const SIGHASH_NONE: u32 = 0x02;
+pub const SIGHASH_SINGLE: u32 = 0x03;
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+// What legacy SIGHASH_SINGLE signs when the input has no matching output:
+// the number 1 as a little-endian uint256.
+const SIGHASH_SINGLE_BUG: [u8; 32] = {
+    let mut one = [0u8; 32];
+    one[0] = 1;
+    one
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SighashError {
+    InputIndexOutOfRange { index: usize, inputs: usize },
+    MissingValue { index: usize },
+    // Legacy SIGHASH_SINGLE with no output at the input's index. There is no
+    // preimage; the digest is the constant 1.
+    SingleWithoutOutput { index: usize },
+    InvalidField(String),
+}
+
+impl fmt::Display for SighashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SighashError::InputIndexOutOfRange { index, inputs } => write!(
+                f,
+                "input index {} out of range (transaction has {} inputs)",
+                index, inputs
+            ),
+            SighashError::MissingValue { index } => {
+                write!(
+                    f,
+                    "input {} is a witness input; its value is required",
+                    index
+                )
+            }
+            SighashError::SingleWithoutOutput { index } => write!(
+                f,
+                "SIGHASH_SINGLE input {} has no matching output, legacy digest is 1",
+                index
+            ),
+            SighashError::InvalidField(e) => write!(f, "invalid transaction field: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SighashError {}
+
+// Preimage for the given input. Passing `value` selects BIP143 (segwit v0),
+// which commits to the spent amount; inputs that carry witness data require
+// it. Without a value the legacy algorithm is used.
+pub fn sighash_preimage(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &[u8],
+    value: Option<u64>,
+    sighash_type: u32,
+) -> Result<Vec<u8>, SighashError> {
+    if input_index < tx.inputs.len() && value.is_none() && !tx.witness_items(input_index).is_empty()
+    {
+        return Err(SighashError::MissingValue { index: input_index });
+    }
+
+    signing_template(
+        tx,
+        input_index,
+        script_code,
+        sighash_type,
+        value.is_some(),
+        value,
+    )
+    .map_err(|e| match e {
+        DecodeError::InputIndexOutOfRange { index, inputs } => {
+            SighashError::InputIndexOutOfRange { index, inputs }
+        }
+        DecodeError::MissingAmount => SighashError::MissingValue { index: input_index },
+        DecodeError::NoMatchingOutput { index } => SighashError::SingleWithoutOutput { index },
+        e => SighashError::InvalidField(e.to_string()),
+    })
+}
+
+// Double-SHA256 of the preimage, i.e. the message the signature commits to.
+// Handles the legacy SIGHASH_SINGLE bug by returning 1.
+pub fn sighash_digest(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &[u8],
+    value: Option<u64>,
+    sighash_type: u32,
+) -> Result<[u8; 32], SighashError> {
+    match sighash_preimage(tx, input_index, script_code, value, sighash_type) {
+        Ok(preimage) => Ok(double_sha256(&preimage)),
+        Err(SighashError::SingleWithoutOutput { .. }) => Ok(SIGHASH_SINGLE_BUG),
+        Err(e) => Err(e),
+    }
+}
+
+// Pre-segwit signature hash for the input, as hex. `script_code` is the
+// script being satisfied (the prevout scriptPubKey, or the redeem script for
+// P2SH).
+pub fn legacy_sighash(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &str,
+    sighash_type: u8,
+) -> Result<String, String> {
+    let script_code =
+        hex::decode(script_code).map_err(|e| format!("Invalid script code hex: {}", e))?;
+
+    match signing_template(
+        tx,
+        input_index,
+        &script_code,
+        sighash_type as u32,
+        false,
+        None,
+    ) {
+        Ok(preimage) => Ok(hex::encode(double_sha256(&preimage))),
+        Err(DecodeError::NoMatchingOutput { .. }) => Ok(hex::encode(SIGHASH_SINGLE_BUG)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// BIP143 (segwit v0) signature hash for the input, as hex. `script_code` is
+// the BIP143 scriptCode, e.g. `76a914{pubkey hash}88ac` for P2WPKH. Use
+// SighashCache directly when hashing several inputs of the same transaction.
+pub fn segwit_v0_sighash(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &str,
+    amount_sats: u64,
+    sighash_type: u8,
+) -> Result<String, String> {
+    SighashCache::new(tx)?.segwit_v0_sighash(input_index, script_code, amount_sats, sighash_type)
+}
+
+// BIP341 signature hash for a taproot key-path spend. Unlike the older
+// algorithms it commits to the amount and scriptPubKey of every input, so
+// `prevouts` holds one entry per input, in order. `sighash_type` 0x00 is
+// SIGHASH_DEFAULT, what a 64-byte signature implies.
+pub fn taproot_key_spend_sighash(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    prevouts: &[PrevOut],
+    sighash_type: u8,
+    annex: Option<&[u8]>,
+) -> Result<[u8; 32], SighashError> {
+    let invalid = |e: DecodeError| SighashError::InvalidField(e.to_string());
+    if input_index >= tx.inputs.len() {
+        return Err(SighashError::InputIndexOutOfRange {
+            index: input_index,
+            inputs: tx.inputs.len(),
+        });
+    }
+    if prevouts.len() != tx.inputs.len() {
+        return Err(SighashError::InvalidField(format!(
+            "{} prevouts for {} inputs",
+            prevouts.len(),
+            tx.inputs.len()
+        )));
+    }
+    if !matches!(sighash_type, 0x00..=0x03 | 0x81..=0x83) {
+        return Err(SighashError::InvalidField(format!(
+            "invalid taproot sighash type {:#04x}",
+            sighash_type
+        )));
+    }
+
+    let base_type = (sighash_type & 0x03) as u32;
+    let anyone_can_pay = sighash_type as u32 & SIGHASH_ANYONECANPAY != 0;
+    if base_type == SIGHASH_SINGLE && input_index >= tx.outputs.len() {
+        return Err(SighashError::SingleWithoutOutput { index: input_index });
+    }
+
+    let spent_output = |i: usize| -> Result<Vec<u8>, DecodeError> {
+        let script = field_bytes(&prevouts[i].script_pubkey)?;
+        let mut out = prevouts[i].value_sats.to_le_bytes().to_vec();
+        out.extend(CompactSize::encode(script.len() as u64));
+        out.extend(script);
+        Ok(out)
+    };
+
+    // Epoch 0, then the hash type
+    let mut preimage = vec![0x00, sighash_type];
+    preimage.extend(field_bytes(&tx.version).map_err(invalid)?);
+    preimage.extend(field_bytes(&tx.locktime).map_err(invalid)?);
+
+    if !anyone_can_pay {
+        let mut outpoints = Vec::new();
+        let mut amounts = Vec::new();
+        let mut scripts = Vec::new();
+        let mut sequences = Vec::new();
+        for (i, input) in tx.inputs.iter().enumerate() {
+            outpoints.extend(field_bytes(&input.txid).map_err(invalid)?);
+            outpoints.extend(field_bytes(&input.vout).map_err(invalid)?);
+            amounts.extend_from_slice(&prevouts[i].value_sats.to_le_bytes());
+            scripts.extend(spent_output(i).map_err(invalid)?.split_off(8));
+            sequences.extend(field_bytes(&input.sequence).map_err(invalid)?);
+        }
+        preimage.extend_from_slice(&sha256(&outpoints));
+        preimage.extend_from_slice(&sha256(&amounts));
+        preimage.extend_from_slice(&sha256(&scripts));
+        preimage.extend_from_slice(&sha256(&sequences));
+    }
+    if base_type != SIGHASH_NONE && base_type != SIGHASH_SINGLE {
+        let mut outputs = Vec::new();
+        for output in &tx.outputs {
+            outputs.extend(serialize_output(output).map_err(invalid)?);
+        }
+        preimage.extend_from_slice(&sha256(&outputs));
+    }
+
+    // Key path, so the extension flag is 0
+    preimage.push(if annex.is_some() { 1 } else { 0 });
+    if anyone_can_pay {
+        let input = &tx.inputs[input_index];
+        preimage.extend(field_bytes(&input.txid).map_err(invalid)?);
+        preimage.extend(field_bytes(&input.vout).map_err(invalid)?);
+        preimage.extend(spent_output(input_index).map_err(invalid)?);
+        preimage.extend(field_bytes(&input.sequence).map_err(invalid)?);
+    } else {
+        preimage.extend_from_slice(&(input_index as u32).to_le_bytes());
+    }
+    if let Some(annex) = annex {
+        let mut data = CompactSize::encode(annex.len() as u64);
+        data.extend_from_slice(annex);
+        preimage.extend_from_slice(&sha256(&data));
+    }
+    if base_type == SIGHASH_SINGLE {
+        let output = serialize_output(&tx.outputs[input_index]).map_err(invalid)?;
+        preimage.extend_from_slice(&sha256(&output));
+    }
+
+    Ok(tagged_hash("TapSighash", &preimage))
+}
+
+// The exact bytes that get double-SHA256'd and signed for the given input,
+// using either the legacy algorithm or BIP143 for segwit v0 inputs.
+pub fn signing_template(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &[u8],
+    sighash_type: u32,
+    segwit: bool,
+    amount: Option<u64>,
+) -> Result<Vec<u8>, DecodeError> {
+    if input_index >= tx.inputs.len() {
+        return Err(DecodeError::InputIndexOutOfRange {
+            index: input_index,
+            inputs: tx.inputs.len(),
+        });
+    }
+
+    if segwit {
+        let amount = amount.ok_or(DecodeError::MissingAmount)?;
+        bip143_template(tx, input_index, script_code, sighash_type, amount)
+    } else {
+        legacy_template(tx, input_index, script_code, sighash_type)
+    }
+}
+
+fn legacy_template(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &[u8],
+    sighash_type: u32,
+) -> Result<Vec<u8>, DecodeError> {
+    let base_type = sighash_type & 0x1f;
+    let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+    // Legacy signs the constant 1 here instead of a real preimage, so there is
+    // no template to return.
+    if base_type == SIGHASH_SINGLE && input_index >= tx.outputs.len() {
+        return Err(DecodeError::NoMatchingOutput { index: input_index });
+    }
+
+    // Work on a stripped copy of the transaction and let encode_transaction
+    // do the serialization.
+    let mut copy = tx.clone();
+    copy.marker.clear();
+    copy.flag.clear();
+    copy.witness.clear();
+
+    let script_code = hex::encode(remove_codeseparators(script_code));
+    for (i, input) in copy.inputs.iter_mut().enumerate() {
+        if i == input_index {
+            input.scriptsig = script_code.clone();
+        } else {
+            input.scriptsig.clear();
+            if base_type == SIGHASH_NONE || base_type == SIGHASH_SINGLE {
+                input.sequence = "00000000".to_string();
+            }
+        }
+        input.scriptsigsize = compact_size_hex(input.scriptsig.len() / 2);
+    }
+
+    match base_type {
+        SIGHASH_NONE => copy.outputs.clear(),
+        SIGHASH_SINGLE => {
+            copy.outputs.truncate(input_index + 1);
+            for output in &mut copy.outputs[..input_index] {
+                output.amount = "ffffffffffffffff".to_string();
+                output.scriptpubkeysize = "00".to_string();
+                output.scriptpubkey.clear();
+            }
+        }
+        _ => {}
+    }
+
+    if anyone_can_pay {
+        copy.inputs = vec![copy.inputs[input_index].clone()];
+    }
+    copy.inputcount = compact_size_hex(copy.inputs.len());
+    copy.outputcount = compact_size_hex(copy.outputs.len());
+
+    let mut preimage = field_bytes(&encode_transaction(&copy)?)?;
+    preimage.extend_from_slice(&sighash_type.to_le_bytes());
+    Ok(preimage)
+}
+
+fn bip143_template(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &[u8],
+    sighash_type: u32,
+    amount: u64,
+) -> Result<Vec<u8>, DecodeError> {
+    SighashCache::new(tx)?.bip143_template(input_index, script_code, sighash_type, amount)
+}
+
+// The hashPrevouts/hashSequence/hashOutputs midstates BIP143 shares across
+// every input of a transaction. Build one per transaction when signing many
+// inputs so each sighash doesn't rehash the whole transaction.
+pub struct SighashCache<'a> {
+    tx: &'a BitcoinTransaction,
+    hash_prevouts: [u8; 32],
+    hash_sequence: [u8; 32],
+    hash_outputs: [u8; 32],
+}
+
+impl<'a> SighashCache<'a> {
+    pub fn new(tx: &'a BitcoinTransaction) -> Result<Self, DecodeError> {
+        let mut prevouts = Vec::new();
+        let mut sequences = Vec::new();
+        for input in &tx.inputs {
+            prevouts.extend(field_bytes(&input.txid)?);
+            prevouts.extend(field_bytes(&input.vout)?);
+            sequences.extend(field_bytes(&input.sequence)?);
+        }
+        let mut outputs = Vec::new();
+        for output in &tx.outputs {
+            outputs.extend(serialize_output(output)?);
+        }
+
+        Ok(SighashCache {
+            tx,
+            hash_prevouts: double_sha256(&prevouts),
+            hash_sequence: double_sha256(&sequences),
+            hash_outputs: double_sha256(&outputs),
+        })
+    }
+
+    // BIP143 signature hash for the input, as hex.
+    pub fn segwit_v0_sighash(
+        &self,
+        input_index: usize,
+        script_code: &str,
+        amount_sats: u64,
+        sighash_type: u8,
+    ) -> Result<String, String> {
+        let script_code =
+            hex::decode(script_code).map_err(|e| format!("Invalid script code hex: {}", e))?;
+        let preimage =
+            self.bip143_template(input_index, &script_code, sighash_type as u32, amount_sats)?;
+        Ok(hex::encode(double_sha256(&preimage)))
+    }
+
+    fn bip143_template(
+        &self,
+        input_index: usize,
+        script_code: &[u8],
+        sighash_type: u32,
+        amount: u64,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let tx = self.tx;
+        if input_index >= tx.inputs.len() {
+            return Err(DecodeError::InputIndexOutOfRange {
+                index: input_index,
+                inputs: tx.inputs.len(),
+            });
+        }
+
+        let base_type = sighash_type & 0x1f;
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+        let hash_prevouts = if anyone_can_pay {
+            [0u8; 32]
+        } else {
+            self.hash_prevouts
+        };
+
+        let hash_sequence =
+            if anyone_can_pay || base_type == SIGHASH_SINGLE || base_type == SIGHASH_NONE {
+                [0u8; 32]
+            } else {
+                self.hash_sequence
+            };
+
+        let hash_outputs = if base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE {
+            self.hash_outputs
+        } else if base_type == SIGHASH_SINGLE && input_index < tx.outputs.len() {
+            double_sha256(&serialize_output(&tx.outputs[input_index])?)
+        } else {
+            [0u8; 32]
+        };
+
+        let input = &tx.inputs[input_index];
+        let mut preimage = Vec::new();
+        preimage.extend(field_bytes(&tx.version)?);
+        preimage.extend_from_slice(&hash_prevouts);
+        preimage.extend_from_slice(&hash_sequence);
+        preimage.extend(field_bytes(&input.txid)?);
+        preimage.extend(field_bytes(&input.vout)?);
+        preimage.extend(CompactSize::encode(script_code.len() as u64));
+        preimage.extend_from_slice(script_code);
+        preimage.extend_from_slice(&amount.to_le_bytes());
+        preimage.extend(field_bytes(&input.sequence)?);
+        preimage.extend_from_slice(&hash_outputs);
+        preimage.extend(field_bytes(&tx.locktime)?);
+        preimage.extend_from_slice(&sighash_type.to_le_bytes());
+        Ok(preimage)
+    }
+}
+
+fn serialize_output(output: &TxOutput) -> Result<Vec<u8>, DecodeError> {
+    let mut out = field_bytes(&output.amount)?;
+    out.extend(field_bytes(&output.scriptpubkeysize)?);
+    out.extend(field_bytes(&output.scriptpubkey)?);
+    Ok(out)
+}
+
+fn field_bytes(field: &str) -> Result<Vec<u8>, DecodeError> {
+    hex::decode(field).map_err(|e| DecodeError::InvalidHex(e.to_string()))
+}
+
+fn compact_size_hex(value: usize) -> String {
+    hex::encode(CompactSize::encode(value as u64))
+}
+
+// Drop OP_CODESEPARATORs from the script code, skipping over push data so
+// that a 0xab byte inside a push is left alone.
+fn remove_codeseparators(script: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(script.len());
+    let mut pos = 0;
+    while pos < script.len() {
+        let opcode = script[pos];
+        let data_len = match opcode {
+            0x01..=0x4b => opcode as usize,
+            0x4c => script.get(pos + 1).map_or(0, |&n| n as usize) + 1,
+            0x4d => match script.get(pos + 1..pos + 3) {
+                Some(n) => u16::from_le_bytes([n[0], n[1]]) as usize + 2,
+                None => script.len(),
+            },
+            0x4e => match script.get(pos + 1..pos + 5) {
+                Some(n) => u32::from_le_bytes([n[0], n[1], n[2], n[3]]) as usize + 4,
+                None => script.len(),
+            },
+            _ => 0,
+        };
+        let end = (pos + 1).saturating_add(data_len).min(script.len());
+        if opcode != OP_CODESEPARATOR {
+            out.extend_from_slice(&script[pos..end]);
+        }
+        pos = end;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+
+    // Block 170: the first bitcoin transaction, spending a P2PK output.
+    const LEGACY_TX: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+    const LEGACY_PREVOUT_SCRIPT: &str = "410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac";
+
+    // BIP143 native P2WPKH example (unsigned transaction).
+    const BIP143_TX: &str = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000";
+    const BIP143_SCRIPT_CODE: &str = "76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac";
+
+    // BIP143 P2SH-P2WPKH example (unsigned transaction).
+    const BIP143_P2SH_TX: &str = "0100000001db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a54770100000000feffffff02b8b4eb0b000000001976a914a457b684d7f0d539a46a45bbc043f35b59d0d96388ac0008af2f000000001976a914fd270b1ee6abcaea97fea7ad0402e8bd8ad6d77c88ac92040000";
+    const BIP143_P2SH_SCRIPT_CODE: &str = "76a91479091972186c449eb1ded22b78e40d009bdf008988ac";
+
+    #[test]
+    fn test_legacy_signing_template() {
+        let tx = decode_transaction(LEGACY_TX).unwrap();
+        let script_code = hex::decode(LEGACY_PREVOUT_SCRIPT).unwrap();
+        let template = signing_template(&tx, 0, &script_code, SIGHASH_ALL, false, None).unwrap();
+
+        assert_eq!(
+            hex::encode(&template),
+            "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd37040000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3acffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac0000000001000000"
+        );
+        // The hash Satoshi's signature in this transaction commits to
+        assert_eq!(
+            hex::encode(double_sha256(&template)),
+            "7a05c6145f10101e9d6325494245adf1297d80f8f38d4d576d57cdba220bcb19"
+        );
+    }
+
+    #[test]
+    fn test_segwit_signing_template() {
+        let tx = decode_transaction(BIP143_TX).unwrap();
+        let script_code = hex::decode(BIP143_SCRIPT_CODE).unwrap();
+        let template =
+            signing_template(&tx, 1, &script_code, SIGHASH_ALL, true, Some(600_000_000)).unwrap();
+
+        assert_eq!(
+            hex::encode(&template),
+            "0100000096b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd3752b0a642eea2fb7ae638c36f6252b6750293dbe574a806984b8e4d8548339a3bef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a010000001976a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac0046c32300000000ffffffff863ef3e1a92afbfdb97f31ad0fc7683ee943e9abcf2501590ff8f6551f47e5e51100000001000000"
+        );
+        assert_eq!(
+            hex::encode(double_sha256(&template)),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+    }
+
+    #[test]
+    fn test_signing_template_errors() {
+        let tx = decode_transaction(BIP143_TX).unwrap();
+        let script_code = hex::decode(BIP143_SCRIPT_CODE).unwrap();
+
+        assert_eq!(
+            signing_template(&tx, 2, &script_code, SIGHASH_ALL, false, None),
+            Err(DecodeError::InputIndexOutOfRange {
+                index: 2,
+                inputs: 2
+            })
+        );
+        assert_eq!(
+            signing_template(&tx, 1, &script_code, SIGHASH_ALL, true, None),
+            Err(DecodeError::MissingAmount)
+        );
+    }
+
+    #[test]
+    fn test_sighash_bip143_native_p2wpkh() {
+        let tx = decode_transaction(BIP143_TX).unwrap();
+        let script_code = hex::decode(BIP143_SCRIPT_CODE).unwrap();
+
+        let preimage =
+            sighash_preimage(&tx, 1, &script_code, Some(600_000_000), SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(preimage),
+            "0100000096b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd3752b0a642eea2fb7ae638c36f6252b6750293dbe574a806984b8e4d8548339a3bef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a010000001976a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac0046c32300000000ffffffff863ef3e1a92afbfdb97f31ad0fc7683ee943e9abcf2501590ff8f6551f47e5e51100000001000000"
+        );
+        let digest = sighash_digest(&tx, 1, &script_code, Some(600_000_000), SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(digest),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+    }
+
+    #[test]
+    fn test_sighash_bip143_p2sh_p2wpkh() {
+        let tx = decode_transaction(BIP143_P2SH_TX).unwrap();
+        let script_code = hex::decode(BIP143_P2SH_SCRIPT_CODE).unwrap();
+
+        let preimage =
+            sighash_preimage(&tx, 0, &script_code, Some(1_000_000_000), SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(preimage),
+            "01000000b0287b4a252ac05af83d2dcef00ba313af78a3e9c329afa216eb3aa2a7b4613a18606b350cd8bf565266bc352f0caddcf01e8fa789dd8a15386327cf8cabe198db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a5477010000001976a91479091972186c449eb1ded22b78e40d009bdf008988ac00ca9a3b00000000feffffffde984f44532e2173ca0d64314fcefe6d30da6f8cf27bafa706da61df8a226c839204000001000000"
+        );
+        let digest =
+            sighash_digest(&tx, 0, &script_code, Some(1_000_000_000), SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(digest),
+            "64f3b0f4dd2bb3aa1ce8566d220cc74dda9df97d8490cc81d89d735c92e59fb6"
+        );
+    }
+
+    #[test]
+    fn test_sighash_legacy_digest() {
+        let tx = decode_transaction(LEGACY_TX).unwrap();
+        let script_code = hex::decode(LEGACY_PREVOUT_SCRIPT).unwrap();
+        let digest = sighash_digest(&tx, 0, &script_code, None, SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(digest),
+            "7a05c6145f10101e9d6325494245adf1297d80f8f38d4d576d57cdba220bcb19"
+        );
+    }
+
+    #[test]
+    fn test_segwit_v0_sighash_known_answer() {
+        let tx = decode_transaction(BIP143_TX).unwrap();
+        assert_eq!(
+            segwit_v0_sighash(&tx, 1, BIP143_SCRIPT_CODE, 600_000_000, 1).unwrap(),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+
+        let tx = decode_transaction(BIP143_P2SH_TX).unwrap();
+        assert_eq!(
+            segwit_v0_sighash(&tx, 0, BIP143_P2SH_SCRIPT_CODE, 1_000_000_000, 1).unwrap(),
+            "64f3b0f4dd2bb3aa1ce8566d220cc74dda9df97d8490cc81d89d735c92e59fb6"
+        );
+    }
+
+    // The sample spends a P2WPKH output; its witness signature commits to
+    // the sighash of input 0 over that output's 1_593_000 sats
+    #[cfg(feature = "verify")]
+    #[test]
+    fn test_segwit_v0_sighash_verifies_sample() {
+        use crate::hashes::hash160;
+        use crate::tests::SAMPLE_TX;
+        use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
+
+        const PREVOUT_SATS: u64 = 1_593_000;
+
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let items = tx.witness_items(0);
+        let sig = hex::decode(&items[0]).unwrap();
+        let pubkey = hex::decode(&items[1]).unwrap();
+        let script_code = format!("76a914{}88ac", hex::encode(hash160(&pubkey)));
+
+        let sighash = segwit_v0_sighash(&tx, 0, &script_code, PREVOUT_SATS, 1).unwrap();
+        let digest: [u8; 32] = hex::decode(sighash).unwrap().try_into().unwrap();
+        let (der, sighash_type) = sig.split_at(sig.len() - 1);
+        assert_eq!(sighash_type, [0x01]);
+
+        let message = Message::from_digest(digest);
+        let signature = Signature::from_der(der).unwrap();
+        let pubkey = PublicKey::from_slice(&pubkey).unwrap();
+        assert!(Secp256k1::verification_only()
+            .verify_ecdsa(&message, &signature, &pubkey)
+            .is_ok());
+
+        // Any other amount gives a different sighash
+        let other = segwit_v0_sighash(&tx, 0, &script_code, PREVOUT_SATS + 1, 1).unwrap();
+        let digest: [u8; 32] = hex::decode(other).unwrap().try_into().unwrap();
+        assert!(Secp256k1::verification_only()
+            .verify_ecdsa(&Message::from_digest(digest), &signature, &pubkey)
+            .is_err());
+    }
+
+    #[test]
+    fn test_sighash_cache_matches_uncached() {
+        let tx = decode_transaction(BIP143_TX).unwrap();
+        let cache = SighashCache::new(&tx).unwrap();
+        let script_code = hex::decode(BIP143_SCRIPT_CODE).unwrap();
+
+        for index in 0..tx.inputs.len() {
+            for sighash_type in [0x01, 0x02, 0x03, 0x81, 0x82, 0x83] {
+                let expected =
+                    sighash_digest(&tx, index, &script_code, Some(1_000), sighash_type as u32)
+                        .unwrap();
+                assert_eq!(
+                    cache
+                        .segwit_v0_sighash(index, BIP143_SCRIPT_CODE, 1_000, sighash_type)
+                        .unwrap(),
+                    hex::encode(expected)
+                );
+            }
+        }
+        assert!(cache
+            .segwit_v0_sighash(2, BIP143_SCRIPT_CODE, 1_000, 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_legacy_sighash_known_answer() {
+        let tx = decode_transaction(LEGACY_TX).unwrap();
+        assert_eq!(
+            legacy_sighash(&tx, 0, LEGACY_PREVOUT_SCRIPT, SIGHASH_ALL as u8).unwrap(),
+            "7a05c6145f10101e9d6325494245adf1297d80f8f38d4d576d57cdba220bcb19"
+        );
+    }
+
+    #[test]
+    fn test_legacy_sighash_single_bug() {
+        // Input 1 of 2 with a single output
+        let mut tx = decode_transaction(BIP143_TX).unwrap();
+        tx.outputs.truncate(1);
+        tx.outputcount = "01".to_string();
+
+        assert_eq!(
+            legacy_sighash(&tx, 1, BIP143_SCRIPT_CODE, SIGHASH_SINGLE as u8).unwrap(),
+            "0100000000000000000000000000000000000000000000000000000000000000"
+        );
+        // Input 0 still has its output and hashes normally
+        assert_ne!(
+            legacy_sighash(&tx, 0, BIP143_SCRIPT_CODE, SIGHASH_SINGLE as u8).unwrap(),
+            "0100000000000000000000000000000000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_legacy_sighash_errors() {
+        let tx = decode_transaction(LEGACY_TX).unwrap();
+        assert!(legacy_sighash(&tx, 1, LEGACY_PREVOUT_SCRIPT, 1).is_err());
+        assert!(legacy_sighash(&tx, 0, "zz", 1).is_err());
+    }
+
+    #[test]
+    fn test_sighash_flag_variants() {
+        let tx = decode_transaction(BIP143_TX).unwrap();
+        let script_code = hex::decode(BIP143_SCRIPT_CODE).unwrap();
+
+        // NONE drops outputs and zeroes the other inputs' sequences
+        let none = sighash_preimage(&tx, 0, &script_code, None, SIGHASH_NONE).unwrap();
+        let none_tx = decode_transaction(&hex::encode(&none[..none.len() - 4])).unwrap();
+        assert!(none_tx.outputs.is_empty());
+        assert_eq!(none_tx.inputs[1].sequence, "00000000");
+        assert_eq!(none_tx.inputs[0].sequence, "eeffffff");
+
+        // SINGLE keeps outputs up to the input's index, blanking earlier ones
+        let single = sighash_preimage(&tx, 1, &script_code, None, SIGHASH_SINGLE).unwrap();
+        let single_tx = decode_transaction(&hex::encode(&single[..single.len() - 4])).unwrap();
+        assert_eq!(single_tx.outputs.len(), 2);
+        assert_eq!(single_tx.outputs[0].amount, "ffffffffffffffff");
+        assert_eq!(single_tx.outputs[0].scriptpubkey, "");
+        assert_eq!(single_tx.outputs[1], tx.outputs[1]);
+
+        // ANYONECANPAY keeps only the signed input
+        let acp = sighash_preimage(
+            &tx,
+            1,
+            &script_code,
+            None,
+            SIGHASH_ALL | SIGHASH_ANYONECANPAY,
+        )
+        .unwrap();
+        assert_eq!(&acp[acp.len() - 4..], &[0x81, 0, 0, 0]);
+        let acp_tx = decode_transaction(&hex::encode(&acp[..acp.len() - 4])).unwrap();
+        assert_eq!(acp_tx.inputs.len(), 1);
+        assert_eq!(acp_tx.inputs[0].txid, tx.inputs[1].txid);
+
+        // BIP143 zeroes hashPrevouts/hashSequence under ANYONECANPAY
+        let acp = sighash_preimage(
+            &tx,
+            1,
+            &script_code,
+            Some(600_000_000),
+            SIGHASH_SINGLE | SIGHASH_ANYONECANPAY,
+        )
+        .unwrap();
+        assert_eq!(&acp[4..68], &[0u8; 64]);
+    }
+
+    #[test]
+    fn test_sighash_single_without_output() {
+        let mut tx = decode_transaction(BIP143_TX).unwrap();
+        tx.outputs.truncate(1);
+        let script_code = hex::decode(BIP143_SCRIPT_CODE).unwrap();
+
+        assert_eq!(
+            sighash_preimage(&tx, 1, &script_code, None, SIGHASH_SINGLE),
+            Err(SighashError::SingleWithoutOutput { index: 1 })
+        );
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(
+            sighash_digest(&tx, 1, &script_code, None, SIGHASH_SINGLE),
+            Ok(one)
+        );
+    }
+
+    #[test]
+    fn test_sighash_errors() {
+        let tx = decode_transaction(crate::tests::SAMPLE_TX).unwrap();
+        assert_eq!(
+            sighash_preimage(&tx, 1, &[], None, SIGHASH_ALL),
+            Err(SighashError::InputIndexOutOfRange {
+                index: 1,
+                inputs: 1
+            })
+        );
+        // The sample's only input carries a witness
+        assert_eq!(
+            sighash_preimage(&tx, 0, &[], None, SIGHASH_ALL),
+            Err(SighashError::MissingValue { index: 0 })
+        );
+    }
+
+    // A serialized vector of outputs, as Bitcoin Core's test framework
+    // stores the prevouts of its sighash vectors
+    fn prevouts(hex: &str) -> Vec<PrevOut> {
+        let bytes = hex::decode(hex).unwrap();
+        let (count, mut pos) = crate::read_compact_size(&bytes, 0).unwrap();
+        (0..count)
+            .map(|_| {
+                let (output, size) = crate::parse_output(&bytes, pos).unwrap();
+                pos += size;
+                PrevOut {
+                    value_sats: output.value_sats,
+                    script_pubkey: output.scriptpubkey,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_taproot_key_spend_sighash() {
+        // Key-spend vectors from Bitcoin Core's test framework
+        let tx = decode_transaction("0200000002fff49be59befe7566050737910f6ccdc5e749c7f8860ddc140386463d88c5ad0f3000000002cf68eb4a3d67f9d4c079249f7e4f27b8854815cb1ed13842d4fbf395f9e217fd605ee24090100000065235d9203f458520000000000160014b6d48333bb13b4c644e57c43a9a26df3a44b785e58020000000000001976a914eea9461a9e1e3f765d3af3e726162e0229fe3eb688ac58020000000000001976a9143a8869c9f2b5ea1d4ff3aeeb6a8fb2fffb1ad5fe88ac0ad7125c").unwrap();
+        let spent = prevouts("02591f220000000000225120f25ad35583ea31998d968871d7de1abd2a52f6fe4178b54ea158274806ff4ece48fb310000000000225120f25ad35583ea31998d968871d7de1abd2a52f6fe4178b54ea158274806ff4ece");
+        let hash = taproot_key_spend_sighash(&tx, 1, &spent, 0x01, None).unwrap();
+        assert_eq!(
+            hex::encode(hash),
+            "626ab955d58c9a8a600a0c580549d06dc7da4e802eb2a531f62a588e430967a8"
+        );
+
+        let tx = decode_transaction("020000000185bed1a6da2bffbd60ec681a1bfb71c5111d6395b99b3f8b2bf90167111bcb18f5010000007c83ace802ded24a00000000001600142c4698f9f7a773866879755aa78c516fb332af8e5802000000000000160014d38639dfbac4259323b98a472405db0c461b31fa61073747").unwrap();
+        let spent = prevouts("0144c84d0000000000225120e3f2107989c88e67296ab2faca930efa2e3a5bd3ff0904835a11c9e807458621");
+        let hash = taproot_key_spend_sighash(&tx, 0, &spent, 0x02, None).unwrap();
+        assert_eq!(
+            hex::encode(hash),
+            "3129de36a5d05fff97ffca31eb75fcccbbbc27b3147a7a36a9e4b45d8b625067"
+        );
+    }
+
+    #[test]
+    fn test_taproot_key_spend_sighash_annex() {
+        // SIGHASH_SINGLE|ANYONECANPAY with an annex
+        let tx = decode_transaction("0200000001df8123752e8f37d132c4e9f1ff7e4f9b986ade9211267e9ebd5fd22a5e718dec6d01000000ce4023b903cb7b23000000000017a914a18b36ea7a094db2f4940fc09edf154e86de7bd787580200000000000017a914afd0d512a2c5c2b40e25669e9cc460303c325b8b87580200000000000017a914a18b36ea7a094db2f4940fc09edf154e86de7bd787f6020000").unwrap();
+        let spent = prevouts("01ea49260000000000225120ab5e9800806bf18cb246edcf5fe63441208fe955a4b5a35bbff65f5db622a010");
+        let annex = hex::decode("507b979802e62d397acb29f56743a791894b99372872fc5af06a4f6e8d242d0615cda53062bb20e6ec79756fe39183f0c128adfe85559a8fa042b042c018aa8010143799e44f0893c40e1e").unwrap();
+        let hash = taproot_key_spend_sighash(&tx, 0, &spent, 0x83, Some(&annex)).unwrap();
+        assert_eq!(
+            hex::encode(hash),
+            "3b003000add359a364a156e73e02846782a59d0d95ca8c4638aaad99f2ef915c"
+        );
+
+        assert!(taproot_key_spend_sighash(&tx, 0, &spent, 0x04, None).is_err());
+        assert!(taproot_key_spend_sighash(&tx, 0, &[], 0x00, None).is_err());
+    }
+
+    #[test]
+    fn test_remove_codeseparators_skips_push_data() {
+        // OP_CODESEPARATOR, a push containing 0xab, OP_CODESEPARATOR, OP_CHECKSIG
+        let script = hex::decode("ab01ababac").unwrap();
+        assert_eq!(
+            remove_codeseparators(&script),
+            hex::decode("01abac").unwrap()
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::error::DecodeError;
+use crate::input_kind::InputKind;
+use crate::script::{instructions, Instruction};
+use crate::sighash::{SIGHASH_ALL, SIGHASH_NONE, SIGHASH_SINGLE};
+use crate::BitcoinTransaction;
+
+// secp256k1 group order / 2, big-endian
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DerSignature {
+    pub r: String,
+    pub s: String,
+    pub sighash_type: u8,
+}
+
+impl DerSignature {
+    // BIP62/BIP146 low-S: s must not exceed half the group order
+    pub fn is_low_s(&self) -> bool {
+        let s = hex::decode(&self.s).unwrap_or_default();
+        let s: Vec<u8> = s.into_iter().skip_while(|&b| b == 0).collect();
+        if s.len() != 32 {
+            return s.len() < 32;
+        }
+        s.as_slice() <= HALF_ORDER.as_slice()
+    }
+}
+
+// Parse a strict-DER (BIP66) ECDSA signature with its trailing sighash byte,
+// as found in scriptSigs and witnesses.
+pub fn parse_der_signature(sig: &[u8]) -> Option<DerSignature> {
+    if sig.len() < 9 || sig.len() > 73 {
+        return None;
+    }
+    if sig[0] != 0x30 || sig[1] as usize != sig.len() - 3 {
+        return None;
+    }
+
+    let len_r = sig[3] as usize;
+    if 5 + len_r >= sig.len() {
+        return None;
+    }
+    let len_s = sig[5 + len_r] as usize;
+    if len_r + len_s + 7 != sig.len() {
+        return None;
+    }
+
+    let r = &sig[4..4 + len_r];
+    let s = &sig[6 + len_r..6 + len_r + len_s];
+    if sig[2] != 0x02 || sig[4 + len_r] != 0x02 || !is_der_integer(r) || !is_der_integer(s) {
+        return None;
+    }
+
+    Some(DerSignature {
+        r: hex::encode(r),
+        s: hex::encode(s),
+        sighash_type: sig[sig.len() - 1],
+    })
+}
+
+// What a strict parser objects to in a signature or key that consensus
+// accepted before BIP66 and the strict-encoding policies
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EncodingIssue {
+    HighS,
+    NonCanonicalDer,
+    SighashZero,
+    // An uncompressed key with a 0x06/0x07 prefix carrying the y parity
+    HybridPubkey,
+}
+
+impl fmt::Display for EncodingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EncodingIssue::HighS => "high-S",
+            EncodingIssue::NonCanonicalDer => "non-canonical DER",
+            EncodingIssue::SighashZero => "sighash 0x00 treated as ALL",
+            EncodingIssue::HybridPubkey => "hybrid pubkey",
+        })
+    }
+}
+
+// Parses an ECDSA signature the way OpenSSL did before BIP66: lengths may
+// be long-form or disagree with the data, integers may be negative or
+// padded, and junk may follow S. R and S are kept as encoded. Returns the
+// signature with everything a strict parser would reject.
+pub fn parse_signature_lax(sig: &[u8]) -> Option<(DerSignature, Vec<EncodingIssue>)> {
+    let (&sighash_type, der) = sig.split_last()?;
+    if der.first() != Some(&0x30) {
+        return None;
+    }
+    let mut pos = 1;
+    der_length(der, &mut pos)?;
+    let r = der_integer(der, &mut pos)?;
+    let s = der_integer(der, &mut pos)?;
+
+    let sig_parsed = DerSignature {
+        r: hex::encode(r),
+        s: hex::encode(s),
+        sighash_type,
+    };
+    let mut issues = Vec::new();
+    if parse_der_signature(sig).is_none() {
+        issues.push(EncodingIssue::NonCanonicalDer);
+    }
+    if !sig_parsed.is_low_s() {
+        issues.push(EncodingIssue::HighS);
+    }
+    if sighash_type == 0 {
+        issues.push(EncodingIssue::SighashZero);
+    }
+    Some((sig_parsed, issues))
+}
+
+// A BER length at `pos`, short or long form, advancing past it
+fn der_length(der: &[u8], pos: &mut usize) -> Option<usize> {
+    let first = *der.get(*pos)?;
+    *pos += 1;
+    if first < 0x80 {
+        return Some(first as usize);
+    }
+    let len_bytes = der.get(*pos..*pos + (first & 0x7f) as usize)?;
+    if len_bytes.is_empty() || len_bytes.len() > 4 {
+        return None;
+    }
+    *pos += len_bytes.len();
+    Some(len_bytes.iter().fold(0, |len, &b| len << 8 | b as usize))
+}
+
+// The contents of the INTEGER at `pos`, advancing past it
+fn der_integer<'a>(der: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    if der.get(*pos) != Some(&0x02) {
+        return None;
+    }
+    *pos += 1;
+    let len = der_length(der, pos)?;
+    let int = der.get(*pos..pos.checked_add(len)?)?;
+    *pos += len;
+    Some(int)
+}
+
+pub fn is_hybrid_pubkey(data: &[u8]) -> bool {
+    data.len() == 65 && matches!(data[0], 0x06 | 0x07)
+}
+
+// Every signature and key encoding issue in an input's scriptSig pushes
+// and witness items
+pub fn encoding_issues(tx: &BitcoinTransaction, index: usize) -> Vec<EncodingIssue> {
+    let mut issues = Vec::new();
+    for element in input_elements(tx, index) {
+        if let Some((_, sig_issues)) = parse_signature_lax(&element) {
+            issues.extend(sig_issues);
+        } else if is_hybrid_pubkey(&element) {
+            issues.push(EncodingIssue::HybridPubkey);
+        }
+    }
+    issues
+}
+
+// Adds each input's encoding issues to its warnings
+pub(crate) fn check_signature_encodings(tx: &mut BitcoinTransaction) {
+    for index in 0..tx.inputs.len() {
+        let issues = encoding_issues(tx, index);
+        tx.inputs[index]
+            .warnings
+            .extend(issues.iter().map(ToString::to_string));
+    }
+}
+
+// Strict mode, for validating modern transactions: the first encoding
+// issue is an error
+pub fn check_strict_encoding(tx: &BitcoinTransaction) -> Result<(), DecodeError> {
+    for index in 0..tx.inputs.len() {
+        if let Some(issue) = encoding_issues(tx, index).first() {
+            return Err(DecodeError::StrictEncoding {
+                input: index,
+                reason: issue.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// How many signatures use each sighash type, over every scriptSig push and
+// witness item that parses as a DER signature.
+pub fn sighash_summary(tx: &BitcoinTransaction) -> HashMap<u8, usize> {
+    let mut summary = HashMap::new();
+    for i in 0..tx.inputs.len() {
+        for sig in input_signatures(tx, i) {
+            *summary.entry(sig.sighash_type).or_insert(0) += 1;
+        }
+    }
+    summary
+}
+
+// Warnings for sighash types that sign something other than intended: a
+// base type (the low five bits) other than ALL, NONE or SINGLE, and
+// SIGHASH_SINGLE on an input with no output at its index, where the legacy
+// algorithm signs the constant 1 instead of an output.
+pub fn check_sighash_types(tx: &BitcoinTransaction) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for i in 0..tx.inputs.len() {
+        for sig in input_signatures(tx, i) {
+            match u32::from(sig.sighash_type & 0x1f) {
+                SIGHASH_ALL | SIGHASH_NONE => {}
+                // An input warning instead, see EncodingIssue::SighashZero
+                _ if sig.sighash_type == 0 => {}
+                SIGHASH_SINGLE if i < tx.outputs.len() => {}
+                SIGHASH_SINGLE => warnings.push(format!(
+                    "input {} signs with SIGHASH_SINGLE but there is no output {}",
+                    i, i
+                )),
+                _ => warnings.push(format!(
+                    "input {} has a signature with unknown sighash type 0x{:02x}",
+                    i, sig.sighash_type
+                )),
+            }
+        }
+    }
+    warnings
+}
+
+// The DER signatures among an input's scriptSig pushes and witness items
+fn input_signatures(tx: &BitcoinTransaction, index: usize) -> Vec<DerSignature> {
+    input_elements(tx, index)
+        .iter()
+        .filter_map(|element| parse_signature_lax(element))
+        .map(|(sig, _)| sig)
+        .collect()
+}
+
+// An input's scriptSig pushes and witness items. Taproot witnesses are left
+// out: their 64-byte Schnorr signatures could happen to look like DER.
+fn input_elements(tx: &BitcoinTransaction, index: usize) -> Vec<Vec<u8>> {
+    let script_sig = hex::decode(&tx.inputs[index].scriptsig).unwrap_or_default();
+    let mut elements: Vec<Vec<u8>> = instructions(&script_sig)
+        .map_while(Result::ok)
+        .filter_map(|(_, instruction)| match instruction {
+            Instruction::Push { data, .. } => Some(data.to_vec()),
+            _ => None,
+        })
+        .collect();
+    if tx.inputs[index].input_kind != InputKind::Taproot {
+        elements.extend(
+            tx.witness_items(index)
+                .iter()
+                .map(|item| hex::decode(item).unwrap_or_default()),
+        );
+    }
+    elements
+}
+
+// Non-empty, positive and without unnecessary leading zeros
+fn is_der_integer(int: &[u8]) -> bool {
+    !int.is_empty() && int[0] & 0x80 == 0 && !(int.len() > 1 && int[0] == 0 && int[1] & 0x80 == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+
+    const SAMPLE_SIG: &str = "304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01";
+    const HIGH_S_SIG: &str = "304502204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd41022100e7eadd137135f821b79f5b5322ed6f6137921779f39c5a19b7b03ce459a9243801";
+
+    #[test]
+    fn test_parse_der_signature() {
+        let sig = parse_der_signature(&hex::decode(SAMPLE_SIG).unwrap()).unwrap();
+        assert_eq!(
+            sig.r,
+            "7bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee"
+        );
+        assert_eq!(sig.sighash_type, 0x01);
+        assert!(sig.is_low_s());
+
+        let sig = parse_der_signature(&hex::decode(HIGH_S_SIG).unwrap()).unwrap();
+        assert!(!sig.is_low_s());
+    }
+
+    #[test]
+    fn test_sighash_summary() {
+        let tx = crate::decode_transaction(crate::tests::SAMPLE_TX).unwrap();
+        assert_eq!(sighash_summary(&tx), HashMap::from([(0x01, 1)]));
+
+        // SIGHASH_SINGLE|ANYONECANPAY in a scriptSig push
+        let mut tx = tx;
+        let mut sig = hex::decode(SAMPLE_SIG).unwrap();
+        *sig.last_mut().unwrap() = 0x83;
+        tx.inputs[0].scriptsig = format!("{:02x}{}", sig.len(), hex::encode(&sig));
+        assert_eq!(sighash_summary(&tx), HashMap::from([(0x01, 1), (0x83, 1)]));
+    }
+
+    #[test]
+    fn test_check_sighash_types() {
+        let tx = crate::decode_transaction(crate::tests::SAMPLE_TX).unwrap();
+        assert!(check_sighash_types(&tx).is_empty());
+
+        // Two inputs and one output: input 1 signs SIGHASH_SINGLE with
+        // nothing to pair it with, input 0 uses an undefined base type
+        let sig = |sighash: u8| {
+            let mut sig = hex::decode(SAMPLE_SIG).unwrap();
+            *sig.last_mut().unwrap() = sighash;
+            sig
+        };
+        let key = vec![0x02; 33];
+        let tx = TxBuilder::new()
+            .add_input(&"11".repeat(32), 0)
+            .add_witness(vec![sig(0x84), key.clone()])
+            .add_input(&"22".repeat(32), 0)
+            .add_witness(vec![sig(0x03), key])
+            .add_output_sats(1_000, &[0x6a])
+            .build()
+            .unwrap();
+        assert_eq!(
+            check_sighash_types(&tx),
+            vec![
+                "input 0 has a signature with unknown sighash type 0x84",
+                "input 1 signs with SIGHASH_SINGLE but there is no output 1"
+            ]
+        );
+        assert!(tx.warnings.contains(&check_sighash_types(&tx)[1]));
+    }
+
+    // 23b397edccd3740a74adb603c9756370fafcde9bcc4483eb271ecad09a94dd63, from
+    // 2011: its signature's S has the high bit set but no 0x00 pad, so it is
+    // a negative DER integer (and high-S)
+    const NON_DER_TX: &str = "0100000001b14bdcbc3e01bdaad36cc08e81e69c82e1060bc14e518db2b49aa43ad90ba26000000000490047304402203f16c6f40162ab686621ef3000b04e75418a0c0cb2d8aebeac894ae360ac1e780220ddc15ecdfc3507ac48e1681a33eb60996631bf6bf5bc0a0682c4db743ce7ca2b01ffffffff0140420f00000000001976a914660d4ef3a743e3e696ad990364e555c271ad504b88ac00000000";
+
+    #[test]
+    fn test_pre_bip66_signature() {
+        let tx = crate::decode_transaction(NON_DER_TX).unwrap();
+        assert_eq!(
+            tx.txid().unwrap(),
+            "23b397edccd3740a74adb603c9756370fafcde9bcc4483eb271ecad09a94dd63"
+        );
+        assert_eq!(tx.inputs[0].warnings, vec!["non-canonical DER", "high-S"]);
+        assert_eq!(sighash_summary(&tx), HashMap::from([(0x01, 1)]));
+
+        assert_eq!(
+            crate::decode_transaction_strict(NON_DER_TX, &Default::default()),
+            Err(DecodeError::StrictEncoding {
+                input: 0,
+                reason: "non-canonical DER".to_string()
+            })
+        );
+        assert!(
+            crate::decode_transaction_strict(crate::tests::SAMPLE_TX, &Default::default()).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_lax_encodings() {
+        // Long-form lengths, sighash 0x00
+        let sig = hex::decode(SAMPLE_SIG).unwrap();
+        let mut long_form = vec![0x30, 0x81, sig[1], 0x02, 0x81];
+        long_form.extend(&sig[3..sig.len() - 1]);
+        long_form.push(0x00);
+        let (parsed, issues) = parse_signature_lax(&long_form).unwrap();
+        assert_eq!(
+            parsed,
+            parse_der_signature(&sig)
+                .map(|sig| DerSignature {
+                    sighash_type: 0,
+                    ..sig
+                })
+                .unwrap()
+        );
+        assert_eq!(
+            issues,
+            vec![EncodingIssue::NonCanonicalDer, EncodingIssue::SighashZero]
+        );
+        assert!(parse_signature_lax(&[0x30, 0x06, 0x02, 0x05, 0x01]).is_none());
+
+        let mut tx = crate::decode_transaction(crate::tests::SAMPLE_TX).unwrap();
+        let hybrid = format!("06{}", "11".repeat(64));
+        tx.inputs[0].scriptsig = format!("41{}", hybrid);
+        assert_eq!(encoding_issues(&tx, 0), vec![EncodingIssue::HybridPubkey]);
+        assert_eq!(EncodingIssue::HybridPubkey.to_string(), "hybrid pubkey");
+    }
+
+    #[test]
+    fn test_parse_der_signature_rejects_non_der() {
+        // A compressed pubkey
+        let pubkey =
+            hex::decode("0260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff")
+                .unwrap();
+        assert!(parse_der_signature(&pubkey).is_none());
+
+        // R padded with an unnecessary zero byte
+        let mut padded = hex::decode(SAMPLE_SIG).unwrap();
+        padded.splice(4..4, [0x00]);
+        padded[1] += 1;
+        padded[3] += 1;
+        assert!(parse_der_signature(&padded).is_none());
+    }
+}
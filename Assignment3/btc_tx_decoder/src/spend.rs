@@ -0,0 +1,297 @@
+use serde::Serialize;
+
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::consensus::PrevOut;
+use crate::hashes::{hash160, sha256};
+use crate::script::{instructions, Instruction};
+use crate::witness::Witness;
+use crate::BitcoinTransaction;
+
+const ANNEX_TAG: u8 = 0x50;
+// A taproot control block: the leaf version and internal key, then up to 128
+// 32-byte merkle path steps
+const CONTROL_BASE_SIZE: usize = 33;
+const CONTROL_NODE_SIZE: usize = 32;
+const CONTROL_MAX_NODES: usize = 128;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SpendCheck {
+    Ok,
+    // The scriptSig or witness doesn't have the shape the spent output needs
+    Mismatch { reason: String },
+}
+
+impl SpendCheck {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, SpendCheck::Ok)
+    }
+}
+
+// Checks that each input's scriptSig and witness fit the output it spends:
+// the revealed keys and scripts hash to what the output commits to, and the
+// stacks have the right number and size of items. `prevouts` holds the spent
+// output of every input, in order. Nothing is executed and no signature is
+// verified, see verify::verify_input for that.
+pub fn check_spends(
+    tx: &BitcoinTransaction,
+    prevouts: &[PrevOut],
+) -> Result<Vec<SpendCheck>, String> {
+    if prevouts.len() != tx.inputs.len() {
+        return Err(format!(
+            "{} prevouts supplied for {} inputs",
+            prevouts.len(),
+            tx.inputs.len()
+        ));
+    }
+
+    let checks = tx
+        .inputs
+        .iter()
+        .zip(prevouts)
+        .enumerate()
+        .map(|(i, (input, prevout))| {
+            let script_pubkey = hex::decode(&prevout.script_pubkey)
+                .map_err(|_| "invalid prevout scriptPubKey hex".to_string())?;
+            let script_sig =
+                hex::decode(&input.scriptsig).map_err(|_| "invalid scriptSig hex".to_string())?;
+            check_spend(&script_pubkey, &script_sig, Witness::from_tx(tx, i).items())
+        })
+        .map(|result| match result {
+            Ok(()) => SpendCheck::Ok,
+            Err(reason) => SpendCheck::Mismatch { reason },
+        })
+        .collect();
+    Ok(checks)
+}
+
+fn check_spend(script_pubkey: &[u8], script_sig: &[u8], witness: &[Vec<u8>]) -> Result<(), String> {
+    match classify_script(script_pubkey, &[], &ClassifyOptions::default()) {
+        ScriptType::P2pkh => {
+            let [_, pubkey] = pushes(script_sig)?[..] else {
+                return Err("P2PKH scriptSig must be <sig> <pubkey>".to_string());
+            };
+            if hash160(pubkey)[..] != script_pubkey[3..23] {
+                return Err("public key hash mismatch".to_string());
+            }
+            no_witness(witness)
+        }
+        ScriptType::P2sh { .. } => {
+            let Some(&redeem_script) = pushes(script_sig)?.last() else {
+                return Err("empty P2SH scriptSig".to_string());
+            };
+            if hash160(redeem_script)[..] != script_pubkey[2..22] {
+                return Err("redeem script hash mismatch".to_string());
+            }
+            match classify_script(redeem_script, &[], &ClassifyOptions::default()) {
+                // P2SH-wrapped segwit: the redeem script must be pushed alone
+                ScriptType::P2wpkh
+                | ScriptType::P2wsh { .. }
+                | ScriptType::P2tr
+                | ScriptType::WitnessUnknown { .. } => {
+                    if pushes(script_sig)?.len() != 1 {
+                        return Err(
+                            "P2SH-wrapped segwit scriptSig must only push the redeem script"
+                                .to_string(),
+                        );
+                    }
+                    check_witness_program(redeem_script, witness)
+                }
+                _ => no_witness(witness),
+            }
+        }
+        ScriptType::P2wpkh
+        | ScriptType::P2wsh { .. }
+        | ScriptType::P2tr
+        | ScriptType::WitnessUnknown { .. } => {
+            if !script_sig.is_empty() {
+                return Err("native segwit spend with a non-empty scriptSig".to_string());
+            }
+            check_witness_program(script_pubkey, witness)
+        }
+        _ => no_witness(witness),
+    }
+}
+
+// The witness against the program it spends
+fn check_witness_program(program: &[u8], witness: &[Vec<u8>]) -> Result<(), String> {
+    match classify_script(program, &[], &ClassifyOptions::default()) {
+        ScriptType::P2wpkh => {
+            let [_, pubkey] = witness else {
+                return Err(format!(
+                    "P2WPKH witness must have 2 items, has {}",
+                    witness.len()
+                ));
+            };
+            if hash160(pubkey)[..] != program[2..] {
+                return Err("public key hash mismatch".to_string());
+            }
+            Ok(())
+        }
+        ScriptType::P2wsh { .. } => {
+            let Some(witness_script) = witness.last() else {
+                return Err("empty P2WSH witness".to_string());
+            };
+            if sha256(witness_script)[..] != program[2..] {
+                return Err("witness script hash mismatch".to_string());
+            }
+            Ok(())
+        }
+        ScriptType::P2tr => {
+            let items = match witness {
+                [rest @ .., last] if !rest.is_empty() && last.first() == Some(&ANNEX_TAG) => rest,
+                _ => witness,
+            };
+            match items {
+                [] => Err("empty taproot witness".to_string()),
+                [signature] if !matches!(signature.len(), 64 | 65) => Err(format!(
+                    "key-path signature must be 64 or 65 bytes, is {}",
+                    signature.len()
+                )),
+                [_] => Ok(()),
+                [.., control_block] => {
+                    let nodes = control_block.len().wrapping_sub(CONTROL_BASE_SIZE);
+                    if control_block.len() < CONTROL_BASE_SIZE
+                        || nodes % CONTROL_NODE_SIZE != 0
+                        || nodes / CONTROL_NODE_SIZE > CONTROL_MAX_NODES
+                    {
+                        return Err(format!(
+                            "control block is {} bytes, not 33 plus a multiple of 32",
+                            control_block.len()
+                        ));
+                    }
+                    Ok(())
+                }
+            }
+        }
+        // Future witness versions have no rules yet
+        _ => Ok(()),
+    }
+}
+
+fn no_witness(witness: &[Vec<u8>]) -> Result<(), String> {
+    if witness.is_empty() {
+        Ok(())
+    } else {
+        Err("witness data spending a non-segwit output".to_string())
+    }
+}
+
+// The data pushes making up a scriptSig, which may hold nothing else
+fn pushes(script: &[u8]) -> Result<Vec<&[u8]>, String> {
+    instructions(script)
+        .map(|instruction| match instruction {
+            Ok((_, Instruction::Push { data, .. })) => Ok(data),
+            Ok((_, Instruction::Op(_))) => Err("scriptSig is not push-only".to_string()),
+            Err(e) => Err(e),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+    use crate::script::encode_push;
+
+    const SIGNATURE: [u8; 72] = [0x30; 72];
+    const PUBKEY: [u8; 33] = [0x02; 33];
+
+    fn prevout(script_pubkey: Vec<u8>) -> PrevOut {
+        PrevOut {
+            value_sats: 100_000,
+            script_pubkey: hex::encode(script_pubkey),
+        }
+    }
+
+    fn p2wpkh() -> Vec<u8> {
+        [&[0x00, 0x14][..], &hash160(&PUBKEY)].concat()
+    }
+
+    fn p2wsh(witness_script: &[u8]) -> Vec<u8> {
+        [&[0x00, 0x20][..], &sha256(witness_script)].concat()
+    }
+
+    #[test]
+    fn test_check_spends() {
+        let multisig = [&[0x51, 0x21][..], &PUBKEY, &[0x51, 0xae]].concat();
+        let p2pkh = [&[0x76, 0xa9, 0x14][..], &hash160(&PUBKEY), &[0x88, 0xac]].concat();
+        let p2sh = [&[0xa9, 0x14][..], &hash160(&p2wpkh()), &[0x87]].concat();
+        let p2tr = [&[0x51, 0x20][..], &[0x07; 32]].concat();
+        let txid = |i: u8| hex::encode([i; 32]);
+
+        let tx = TxBuilder::new()
+            .add_input(&txid(1), 0)
+            .script_sig(&[&encode_push(&SIGNATURE)[..], &encode_push(&PUBKEY)].concat())
+            .sequence(0)
+            .add_input(&txid(2), 0)
+            .sequence(0)
+            .add_witness(vec![SIGNATURE.to_vec(), PUBKEY.to_vec()])
+            .add_input(&txid(3), 0)
+            .script_sig(&encode_push(&p2wpkh()))
+            .sequence(0)
+            .add_witness(vec![SIGNATURE.to_vec(), PUBKEY.to_vec()])
+            .add_input(&txid(4), 0)
+            .sequence(0)
+            .add_witness(vec![vec![], SIGNATURE.to_vec(), multisig.clone()])
+            .add_input(&txid(5), 0)
+            .sequence(0)
+            .add_witness(vec![vec![0x09; 64]])
+            .add_output_sats(90_000, &p2tr)
+            .build()
+            .unwrap();
+        let prevouts = [
+            prevout(p2pkh),
+            prevout(p2wpkh()),
+            prevout(p2sh),
+            prevout(p2wsh(&multisig)),
+            prevout(p2tr.clone()),
+        ];
+        let checks = check_spends(&tx, &prevouts).unwrap();
+        assert!(checks.iter().all(SpendCheck::is_ok), "{:?}", checks);
+
+        assert!(check_spends(&tx, &prevouts[..1]).is_err());
+    }
+
+    #[test]
+    fn test_check_spends_mismatches() {
+        let witness_script = [&[0x21][..], &PUBKEY, &[0xac]].concat();
+        let other_script = [&[0x21][..], &[0x03; 33], &[0xac]].concat();
+        let tx = TxBuilder::new()
+            .add_input(&"01".repeat(32), 0)
+            .sequence(0)
+            .add_witness(vec![SIGNATURE.to_vec(), other_script])
+            .add_input(&"02".repeat(32), 0)
+            .sequence(0)
+            .add_witness(vec![vec![0x09; 70]])
+            .add_input(&"03".repeat(32), 0)
+            .script_sig(&[0x51])
+            .sequence(0)
+            .add_witness(vec![SIGNATURE.to_vec(), PUBKEY.to_vec()])
+            .add_output_sats(90_000, &p2wpkh())
+            .build()
+            .unwrap();
+        let prevouts = [
+            prevout(p2wsh(&witness_script)),
+            prevout([&[0x51, 0x20][..], &[0x07; 32]].concat()),
+            prevout(vec![0x51]),
+        ];
+
+        let reasons: Vec<String> = check_spends(&tx, &prevouts)
+            .unwrap()
+            .into_iter()
+            .map(|check| match check {
+                SpendCheck::Mismatch { reason } => reason,
+                SpendCheck::Ok => "ok".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "witness script hash mismatch",
+                "key-path signature must be 64 or 65 bytes, is 70",
+                "witness data spending a non-segwit output",
+            ]
+        );
+    }
+}
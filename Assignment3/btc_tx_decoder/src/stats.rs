@@ -0,0 +1,64 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::error::DecodeError;
+use crate::{decode_transaction_with_limits, BitcoinTransaction, DecodeLimits};
+
+// What a decode went through, for tracking throughput
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeStats {
+    // Up to the end of the locktime; anything after it is ignored
+    pub bytes_consumed: usize,
+    pub inputs: usize,
+    pub outputs: usize,
+    // Across every input's stack
+    pub witness_items: usize,
+    // Hex decoding, parsing and annotation
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for DecodeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes, {} inputs, {} outputs, {} witness items in {:.2?}",
+            self.bytes_consumed, self.inputs, self.outputs, self.witness_items, self.elapsed
+        )
+    }
+}
+
+// decode_transaction, timed and counted
+pub fn decode_with_stats(input: &str) -> Result<(BitcoinTransaction, DecodeStats), DecodeError> {
+    let start = Instant::now();
+    let tx = decode_transaction_with_limits(input, &DecodeLimits::default())?;
+    let elapsed = start.elapsed();
+
+    let stats = DecodeStats {
+        bytes_consumed: tx.total_size(),
+        inputs: tx.inputs.len(),
+        outputs: tx.outputs.len(),
+        witness_items: tx.witness.iter().map(|stack| stack.items.len()).sum(),
+        elapsed,
+    };
+    Ok((tx, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::consolidation;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_decode_with_stats() {
+        let (tx, stats) = decode_with_stats(SAMPLE_TX).unwrap();
+        assert_eq!(tx, crate::decode_transaction(SAMPLE_TX).unwrap());
+        assert_eq!(stats.bytes_consumed, SAMPLE_TX.len() / 2);
+        assert_eq!((stats.inputs, stats.outputs), (1, 2));
+        assert_eq!(stats.witness_items, 2);
+
+        let (_, stats) = decode_with_stats(&consolidation(50)).unwrap();
+        assert_eq!((stats.inputs, stats.outputs), (50, 1));
+        assert_eq!(stats.witness_items, 100);
+    }
+}
@@ -0,0 +1,115 @@
+// Made-up transactions of a given shape, for benchmarks and tests that need
+// something bigger than a fixture. Keys, signatures and txids are filler
+// bytes: the transactions decode, but don't verify.
+
+use crate::builder::TxBuilder;
+
+// A DER signature with its sighash byte, and a compressed key
+const SIGNATURE_LEN: usize = 72;
+const PUBKEY_LEN: usize = 33;
+
+// `inputs` P2WPKH inputs swept into a single P2WPKH output
+pub fn consolidation(inputs: usize) -> String {
+    let mut builder = TxBuilder::new();
+    for i in 0..inputs {
+        builder = builder
+            .add_input(&txid(i), i as u32 % 4)
+            .sequence(0xfffffffd)
+            .add_witness(vec![signature(), vec![0x02; PUBKEY_LEN]]);
+    }
+    builder
+        .add_output_sats(inputs as u64 * 10_000, &p2wpkh(0))
+        .build_hex()
+        .expect("a well-formed consolidation")
+}
+
+// One P2WPKH input paying 546 sats to each of `outputs` distinct P2WPKH
+// outputs
+pub fn dusting(outputs: usize) -> String {
+    let mut builder = TxBuilder::new()
+        .add_input(&txid(0), 0)
+        .add_witness(vec![signature(), vec![0x03; PUBKEY_LEN]]);
+    for i in 0..outputs {
+        builder = builder.add_output_sats(546, &p2wpkh(i));
+    }
+    builder
+        .build_hex()
+        .expect("a well-formed dusting transaction")
+}
+
+// A taproot script-path spend: a Schnorr signature, a leaf script of
+// `script_len` bytes and a control block eight levels deep
+pub fn taproot_script_path(script_len: usize) -> String {
+    // <key> OP_CHECKSIG, padded out with OP_NOPs in front
+    let mut script = vec![0x61; script_len.saturating_sub(34)];
+    script.push(0x20);
+    script.extend([0x05; 32]);
+    script.push(0xac);
+
+    let mut control_block = vec![0xc0];
+    control_block.extend([0x06; 32 * 9]);
+
+    TxBuilder::new()
+        .add_input(&txid(0), 0)
+        .sequence(0xfffffffd)
+        .add_witness(vec![vec![0x07; 64], script, control_block])
+        .add_output_sats(90_000, &[&[0x51, 0x20][..], &[0x08; 32]].concat())
+        .build_hex()
+        .expect("a well-formed taproot spend")
+}
+
+// In display order, for TxBuilder
+fn txid(i: usize) -> String {
+    let mut txid = [0x11; 32];
+    txid[..8].copy_from_slice(&(i as u64).to_le_bytes());
+    hex::encode(txid)
+}
+
+fn p2wpkh(i: usize) -> Vec<u8> {
+    let mut script = vec![0x00, 0x14];
+    script.extend([0x22; 12]);
+    script.extend((i as u64).to_le_bytes());
+    script
+}
+
+fn signature() -> Vec<u8> {
+    let mut signature = vec![0x30; SIGNATURE_LEN];
+    signature[SIGNATURE_LEN - 1] = 0x01;
+    signature
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_transaction;
+    use crate::input_kind::InputKind;
+
+    #[test]
+    fn test_consolidation() {
+        let tx = decode_transaction(&consolidation(1_000)).unwrap();
+        assert_eq!(tx.inputs.len(), 1_000);
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.inputcount, "fde803");
+        assert!(tx
+            .iter_inputs()
+            .all(|input| input.input_kind == InputKind::NativeSegwitV0));
+    }
+
+    #[test]
+    fn test_dusting() {
+        let tx = decode_transaction(&dusting(10_000)).unwrap();
+        assert_eq!(tx.outputs.len(), 10_000);
+        assert!(tx.iter_outputs().all(|output| output.value_sats == 546));
+        assert_ne!(tx.outputs[0].scriptpubkey, tx.outputs[1].scriptpubkey);
+    }
+
+    #[test]
+    fn test_taproot_script_path() {
+        let tx = decode_transaction(&taproot_script_path(10_000)).unwrap();
+        assert_eq!(tx.inputs[0].input_kind, InputKind::Taproot);
+        let items = tx.witness_items(0);
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].len() / 2, 10_000);
+        assert_eq!(items[2].len() / 2, 33 + 32 * 8);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::Serialize;
+
+use crate::witness::Witness;
+
+// BIP341: an annex is a last witness item starting with 0x50, and a control
+// block is 33 bytes plus up to 128 32-byte Merkle nodes.
+const ANNEX_TAG: u8 = 0x50;
+const CONTROL_BLOCK_BASE_SIZE: usize = 33;
+const TAPROOT_NODE_SIZE: usize = 32;
+const MAX_MERKLE_DEPTH: usize = 128;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ControlBlock {
+    // The leaf version, 0xc0 for tapscript
+    pub leaf_version: u8,
+    // Parity of the output key's Y coordinate
+    pub output_key_parity: u8,
+    pub internal_key: String,
+    // Nodes from the leaf up to the root
+    pub merkle_branch: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaprootSpend {
+    KeyPath {
+        signature: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annex: Option<String>,
+    },
+    ScriptPath {
+        // The leaf script being run and the stack items it is run with
+        script: String,
+        stack: Vec<String>,
+        control_block: ControlBlock,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annex: Option<String>,
+    },
+    // An empty witness or a malformed control block
+    Invalid {
+        reason: String,
+    },
+}
+
+// Reads a witness as a taproot (segwit v1) spend. Whether the input really
+// spends a P2TR output needs its prevout, so that is left to the caller.
+pub fn parse_taproot_witness(witness: &Witness) -> TaprootSpend {
+    let mut items = witness.items();
+    let annex = match items {
+        [rest @ .., last] if !rest.is_empty() && last.first() == Some(&ANNEX_TAG) => {
+            items = rest;
+            Some(hex::encode(last))
+        }
+        _ => None,
+    };
+
+    match items {
+        [] => TaprootSpend::Invalid {
+            reason: "empty witness".to_string(),
+        },
+        [signature] => TaprootSpend::KeyPath {
+            signature: hex::encode(signature),
+            annex,
+        },
+        [stack @ .., script, control_block] => match parse_control_block(control_block) {
+            Ok(control_block) => TaprootSpend::ScriptPath {
+                script: hex::encode(script),
+                stack: stack.iter().map(hex::encode).collect(),
+                control_block,
+                annex,
+            },
+            Err(reason) => TaprootSpend::Invalid { reason },
+        },
+    }
+}
+
+fn parse_control_block(bytes: &[u8]) -> Result<ControlBlock, String> {
+    let path_len = bytes.len().wrapping_sub(CONTROL_BLOCK_BASE_SIZE);
+    if bytes.len() < CONTROL_BLOCK_BASE_SIZE
+        || !path_len.is_multiple_of(TAPROOT_NODE_SIZE)
+        || path_len / TAPROOT_NODE_SIZE > MAX_MERKLE_DEPTH
+    {
+        return Err(format!("control block has invalid size {}", bytes.len()));
+    }
+
+    Ok(ControlBlock {
+        leaf_version: bytes[0] & 0xfe,
+        output_key_parity: bytes[0] & 1,
+        internal_key: hex::encode(&bytes[1..CONTROL_BLOCK_BASE_SIZE]),
+        merkle_branch: bytes[CONTROL_BLOCK_BASE_SIZE..]
+            .chunks(TAPROOT_NODE_SIZE)
+            .map(hex::encode)
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP341's unspendable "NUMS" point, a common internal key for
+    // script-only outputs
+    const NUMS_KEY: &str = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        hex::decode(hex).unwrap()
+    }
+
+    #[test]
+    fn test_key_path_spend() {
+        let signature = vec![0x11; 64];
+        let spend = parse_taproot_witness(&Witness::new(vec![signature.clone()]));
+        assert_eq!(
+            spend,
+            TaprootSpend::KeyPath {
+                signature: hex::encode(&signature),
+                annex: None
+            }
+        );
+
+        // With an annex the signature is still the only other item
+        let annex = vec![ANNEX_TAG, 0x01];
+        let spend = parse_taproot_witness(&Witness::new(vec![signature, annex]));
+        assert!(matches!(
+            spend,
+            TaprootSpend::KeyPath { annex: Some(a), .. } if a == "5001"
+        ));
+    }
+
+    #[test]
+    fn test_script_path_spend() {
+        // <sig> <<key> OP_CHECKSIG> <control block: c1, NUMS key, one node>
+        let script = bytes(&format!("20{}ac", "22".repeat(32)));
+        let node = "33".repeat(32);
+        let control_block = bytes(&format!("c1{}{}", NUMS_KEY, node));
+        let witness = Witness::new(vec![vec![0x44; 64], script, control_block]);
+
+        let TaprootSpend::ScriptPath {
+            script,
+            stack,
+            control_block,
+            annex,
+        } = parse_taproot_witness(&witness)
+        else {
+            panic!("expected a script-path spend");
+        };
+        assert_eq!(script, format!("20{}ac", "22".repeat(32)));
+        assert_eq!(stack, vec!["44".repeat(64)]);
+        assert_eq!(control_block.leaf_version, 0xc0);
+        assert_eq!(control_block.output_key_parity, 1);
+        assert_eq!(control_block.internal_key, NUMS_KEY);
+        assert_eq!(control_block.merkle_branch, vec![node]);
+        assert_eq!(annex, None);
+    }
+
+    #[test]
+    fn test_invalid_control_block() {
+        let witness = Witness::new(vec![vec![0x51], vec![0xc0; 40]]);
+        assert!(matches!(
+            parse_taproot_witness(&witness),
+            TaprootSpend::Invalid { .. }
+        ));
+        assert!(matches!(
+            parse_taproot_witness(&Witness::default()),
+            TaprootSpend::Invalid { .. }
+        ));
+    }
+}
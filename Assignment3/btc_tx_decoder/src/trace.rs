@@ -0,0 +1,110 @@
+use serde::Serialize;
+
+use crate::error::DecodeError;
+use crate::{parse_transaction_traced, BitcoinTransaction, DecodeLimits};
+
+// One field read by the parser: where it started, what it was and how many
+// bytes it consumed.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub offset: usize,
+    pub field: String,
+    pub bytes_read: usize,
+}
+
+// Collects TraceEvents when enabled. The default tracer records nothing, so
+// the plain parse path never builds field names.
+#[derive(Default)]
+pub(crate) struct Tracer {
+    events: Option<Vec<TraceEvent>>,
+}
+
+impl Tracer {
+    fn enabled() -> Self {
+        Tracer {
+            events: Some(Vec::new()),
+        }
+    }
+
+    // An empty tracer, enabled if this one is, for a speculative parse
+    pub(crate) fn fork(&self) -> Self {
+        Tracer {
+            events: self.events.as_ref().map(|_| Vec::new()),
+        }
+    }
+
+    pub(crate) fn extend(&mut self, other: Tracer) {
+        if let (Some(events), Some(other)) = (&mut self.events, other.events) {
+            events.extend(other);
+        }
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        offset: usize,
+        bytes_read: usize,
+        field: impl FnOnce() -> String,
+    ) {
+        if let Some(events) = &mut self.events {
+            events.push(TraceEvent {
+                offset,
+                field: field(),
+                bytes_read,
+            });
+        }
+    }
+}
+
+// Decodes the transaction while recording every field the parser reads. On
+// failure the trace holds everything read before the error.
+pub fn decode_with_trace(
+    bytes: &[u8],
+) -> (Result<BitcoinTransaction, DecodeError>, Vec<TraceEvent>) {
+    let mut tracer = Tracer::enabled();
+    let result = parse_transaction_traced(bytes, &DecodeLimits::default(), &mut tracer);
+    (result, tracer.events.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SAMPLE_TX;
+
+    #[test]
+    fn test_trace_sample() {
+        let bytes = hex::decode(SAMPLE_TX).unwrap();
+        let (result, trace) = decode_with_trace(&bytes);
+        assert!(result.is_ok());
+
+        assert_eq!(
+            trace[0],
+            TraceEvent {
+                offset: 0,
+                field: "version".to_string(),
+                bytes_read: 4
+            }
+        );
+        let fields: Vec<_> = trace.iter().take(5).map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["version", "marker", "flag", "inputcount", "inputs[0].txid"]
+        );
+
+        // Events are contiguous and cover the whole transaction
+        let mut pos = 0;
+        for event in &trace {
+            assert_eq!(event.offset, pos, "{}", event.field);
+            pos += event.bytes_read;
+        }
+        assert_eq!(pos, bytes.len());
+        assert_eq!(trace.last().unwrap().field, "locktime");
+    }
+
+    #[test]
+    fn test_trace_stops_at_error() {
+        let bytes = hex::decode(&SAMPLE_TX[..140]).unwrap();
+        let (result, trace) = decode_with_trace(&bytes);
+        assert!(result.is_err());
+        assert_eq!(trace.last().unwrap().field, "outputs[0].scriptpubkeysize");
+    }
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+
+use crate::script::is_witness_program;
+use crate::{le_hex_to_u64, BitcoinTransaction};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+}
+
+// Checks for transactions that are valid but were serialized oddly.
+pub fn validate(tx: &BitcoinTransaction) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    // Segwit serialization with nothing in any witness should have been
+    // serialized as legacy instead.
+    if tx.is_segwit() && (0..tx.inputs.len()).all(|i| tx.witness_items(i).is_empty()) {
+        findings.push(Finding {
+            severity: Severity::Info,
+            code: "empty-segwit",
+            message: "transaction uses the segwit serialization but carries no witness data"
+                .to_string(),
+        });
+    }
+
+    findings
+}
+
+// Non-fatal warnings about scriptSig/witness combinations that don't fit any
+// well-formed spend.
+pub fn check_segwit_consistency(tx: &BitcoinTransaction) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if !tx.is_segwit() {
+        if tx
+            .witness
+            .iter()
+            .enumerate()
+            .any(|(i, _)| !tx.witness_items(i).is_empty())
+        {
+            warnings.push(
+                "witness data present but the transaction is not flagged as segwit".to_string(),
+            );
+        }
+        return warnings;
+    }
+
+    // BIP144 requires a nonzero flag and only defines 0x01
+    if tx.flag != "01" {
+        warnings.push(format!(
+            "unknown segwit flag 0x{}, only 0x01 is defined",
+            tx.flag
+        ));
+    }
+
+    for (i, input) in tx.inputs.iter().enumerate() {
+        if input.scriptsig.is_empty() || tx.witness_items(i).is_empty() {
+            continue;
+        }
+        // P2SH-wrapped segwit: the scriptSig is a single push of the
+        // witness program used as redeem script
+        let script_sig = hex::decode(&input.scriptsig).unwrap_or_default();
+        let wrapped = script_sig.len() > 1
+            && script_sig[0] as usize == script_sig.len() - 1
+            && is_witness_program(&script_sig[1..]);
+        if !wrapped {
+            warnings.push(format!(
+                "input {} has both a non-empty scriptSig and a witness",
+                i
+            ));
+        }
+    }
+
+    warnings
+}
+
+// What the version number means for relay: 2 enables BIP68 relative
+// locktimes, 3 opts into the TRUC (BIP431) topology rules.
+pub fn version_meaning(version: &str) -> &'static str {
+    match version_number(version) {
+        Some(1) => "1",
+        Some(2) => "2 (BIP68)",
+        Some(3) => "3 (TRUC)",
+        _ => "unknown/nonstandard",
+    }
+}
+
+// Versions outside 1-3 are consensus-valid but not relayed by Bitcoin Core
+pub fn check_version(tx: &BitcoinTransaction) -> Option<String> {
+    match version_number(&tx.version) {
+        Some(1..=3) => None,
+        Some(version) => Some(format!(
+            "version {} is nonstandard, only 1-3 are relayed",
+            version
+        )),
+        None => Some(format!("unreadable version field {}", tx.version)),
+    }
+}
+
+// The signed 32-bit version from its little-endian hex
+fn version_number(version: &str) -> Option<i32> {
+    le_hex_to_u64(version)
+        .filter(|_| version.len() == 8)
+        .map(|v| v as u32 as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SAMPLE_TX;
+    use crate::{decode_transaction, encode_transaction, WitnessStack};
+
+    #[test]
+    fn test_validate_sample_has_no_findings() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(validate(&tx).is_empty());
+    }
+
+    #[test]
+    fn test_segwit_consistency_clean() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        assert!(check_segwit_consistency(&tx).is_empty());
+        assert!(tx.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_segwit_consistency_warnings() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+
+        // A P2SH-P2WPKH style scriptSig is fine
+        tx.inputs[0].scriptsig = "16001485d78eb795bd9c8a21afefc8b6fdaedf71836809".to_string();
+        assert!(check_segwit_consistency(&tx).is_empty());
+
+        tx.inputs[0].scriptsig = "51".to_string();
+        assert_eq!(
+            check_segwit_consistency(&tx),
+            vec!["input 0 has both a non-empty scriptSig and a witness"]
+        );
+
+        tx.marker.clear();
+        tx.flag.clear();
+        assert_eq!(
+            check_segwit_consistency(&tx),
+            vec!["witness data present but the transaction is not flagged as segwit"]
+        );
+    }
+
+    #[test]
+    fn test_validate_segwit_without_witness_data() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.witness[0] = WitnessStack::new(Vec::new());
+        let tx = decode_transaction(&encode_transaction(&tx).unwrap()).unwrap();
+
+        let findings = validate(&tx);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Info);
+        assert_eq!(findings[0].code, "empty-segwit");
+    }
+
+    #[test]
+    fn test_future_versions() {
+        use crate::builder::TxBuilder;
+        use crate::classify::{classify_script, ClassifyOptions};
+
+        // A TRUC transaction paying to a v2 witness program
+        let v2_program = hex::decode(format!("5210{}", "11".repeat(16))).unwrap();
+        let build = |version| {
+            TxBuilder::new()
+                .version(version)
+                .add_input(&"ab".repeat(32), 0)
+                .add_output_sats(1_000, &v2_program)
+                .build_hex()
+                .unwrap()
+        };
+        let tx = decode_transaction(&build(3)).unwrap();
+        assert_eq!(tx.version, "03000000");
+        assert_eq!(tx.version_meaning, "3 (TRUC)");
+        assert!(tx.warnings.is_empty());
+
+        let script_type = classify_script(&v2_program, &[], &ClassifyOptions::default());
+        assert_eq!(script_type.label(), "witness_v2_unknown");
+        // Still a bech32m address, that part is version-generic
+        assert_eq!(
+            tx.outputs[0].descriptor,
+            "addr(bc1zzyg3zyg3zyg3zyg3zyg3zyg3zymzl0k4)#gys0mwxu"
+        );
+
+        let tx = decode_transaction(&build(-1)).unwrap();
+        assert_eq!(tx.version_meaning, "unknown/nonstandard");
+        assert_eq!(
+            tx.warnings,
+            vec!["version -1 is nonstandard, only 1-3 are relayed"]
+        );
+        assert_eq!(version_meaning("01000000"), "1");
+    }
+}
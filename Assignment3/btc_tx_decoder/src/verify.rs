@@ -0,0 +1,449 @@
+use std::fmt;
+
+use secp256k1::{ecdsa, schnorr, Message, PublicKey, Secp256k1, VerifyOnly, XOnlyPublicKey};
+use serde::Serialize;
+
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::consensus::PrevOut;
+use crate::hashes::{hash160, sha256};
+use crate::script::{instructions, Instruction};
+use crate::sighash::{sighash_digest, taproot_key_spend_sighash, SighashError};
+use crate::witness::Witness;
+use crate::BitcoinTransaction;
+
+const ANNEX_TAG: u8 = 0x50;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignatureResult {
+    // As found in the input, including the sighash byte
+    pub signature: String,
+    // The key the signature verified against, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+    // 0x00 is taproot's SIGHASH_DEFAULT
+    pub sighash_type: u8,
+    pub valid: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureCheck {
+    Checked {
+        // p2pkh, p2wpkh, p2wsh or p2tr
+        template: &'static str,
+        signatures: Vec<SignatureResult>,
+    },
+    // The spent script isn't one verify_input knows how to check, which says
+    // nothing about whether the signatures are valid
+    Unsupported {
+        reason: String,
+    },
+}
+
+impl SignatureCheck {
+    // Checked, with every signature valid
+    pub fn is_valid(&self) -> bool {
+        match self {
+            SignatureCheck::Checked { signatures, .. } => signatures.iter().all(|s| s.valid),
+            SignatureCheck::Unsupported { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    InputIndexOutOfRange { index: usize, inputs: usize },
+    PrevoutCountMismatch { prevouts: usize, inputs: usize },
+    // The scriptSig or witness doesn't fit the template of the spent output
+    MalformedSpend(String),
+    Sighash(SighashError),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::InputIndexOutOfRange { index, inputs } => write!(
+                f,
+                "input index {} out of range (transaction has {} inputs)",
+                index, inputs
+            ),
+            VerifyError::PrevoutCountMismatch { prevouts, inputs } => {
+                write!(f, "{} prevouts supplied for {} inputs", prevouts, inputs)
+            }
+            VerifyError::MalformedSpend(e) => write!(f, "malformed spend: {}", e),
+            VerifyError::Sighash(e) => write!(f, "sighash: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<SighashError> for VerifyError {
+    fn from(e: SighashError) -> Self {
+        VerifyError::Sighash(e)
+    }
+}
+
+// Verifies the signatures of an input against the output it spends.
+// `prevouts` holds the spent output of every input, in order: taproot
+// sighashes commit to all of them. Supports P2PKH, P2WPKH, P2WSH single-key
+// and multisig, and P2TR key-path spends.
+pub fn verify_input(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    prevouts: &[PrevOut],
+) -> Result<SignatureCheck, VerifyError> {
+    if input_index >= tx.inputs.len() {
+        return Err(VerifyError::InputIndexOutOfRange {
+            index: input_index,
+            inputs: tx.inputs.len(),
+        });
+    }
+    if prevouts.len() != tx.inputs.len() {
+        return Err(VerifyError::PrevoutCountMismatch {
+            prevouts: prevouts.len(),
+            inputs: tx.inputs.len(),
+        });
+    }
+
+    let prevout = &prevouts[input_index];
+    let script_pubkey = decode(&prevout.script_pubkey, "prevout scriptPubKey")?;
+    let script_sig = decode(&tx.inputs[input_index].scriptsig, "scriptSig")?;
+    let witness = Witness::from_tx(tx, input_index);
+    let items = witness.items();
+    let secp = Secp256k1::verification_only();
+    let options = ClassifyOptions::default();
+
+    let (template, signatures) = match classify_script(&script_pubkey, &[], &options) {
+        ScriptType::P2pkh => {
+            let [sig, pubkey] = pushes(&script_sig)[..] else {
+                return Err(malformed("P2PKH scriptSig must be <sig> <pubkey>"));
+            };
+            if hash160(pubkey)[..] != script_pubkey[3..23] {
+                return Err(malformed("public key does not match the P2PKH hash"));
+            }
+            let digest = |t| sighash_digest(tx, input_index, &script_pubkey, None, t);
+            ("p2pkh", check_ecdsa(&secp, &[sig], &[pubkey], digest)?)
+        }
+        ScriptType::P2wpkh => {
+            let [sig, pubkey] = items else {
+                return Err(malformed("P2WPKH witness must be <sig> <pubkey>"));
+            };
+            if hash160(pubkey)[..] != script_pubkey[2..] {
+                return Err(malformed("public key does not match the P2WPKH hash"));
+            }
+            let script_code = [&[0x76, 0xa9, 0x14], &script_pubkey[2..], &[0x88, 0xac]].concat();
+            let amount = Some(prevout.value_sats);
+            let digest = |t| sighash_digest(tx, input_index, &script_code, amount, t);
+            ("p2wpkh", check_ecdsa(&secp, &[sig], &[pubkey], digest)?)
+        }
+        ScriptType::P2wsh { .. } => {
+            let [args @ .., witness_script] = items else {
+                return Err(malformed("empty P2WSH witness"));
+            };
+            if sha256(witness_script)[..] != script_pubkey[2..] {
+                return Err(malformed("witness script does not match the P2WSH hash"));
+            }
+            let amount = Some(prevout.value_sats);
+            let digest = |t| sighash_digest(tx, input_index, witness_script, amount, t);
+            let keys = pushes(witness_script);
+            let sigs: Vec<&[u8]> = match classify_script(witness_script, &[], &options) {
+                ScriptType::P2pk => match args {
+                    [sig] => vec![sig],
+                    _ => return Err(malformed("single-key witness must be <sig> <script>")),
+                },
+                // The extra item CHECKMULTISIG pops must be empty (NULLDUMMY)
+                ScriptType::Multisig { .. } => match args {
+                    [dummy, sigs @ ..] if dummy.is_empty() => {
+                        sigs.iter().map(Vec::as_slice).collect()
+                    }
+                    _ => return Err(malformed("multisig witness must start with an empty item")),
+                },
+                other => return Ok(unsupported(format!("{} witness scripts", other.name()))),
+            };
+            ("p2wsh", check_ecdsa(&secp, &sigs, &keys, digest)?)
+        }
+        ScriptType::P2tr => {
+            let (items, annex) = match items {
+                [rest @ .., last] if !rest.is_empty() && last.first() == Some(&ANNEX_TAG) => {
+                    (rest, Some(last.as_slice()))
+                }
+                _ => (items, None),
+            };
+            let [sig] = items else {
+                return Ok(unsupported("taproot script-path spends".to_string()));
+            };
+            let signature = check_schnorr(&secp, sig, &script_pubkey[2..], |t| {
+                taproot_key_spend_sighash(tx, input_index, prevouts, t, annex)
+            })?;
+            ("p2tr", vec![signature])
+        }
+        other => return Ok(unsupported(format!("{} outputs", other.name()))),
+    };
+
+    Ok(SignatureCheck::Checked {
+        template,
+        signatures,
+    })
+}
+
+// CHECKMULTISIG matching: signatures and keys are both in order, and each
+// signature is tried against the keys after the last one that matched.
+fn check_ecdsa(
+    secp: &Secp256k1<VerifyOnly>,
+    sigs: &[&[u8]],
+    keys: &[&[u8]],
+    digest: impl Fn(u32) -> Result<[u8; 32], SighashError>,
+) -> Result<Vec<SignatureResult>, VerifyError> {
+    let mut keys = keys.iter();
+    let mut results = Vec::new();
+    for sig in sigs {
+        let Some((&sighash_type, der)) = sig.split_last() else {
+            results.push(SignatureResult {
+                signature: String::new(),
+                pubkey: None,
+                sighash_type: 0,
+                valid: false,
+            });
+            continue;
+        };
+        let message = Message::from_digest(digest(sighash_type as u32)?);
+        // Consensus accepts high-S signatures; libsecp256k1 only verifies
+        // normalized ones
+        let signature = ecdsa::Signature::from_der_lax(der).ok().map(|mut s| {
+            s.normalize_s();
+            s
+        });
+
+        let matched = signature.and_then(|signature| {
+            keys.by_ref().find(|key| {
+                PublicKey::from_slice(key)
+                    .is_ok_and(|key| secp.verify_ecdsa(&message, &signature, &key).is_ok())
+            })
+        });
+        results.push(SignatureResult {
+            signature: hex::encode(sig),
+            pubkey: matched.map(hex::encode),
+            sighash_type,
+            valid: matched.is_some(),
+        });
+    }
+    Ok(results)
+}
+
+fn check_schnorr(
+    secp: &Secp256k1<VerifyOnly>,
+    sig: &[u8],
+    output_key: &[u8],
+    digest: impl Fn(u8) -> Result<[u8; 32], SighashError>,
+) -> Result<SignatureResult, VerifyError> {
+    // 64 bytes means SIGHASH_DEFAULT; an explicit 0x00 byte is invalid
+    let (sighash_type, bytes) = match sig.split_last() {
+        Some((&t, bytes)) if sig.len() == 65 && t != 0x00 => (t, bytes),
+        _ => (0x00, sig),
+    };
+    let valid = match (
+        schnorr::Signature::from_slice(bytes),
+        XOnlyPublicKey::from_slice(output_key),
+    ) {
+        (Ok(signature), Ok(key)) => {
+            let message = Message::from_digest(digest(sighash_type)?);
+            secp.verify_schnorr(&signature, &message, &key).is_ok()
+        }
+        _ => false,
+    };
+    Ok(SignatureResult {
+        signature: hex::encode(sig),
+        pubkey: valid.then(|| hex::encode(output_key)),
+        sighash_type,
+        valid,
+    })
+}
+
+fn pushes(script: &[u8]) -> Vec<&[u8]> {
+    instructions(script)
+        .map_while(Result::ok)
+        .filter_map(|(_, instruction)| match instruction {
+            Instruction::Push { data, .. } => Some(data),
+            Instruction::Op(_) => None,
+        })
+        .collect()
+}
+
+fn decode(field: &str, name: &str) -> Result<Vec<u8>, VerifyError> {
+    hex::decode(field).map_err(|e| malformed(&format!("{}: {}", name, e)))
+}
+
+fn malformed(message: &str) -> VerifyError {
+    VerifyError::MalformedSpend(message.to_string())
+}
+
+fn unsupported(reason: String) -> SignatureCheck {
+    SignatureCheck::Unsupported { reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::TxBuilder;
+    use crate::script::encode_push;
+    use crate::{WitnessItem, WitnessStack};
+    use secp256k1::{Keypair, SecretKey};
+
+    const PREV_TXID: &str = "c1368b8e3daedf15612b0185f79f4e82df90f6bcd93714e0e057c355d31c8131";
+
+    fn unsigned_tx() -> BitcoinTransaction {
+        let script = hex::decode("0014841b80d2cc75f5345c482af96294d04fdd66b2b7").unwrap();
+        TxBuilder::new()
+            .add_input(PREV_TXID, 0)
+            .add_output_sats(90_000, &script)
+            .build()
+            .unwrap()
+    }
+
+    fn set_witness(tx: &mut BitcoinTransaction, items: &[Vec<u8>]) {
+        let stack = WitnessStack::new(items.iter().map(|item| WitnessItem::new(item)).collect());
+        tx.marker = "00".to_string();
+        tx.flag = "01".to_string();
+        tx.witness = vec![stack];
+    }
+
+    fn key(byte: u8) -> (SecretKey, Vec<u8>) {
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        let public = secret.public_key(&Secp256k1::new()).serialize().to_vec();
+        (secret, public)
+    }
+
+    fn sign(digest: [u8; 32], secret: &SecretKey) -> Vec<u8> {
+        let message = Message::from_digest(digest);
+        let mut sig = Secp256k1::new()
+            .sign_ecdsa(&message, secret)
+            .serialize_der()
+            .to_vec();
+        sig.push(0x01);
+        sig
+    }
+
+    fn prevout(script_pubkey: &[u8]) -> Vec<PrevOut> {
+        vec![PrevOut {
+            value_sats: 100_000,
+            script_pubkey: hex::encode(script_pubkey),
+        }]
+    }
+
+    #[test]
+    fn test_verify_p2wpkh() {
+        let (secret, public) = key(1);
+        let script_pubkey = [&[0x00, 0x14][..], &hash160(&public)].concat();
+        let script_code = [&[0x76, 0xa9, 0x14][..], &hash160(&public), &[0x88, 0xac]].concat();
+        let mut tx = unsigned_tx();
+        let digest = sighash_digest(&tx, 0, &script_code, Some(100_000), 1).unwrap();
+        let sig = sign(digest, &secret);
+
+        set_witness(&mut tx, &[sig.clone(), public.clone()]);
+        let check = verify_input(&tx, 0, &prevout(&script_pubkey)).unwrap();
+        assert!(check.is_valid());
+        let SignatureCheck::Checked {
+            template,
+            signatures,
+        } = check
+        else {
+            panic!("expected a checked spend");
+        };
+        assert_eq!(template, "p2wpkh");
+        assert_eq!(signatures[0].sighash_type, 0x01);
+        assert_eq!(signatures[0].pubkey, Some(hex::encode(&public)));
+
+        // Flip a bit of s
+        let mut bad = sig;
+        let len = bad.len();
+        bad[len - 2] ^= 0x01;
+        set_witness(&mut tx, &[bad, public]);
+        let check = verify_input(&tx, 0, &prevout(&script_pubkey)).unwrap();
+        assert!(!check.is_valid());
+    }
+
+    #[test]
+    fn test_verify_p2pkh() {
+        let (secret, public) = key(2);
+        let script_pubkey = [&[0x76, 0xa9, 0x14][..], &hash160(&public), &[0x88, 0xac]].concat();
+        let mut tx = unsigned_tx();
+        let digest = sighash_digest(&tx, 0, &script_pubkey, None, 1).unwrap();
+        let script_sig = [encode_push(&sign(digest, &secret)), encode_push(&public)].concat();
+        tx.inputs[0].scriptsig = hex::encode(&script_sig);
+
+        let check = verify_input(&tx, 0, &prevout(&script_pubkey)).unwrap();
+        assert!(check.is_valid());
+    }
+
+    #[test]
+    fn test_verify_p2wsh_multisig() {
+        // 2-of-3, signed by the first and third keys
+        let keys: Vec<_> = (3..6).map(key).collect();
+        let mut witness_script = vec![0x52];
+        for (_, public) in &keys {
+            witness_script.extend(encode_push(public));
+        }
+        witness_script.extend([0x53, 0xae]);
+        let script_pubkey = [&[0x00, 0x20][..], &sha256(&witness_script)].concat();
+
+        let mut tx = unsigned_tx();
+        let digest = sighash_digest(&tx, 0, &witness_script, Some(100_000), 1).unwrap();
+        let sigs = [sign(digest, &keys[0].0), sign(digest, &keys[2].0)];
+        set_witness(
+            &mut tx,
+            &[
+                vec![],
+                sigs[0].clone(),
+                sigs[1].clone(),
+                witness_script.clone(),
+            ],
+        );
+        let check = verify_input(&tx, 0, &prevout(&script_pubkey)).unwrap();
+        assert!(check.is_valid());
+
+        // Out of order signatures fail CHECKMULTISIG
+        set_witness(
+            &mut tx,
+            &[vec![], sigs[1].clone(), sigs[0].clone(), witness_script],
+        );
+        let check = verify_input(&tx, 0, &prevout(&script_pubkey)).unwrap();
+        assert!(!check.is_valid());
+    }
+
+    #[test]
+    fn test_verify_p2tr_key_path() {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_slice(&secp, &[7; 32]).unwrap();
+        let (output_key, _) = keypair.x_only_public_key();
+        let script_pubkey = [&[0x51, 0x20][..], &output_key.serialize()].concat();
+        let prevouts = prevout(&script_pubkey);
+
+        let mut tx = unsigned_tx();
+        let digest = taproot_key_spend_sighash(&tx, 0, &prevouts, 0x00, None).unwrap();
+        let sig = secp.sign_schnorr_no_aux_rand(&Message::from_digest(digest), &keypair);
+        set_witness(&mut tx, &[sig.as_ref().to_vec()]);
+        assert!(verify_input(&tx, 0, &prevouts).unwrap().is_valid());
+
+        let mut bad = sig.as_ref().to_vec();
+        bad[10] ^= 0x80;
+        set_witness(&mut tx, &[bad]);
+        assert!(!verify_input(&tx, 0, &prevouts).unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_verify_unsupported() {
+        let tx = unsigned_tx();
+        let script_pubkey = hex::decode("a914000000000000000000000000000000000000000087").unwrap();
+        assert_eq!(
+            verify_input(&tx, 0, &prevout(&script_pubkey)),
+            Ok(SignatureCheck::Unsupported {
+                reason: "p2sh outputs".to_string()
+            })
+        );
+        assert!(matches!(
+            verify_input(&tx, 1, &prevout(&script_pubkey)),
+            Err(VerifyError::InputIndexOutOfRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::decode_transaction;
+
+// Decodes a raw transaction hex into the same object the CLI prints as JSON.
+#[wasm_bindgen]
+pub fn decode_tx(hex: &str) -> Result<JsValue, JsValue> {
+    let tx = decode_transaction(hex).map_err(|e| JsValue::from_str(&e))?;
+    tx.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+// Transaction id of a raw transaction hex, as explorers display it.
+#[wasm_bindgen]
+pub fn tx_id(hex: &str) -> Result<String, JsValue> {
+    let tx = decode_transaction(hex).map_err(|e| JsValue::from_str(&e))?;
+    tx.txid().map_err(|e| JsValue::from_str(&e.to_string()))
+}
@@ -0,0 +1,623 @@
+// The wire format read into plain byte structs. Only `core` and `alloc` are
+// used here, so this builds without the `std` feature; BitcoinTransaction
+// (hex fields, JSON, annotations) is built on top of it.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::compact_size::CompactSize;
+use crate::error::DecodeError;
+use crate::limits::{
+    check_fits, check_limit, DecodeLimits, MIN_INPUT_SIZE, MIN_OUTPUT_SIZE, MIN_WITNESS_ITEM_SIZE,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawInput {
+    // Internal byte order, as on the wire
+    pub txid: [u8; 32],
+    pub vout: u32,
+    pub script_sig_len: CompactSize,
+    pub script_sig: Vec<u8>,
+    // None when a lenient decode stopped at a truncated scriptSig
+    pub sequence: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawOutput {
+    pub value: u64,
+    pub script_pubkey_len: CompactSize,
+    pub script_pubkey: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawWitnessItem {
+    pub size: CompactSize,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawWitness {
+    pub stack_items: CompactSize,
+    pub items: Vec<RawWitnessItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawTransaction {
+    pub version: i32,
+    // The segwit flag byte; None for a legacy serialization
+    pub flag: Option<u8>,
+    pub input_count: CompactSize,
+    pub inputs: Vec<RawInput>,
+    // None when a lenient decode stopped at a truncated scriptSig
+    pub output_count: Option<CompactSize>,
+    pub outputs: Vec<RawOutput>,
+    // One stack per input when segwit, empty otherwise
+    pub witness: Vec<RawWitness>,
+    // None when a lenient decode stopped at a truncated script
+    pub locktime: Option<u32>,
+    // Scripts cut short by a lenient decode
+    pub warnings: Vec<String>,
+}
+
+impl RawTransaction {
+    // A lenient decode stopped at a truncated script: what came after it
+    // isn't in the input, so it is missing here rather than zero
+    pub fn is_truncated(&self) -> bool {
+        self.locktime.is_none()
+    }
+}
+
+// A field the parser read, for tracing. Displays as the field's path in the
+// decoder's JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Version,
+    Marker,
+    Flag,
+    InputCount,
+    Txid(usize),
+    Vout(usize),
+    ScriptSigSize(usize),
+    ScriptSig(usize),
+    Sequence(usize),
+    OutputCount,
+    Amount(usize),
+    ScriptPubKeySize(usize),
+    ScriptPubKey(usize),
+    StackItems(usize),
+    ItemSize(usize, usize),
+    Item(usize, usize),
+    Locktime,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Version => write!(f, "version"),
+            Field::Marker => write!(f, "marker"),
+            Field::Flag => write!(f, "flag"),
+            Field::InputCount => write!(f, "inputcount"),
+            Field::Txid(i) => write!(f, "inputs[{}].txid", i),
+            Field::Vout(i) => write!(f, "inputs[{}].vout", i),
+            Field::ScriptSigSize(i) => write!(f, "inputs[{}].scriptsigsize", i),
+            Field::ScriptSig(i) => write!(f, "inputs[{}].scriptsig", i),
+            Field::Sequence(i) => write!(f, "inputs[{}].sequence", i),
+            Field::OutputCount => write!(f, "outputcount"),
+            Field::Amount(i) => write!(f, "outputs[{}].amount", i),
+            Field::ScriptPubKeySize(i) => write!(f, "outputs[{}].scriptpubkeysize", i),
+            Field::ScriptPubKey(i) => write!(f, "outputs[{}].scriptpubkey", i),
+            Field::StackItems(i) => write!(f, "witness[{}].stackitems", i),
+            Field::ItemSize(i, j) => write!(f, "witness[{}][{}].size", i, j),
+            Field::Item(i, j) => write!(f, "witness[{}][{}].item", i, j),
+            Field::Locktime => write!(f, "locktime"),
+        }
+    }
+}
+
+// Called with each field as it is read: the field, its offset and its length
+pub type Record<'a> = &'a mut dyn FnMut(Field, usize, usize);
+
+// Where the parser reads from. A slice has every byte up front; a stream
+// (see reader.rs) reads more as the parser asks for it, and never further.
+pub trait ByteSource {
+    // Makes the first `len` bytes available, or all there are if fewer
+    fn fill(&mut self, len: usize) -> Result<(), DecodeError>;
+    // The bytes available so far, from the start of the transaction
+    fn bytes(&self) -> &[u8];
+    // Whether bytes() is everything, so counts can be checked against what
+    // is left before looping over them
+    fn is_complete(&self) -> bool;
+}
+
+impl ByteSource for &[u8] {
+    fn fill(&mut self, _len: usize) -> Result<(), DecodeError> {
+        Ok(())
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn is_complete(&self) -> bool {
+        true
+    }
+}
+
+// Decodes a transaction with the default limits. Like the rest of the
+// decoder, anything after the locktime is ignored.
+pub fn decode(bytes: &[u8]) -> Result<RawTransaction, DecodeError> {
+    decode_with_limits(bytes, &DecodeLimits::default())
+}
+
+// `decode` for hex input, which needs nothing beyond alloc either
+pub fn decode_hex(hex: &str) -> Result<RawTransaction, DecodeError> {
+    let bytes = hex::decode(hex).map_err(|e| DecodeError::InvalidHex(e.to_string()))?;
+    decode(&bytes)
+}
+
+pub fn decode_with_limits(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<RawTransaction, DecodeError> {
+    if !has_segwit_marker(bytes) {
+        return parse_body(bytes, false, limits, &mut |_, _, _| {}).map(|(tx, _)| tx);
+    }
+    let segwit = parse_body(bytes, true, limits, &mut |_, _, _| {});
+    let legacy = parse_body(bytes, false, limits, &mut |_, _, _| {});
+    let (tx, _) = if prefer_legacy(&segwit, &legacy, bytes.len()) {
+        legacy?
+    } else {
+        segwit?
+    };
+    Ok(tx)
+}
+
+// A 0x00 marker needs a nonzero flag after it: 00 00 can only be a legacy
+// transaction with no inputs and no outputs
+pub fn has_segwit_marker(bytes: &[u8]) -> bool {
+    bytes.get(4) == Some(&0x00) && bytes.get(5).is_some_and(|&flag| flag != 0x00)
+}
+
+// 00 and a nonzero flag after the version is either the segwit marker and
+// flag or a legacy transaction with no inputs and some outputs. Given both
+// parses, keep whichever consumes exactly the input, preferring segwit.
+pub(crate) fn prefer_legacy<T>(
+    segwit: &Result<(T, usize), DecodeError>,
+    legacy: &Result<(T, usize), DecodeError>,
+    len: usize,
+) -> bool {
+    !consumes(segwit, len) && (consumes(legacy, len) || (segwit.is_err() && legacy.is_ok()))
+}
+
+pub(crate) fn consumes<T>(result: &Result<(T, usize), DecodeError>, len: usize) -> bool {
+    matches!(result, Ok((_, end)) if *end == len)
+}
+
+// Parses the transaction as segwit (marker, flag and witness) or legacy,
+// returning it with the number of bytes consumed.
+pub fn parse_body(
+    mut bytes: &[u8],
+    is_segwit: bool,
+    limits: &DecodeLimits,
+    record: Record,
+) -> Result<(RawTransaction, usize), DecodeError> {
+    parse_body_from(&mut bytes, is_segwit, limits, record)
+}
+
+// parse_body for any ByteSource, e.g. a stream read as the parser goes
+pub fn parse_body_from<S: ByteSource + ?Sized>(
+    src: &mut S,
+    is_segwit: bool,
+    limits: &DecodeLimits,
+    record: Record,
+) -> Result<(RawTransaction, usize), DecodeError> {
+    let mut pos = 0;
+
+    // Parse version (4 bytes)
+    let version = i32::from_le_bytes(read_array(src, pos, "Input too short for version")?);
+    record(Field::Version, pos, 4);
+    pos += 4;
+
+    // Segwit marker and flag
+    let flag = if is_segwit {
+        let [_, flag] = read_array(src, pos, "Input too short for marker and flag")?;
+        record(Field::Marker, pos, 1);
+        record(Field::Flag, pos + 1, 1);
+        pos += 2;
+        Some(flag)
+    } else {
+        None
+    };
+
+    // Parse input count (compact size)
+    let (input_count, count_size) = read_compact_size(src, pos)?;
+    check_limit("input count", input_count.value, limits.max_inputs)?;
+    record(Field::InputCount, pos, count_size);
+    pos += count_size;
+    check_fits_in(src, "input count", input_count.value, MIN_INPUT_SIZE, pos)?;
+
+    let mut tx = RawTransaction {
+        version,
+        flag,
+        input_count,
+        inputs: Vec::new(),
+        // Filled in below. A lenient decode that stops at a truncated script
+        // leaves out everything after it.
+        output_count: None,
+        outputs: Vec::new(),
+        witness: Vec::new(),
+        locktime: None,
+        warnings: Vec::new(),
+    };
+
+    // Parse inputs
+    tx.inputs.reserve(tx.input_count.len());
+    for index in 0..tx.input_count.len() {
+        let (input, size) = parse_input_with(src, pos, index, limits, &mut tx.warnings, record)?;
+        let truncated = input.script_sig.len() < input.script_sig_len.len();
+        tx.inputs.push(input);
+        pos += size;
+        if truncated {
+            return Ok((tx, pos));
+        }
+    }
+
+    // Parse output count
+    let (output_count, count_size) = read_compact_size(src, pos)?;
+    check_limit("output count", output_count.value, limits.max_outputs)?;
+    record(Field::OutputCount, pos, count_size);
+    pos += count_size;
+    check_fits_in(
+        src,
+        "output count",
+        output_count.value,
+        MIN_OUTPUT_SIZE,
+        pos,
+    )?;
+    let outputs = output_count.len();
+    tx.output_count = Some(output_count);
+
+    // Parse outputs
+    tx.outputs.reserve(outputs);
+    for index in 0..outputs {
+        let (output, size) = parse_output_with(src, pos, index, limits, &mut tx.warnings, record)?;
+        let truncated = output.script_pubkey.len() < output.script_pubkey_len.len();
+        tx.outputs.push(output);
+        pos += size;
+        if truncated {
+            return Ok((tx, pos));
+        }
+    }
+
+    // Parse witness data if segwit
+    if is_segwit {
+        tx.witness.reserve(tx.inputs.len());
+        for input in 0..tx.inputs.len() {
+            let (stack_items, stack_size) = read_compact_size(src, pos)?;
+            check_limit(
+                "witness item count",
+                stack_items.value,
+                limits.max_witness_items,
+            )?;
+            record(Field::StackItems(input), pos, stack_size);
+            pos += stack_size;
+            check_fits_in(
+                src,
+                "witness item count",
+                stack_items.value,
+                MIN_WITNESS_ITEM_SIZE,
+                pos,
+            )?;
+
+            let mut items = Vec::with_capacity(stack_items.len());
+            for i in 0..stack_items.len() {
+                let (size, size_len) = read_compact_size(src, pos)?;
+                check_limit("witness item length", size.value, limits.max_script_len)?;
+                record(Field::ItemSize(input, i), pos, size_len);
+                pos += size_len;
+
+                let data = read_slice_from(src, pos, size.len())?;
+                record(Field::Item(input, i), pos, data.len());
+                pos += data.len();
+
+                items.push(RawWitnessItem {
+                    size,
+                    data: data.to_vec(),
+                });
+            }
+            tx.witness.push(RawWitness { stack_items, items });
+        }
+    }
+
+    // Parse locktime (4 bytes)
+    tx.locktime = Some(u32::from_le_bytes(read_array(
+        src,
+        pos,
+        "Input too short for locktime",
+    )?));
+    record(Field::Locktime, pos, 4);
+    Ok((tx, pos + 4))
+}
+
+// The input at `pos`, with the number of bytes it took
+pub fn parse_input(mut bytes: &[u8], pos: usize) -> Result<(RawInput, usize), DecodeError> {
+    let limits = DecodeLimits::default();
+    parse_input_with(
+        &mut bytes,
+        pos,
+        0,
+        &limits,
+        &mut Vec::new(),
+        &mut |_, _, _| {},
+    )
+}
+
+// A truncated scriptSig (lenient limits only) takes the rest of the input, and
+// the sequence after it is missing
+pub(crate) fn parse_input_with<S: ByteSource + ?Sized>(
+    src: &mut S,
+    pos: usize,
+    index: usize,
+    limits: &DecodeLimits,
+    warnings: &mut Vec<String>,
+    record: Record,
+) -> Result<(RawInput, usize), DecodeError> {
+    let mut offset = pos;
+
+    // Parse previous txid (32 bytes, no reversal)
+    let txid = read_array(src, offset, "Invalid input: txid too short")?;
+    record(Field::Txid(index), offset, 32);
+    offset += 32;
+
+    // Parse vout (4 bytes)
+    let vout = u32::from_le_bytes(read_array(src, offset, "Invalid input: vout too short")?);
+    record(Field::Vout(index), offset, 4);
+    offset += 4;
+
+    // Parse script sig length and script sig
+    let (script_sig_len, len_size) = read_compact_size(src, offset)?;
+    check_limit("script length", script_sig_len.value, limits.max_script_len)?;
+    record(Field::ScriptSigSize(index), offset, len_size);
+    offset += len_size;
+
+    let script_sig =
+        read_script(src, offset, &script_sig_len, "scriptsig", limits, warnings)?.to_vec();
+    record(Field::ScriptSig(index), offset, script_sig.len());
+    offset += script_sig.len();
+    if script_sig.len() < script_sig_len.len() {
+        let input = RawInput {
+            txid,
+            vout,
+            script_sig_len,
+            script_sig,
+            sequence: None,
+        };
+        return Ok((input, offset - pos));
+    }
+
+    // Parse sequence (4 bytes)
+    let sequence = u32::from_le_bytes(read_array(
+        src,
+        offset,
+        "Invalid input: sequence too short",
+    )?);
+    record(Field::Sequence(index), offset, 4);
+    offset += 4;
+
+    let input = RawInput {
+        txid,
+        vout,
+        script_sig_len,
+        script_sig,
+        sequence: Some(sequence),
+    };
+    Ok((input, offset - pos))
+}
+
+// The output at `pos`, with the number of bytes it took
+pub fn parse_output(mut bytes: &[u8], pos: usize) -> Result<(RawOutput, usize), DecodeError> {
+    let limits = DecodeLimits::default();
+    parse_output_with(
+        &mut bytes,
+        pos,
+        0,
+        &limits,
+        &mut Vec::new(),
+        &mut |_, _, _| {},
+    )
+}
+
+pub(crate) fn parse_output_with<S: ByteSource + ?Sized>(
+    src: &mut S,
+    pos: usize,
+    index: usize,
+    limits: &DecodeLimits,
+    warnings: &mut Vec<String>,
+    record: Record,
+) -> Result<(RawOutput, usize), DecodeError> {
+    let mut offset = pos;
+
+    // Parse amount (8 bytes)
+    let value = u64::from_le_bytes(read_array(src, offset, "Invalid output: amount too short")?);
+    record(Field::Amount(index), offset, 8);
+    offset += 8;
+
+    // Parse script pubkey length and script pubkey
+    let (script_pubkey_len, len_size) = read_compact_size(src, offset)?;
+    check_limit(
+        "script length",
+        script_pubkey_len.value,
+        limits.max_script_len,
+    )?;
+    record(Field::ScriptPubKeySize(index), offset, len_size);
+    offset += len_size;
+
+    let script_pubkey = read_script(
+        src,
+        offset,
+        &script_pubkey_len,
+        "scriptpubkey",
+        limits,
+        warnings,
+    )?;
+    record(Field::ScriptPubKey(index), offset, script_pubkey.len());
+    offset += script_pubkey.len();
+
+    let output = RawOutput {
+        value,
+        script_pubkey_len,
+        script_pubkey: script_pubkey.to_vec(),
+    };
+    Ok((output, offset - pos))
+}
+
+// `len` bytes at `pos`. Lengths come straight off the wire and may be absurd,
+// so check them against what is left before touching the buffer.
+pub fn read_slice(bytes: &[u8], pos: usize, len: usize) -> Result<&[u8], DecodeError> {
+    pos.checked_add(len)
+        .and_then(|end| bytes.get(pos..end))
+        .ok_or(DecodeError::UnexpectedEof {
+            needed: len,
+            available: bytes.len().saturating_sub(pos),
+        })
+}
+
+// read_slice once the source has read that far
+fn read_slice_from<S: ByteSource + ?Sized>(
+    src: &mut S,
+    pos: usize,
+    len: usize,
+) -> Result<&[u8], DecodeError> {
+    src.fill(pos.saturating_add(len))?;
+    read_slice(src.bytes(), pos, len)
+}
+
+// The CompactSize at `pos`, reading its first byte before the rest so a
+// stream isn't read past it
+fn read_compact_size<S: ByteSource + ?Sized>(
+    src: &mut S,
+    pos: usize,
+) -> Result<(CompactSize, usize), DecodeError> {
+    src.fill(pos + 1)?;
+    let size = match src.bytes().get(pos) {
+        Some(0xfd) => 3,
+        Some(0xfe) => 5,
+        Some(0xff) => 9,
+        _ => 1,
+    };
+    src.fill(pos + size)?;
+    CompactSize::read(src.bytes(), pos)
+}
+
+// check_fits against what is left, when the source knows. A stream doesn't,
+// and reads a bogus count's elements one by one until it runs dry instead.
+fn check_fits_in<S: ByteSource + ?Sized>(
+    src: &S,
+    field: &'static str,
+    count: u64,
+    min_size: usize,
+    pos: usize,
+) -> Result<(), DecodeError> {
+    if !src.is_complete() {
+        return Ok(());
+    }
+    check_fits(field, count, min_size, src.bytes().len() - pos)
+}
+
+// The script of `len` bytes at `pos`. With lenient limits, one that runs
+// past the end of the input is cut short with a warning instead of failing.
+fn read_script<'a, S: ByteSource + ?Sized>(
+    src: &'a mut S,
+    pos: usize,
+    len: &CompactSize,
+    name: &str,
+    limits: &DecodeLimits,
+    warnings: &mut Vec<String>,
+) -> Result<&'a [u8], DecodeError> {
+    src.fill(pos.saturating_add(len.len()))?;
+    let bytes = src.bytes();
+    match read_slice(bytes, pos, len.len()) {
+        Err(DecodeError::UnexpectedEof { available, .. }) if limits.lenient => {
+            warnings.push(format!(
+                "{} truncated: declared {}, available {}",
+                name, len.value, available
+            ));
+            Ok(bytes.get(pos..).unwrap_or_default())
+        }
+        result => result,
+    }
+}
+
+// A fixed-size field at `pos`, failing with `message` when the input is too
+// short for it
+fn read_array<const N: usize, S: ByteSource + ?Sized>(
+    src: &mut S,
+    pos: usize,
+    message: &str,
+) -> Result<[u8; N], DecodeError> {
+    src.fill(pos + N)?;
+    src.bytes()
+        .get(pos..)
+        .and_then(|rest| rest.get(..N))
+        .map(|field| field.try_into().unwrap())
+        .ok_or_else(|| invalid(message))
+}
+
+fn invalid(message: &str) -> DecodeError {
+    DecodeError::InvalidTransaction(message.to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // The assignment's sample transaction, shared by the tests of every module.
+    pub(crate) const SAMPLE_TX: &str = "0200000000010131811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c10100000000fdffffff0220a107000000000016001485d78eb795bd9c8a21afefc8b6fdaedf718368094c08100000000000160014840ab165c9c2555d4a31b9208ad806f89d2535e20247304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01210260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff43030e00";
+
+    // Runs with --no-default-features too
+    #[test]
+    fn test_decode_raw() {
+        let bytes = hex::decode(SAMPLE_TX).unwrap();
+        let tx = decode(&bytes).unwrap();
+        assert_eq!(tx.version, 2);
+        assert_eq!(tx.flag, Some(1));
+        assert_eq!(tx.inputs.len(), 1);
+        assert_eq!(tx.inputs[0].txid[..2], [0x31, 0x81]);
+        assert_eq!(tx.inputs[0].vout, 1);
+        assert!(tx.inputs[0].script_sig.is_empty());
+        assert_eq!(tx.inputs[0].sequence, Some(0xfffffffd));
+        assert_eq!(tx.outputs.len(), 2);
+        assert_eq!(tx.outputs[0].value, 500_000);
+        assert_eq!(tx.outputs[1].value, 1_050_700);
+        assert_eq!(tx.outputs[0].script_pubkey.len(), 22);
+        assert_eq!(tx.witness.len(), 1);
+        assert_eq!(tx.witness[0].items.len(), 2);
+        assert_eq!(tx.witness[0].items[0].data.len(), 71);
+        assert_eq!(tx.witness[0].items[1].data.len(), 33);
+        assert_eq!(tx.locktime, Some(918_339));
+
+        // The output parsed on its own
+        let (output, size) = parse_output(&bytes, 49).unwrap();
+        assert_eq!(output, tx.outputs[0]);
+        assert_eq!(size, 31);
+    }
+
+    #[test]
+    fn test_decode_raw_errors() {
+        let bytes = hex::decode(SAMPLE_TX).unwrap();
+        assert!(matches!(
+            decode(&bytes[..70]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert_eq!(
+            decode(&bytes[..3]),
+            Err(invalid("Input too short for version"))
+        );
+        assert_eq!(
+            parse_input(&bytes[..40], 7),
+            Err(invalid("Invalid input: vout too short"))
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::witness_role::WitnessRole;
+use crate::BitcoinTransaction;
+
+// One witness item as it appears on the wire. An empty push (the
+// CHECKMULTISIG dummy, a false branch selector) has size "00" and item "".
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WitnessItem {
+    pub size: String,
+    pub item: String,
+    // Signature, public key or witness script, see witness_role::witness_roles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<WitnessRole>,
+}
+
+impl WitnessItem {
+    pub fn new(item: &[u8]) -> Self {
+        WitnessItem {
+            size: hex::encode(crate::CompactSize::encode(item.len() as u64)),
+            item: hex::encode(item),
+            role: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.item.is_empty()
+    }
+}
+
+// An input's witness stack: the raw item count and the items, bottom first.
+// Inputs with no witness in a segwit transaction (legacy spends) have
+// stackitems "00" and no items.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(try_from = "Value")]
+pub struct WitnessStack {
+    pub stackitems: String,
+    pub items: Vec<WitnessItem>,
+}
+
+impl WitnessStack {
+    pub fn new(items: Vec<WitnessItem>) -> Self {
+        WitnessStack {
+            stackitems: hex::encode(crate::CompactSize::encode(items.len() as u64)),
+            items,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // The shape btc_tx_decoder has always printed: items under "0", "1", ...
+    pub fn to_legacy_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("stackitems".to_string(), json!(self.stackitems));
+        for (i, item) in self.items.iter().enumerate() {
+            object.insert(i.to_string(), json!(item));
+        }
+        Value::Object(object)
+    }
+}
+
+// Accepts the items either as an array or under numeric keys
+impl TryFrom<Value> for WitnessStack {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, String> {
+        let stackitems = value["stackitems"]
+            .as_str()
+            .ok_or("witness stack without stackitems")?
+            .to_string();
+        let items = match value.get("items") {
+            Some(items) => serde_json::from_value(items.clone()).map_err(|e| e.to_string())?,
+            None => {
+                let mut items = Vec::new();
+                while let Some(item) = value.get(items.len().to_string()) {
+                    items.push(serde_json::from_value(item.clone()).map_err(|e| e.to_string())?);
+                }
+                items
+            }
+        };
+        Ok(WitnessStack { stackitems, items })
+    }
+}
+
+// The transaction as JSON with the witness in the numeric-key shape
+pub fn legacy_json(tx: &BitcoinTransaction) -> Value {
+    let mut value = json!(tx);
+    value["witness"] = tx
+        .witness
+        .iter()
+        .map(WitnessStack::to_legacy_json)
+        .collect();
+    value
+}
+
+// One input's witness stack as raw bytes, bottom item first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Witness {
+    items: Vec<Vec<u8>>,
+}
+
+impl Witness {
+    pub fn new(items: Vec<Vec<u8>>) -> Self {
+        Witness { items }
+    }
+
+    // The witness of an input; empty for legacy transactions and unknown
+    // inputs.
+    pub fn from_tx(tx: &BitcoinTransaction, input_index: usize) -> Self {
+        let items = tx
+            .witness_items(input_index)
+            .iter()
+            .map(|item| hex::decode(item).unwrap_or_default())
+            .collect();
+        Witness { items }
+    }
+
+    pub fn items(&self) -> &[Vec<u8>] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_transaction, encode_transaction};
+
+    // Mainnet: two P2PKH inputs followed by a P2WPKH input
+    const MIXED_TX: &str = "010000000001036b6b6ac7e34e97c53c1cc74c99c7948af2e6aac75d8778004ae458d813456764000000006a473044022001deec7d9075109306320b3754188f81a8236d0d232b44bc69f8309115638b8f02204e17a5194a519cf994d0afeea1268740bdc10616b031a521113681cc415e815c012103488d3272a9fad78ee887f0684cb8ebcfc06d0945e1401d002e590c7338b163feffffffffc75bd7aa6424aee972789ec28ba181254ee6d8311b058d165bd045154d7660b0000000006b483045022100c8641bcbee3e4c47a00417875015d8c5d5ea918fb7e96f18c6ffe51bc555b401022074e2c46f5b1109cd79e39a9aa203eadd1d75356415e51d80928a5fb5feb0efee0121033504b4c6dfc3a5daaf7c425aead4c2dbbe4e7387ce8e6be2648805939ecf7054ffffffff494df3b205cd9430a26f8e8c0dc0bb80496fbc555a524d6ea307724bc7e60eee0100000000ffffffff026d861500000000001976a9145c54ed1360072ebaf56e87693b88482d2c6a101588ace407000000000000160014761e31e2629c6e11936f2f9888179d60a5d4c1f900000247304402201fa38a67a63e58b67b6cfffd02f59121ca1c8a1b22e1efe2573ae7e4b4f06c2b022002b9b431b58f6e36b3334fb14eaecee7d2f06967a77ef50d8d5f90dda1057f0c01210257dc6ce3b1100903306f518ee8fa113d778e403f118c080b50ce079fba40e09a00000000";
+
+    // Mainnet: a 2-of-3 P2WSH multisig spend
+    const MULTISIG_TX: &str = "01000000000101a3ccad197118a2d4975fadc47b90eacfdeaf8268adfdf10ed3b4c3b7e1ad14530300000000ffffffff0200cc5501000000001976a91428ec6f21f4727bff84bb844e9697366feeb69f4d88aca2a5100d00000000220020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d04004730440220548f11130353b3a8f943d2f14260345fc7c20bde91704c9f1cbb5456355078cd0220383ed4ed39b079b618bcb279bbc1f2ca18cb028c4641cb522c9c5868c52a0dc20147304402203c332ecccb3181ca82c0600520ee51fee80d3b4a6ab110945e59475ec71e44ac0220679a11f3ca9993b04ccebda3c834876f353b065bb08f50076b25f5bb93c72ae1016952210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae00000000";
+
+    #[test]
+    fn test_empty_stacks_for_legacy_inputs() {
+        let tx = decode_transaction(MIXED_TX).unwrap();
+        assert_eq!(tx.witness.len(), 3);
+        for stack in &tx.witness[..2] {
+            assert_eq!(stack.stackitems, "00");
+            assert!(stack.is_empty());
+        }
+        assert_eq!(tx.witness[2].items.len(), 2);
+        assert_eq!(tx.witness[2].items[1].size, "21");
+        assert_eq!(encode_transaction(&tx).unwrap(), MIXED_TX);
+
+        let value = serde_json::to_value(&tx).unwrap();
+        assert_eq!(
+            value["witness"][0],
+            json!({ "stackitems": "00", "items": [] })
+        );
+        assert_eq!(value["witness"][2]["items"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            legacy_json(&tx)["witness"][0],
+            json!({ "stackitems": "00" })
+        );
+    }
+
+    #[test]
+    fn test_empty_multisig_dummy() {
+        let tx = decode_transaction(MULTISIG_TX).unwrap();
+        let stack = &tx.witness[0];
+        assert_eq!(stack.stackitems, "04");
+        assert_eq!(stack.items[0], WitnessItem::new(&[]));
+        assert!(stack.items[0].is_empty());
+        assert!(!stack.items[3].is_empty());
+        assert_eq!(Witness::from_tx(&tx, 0).items()[0], Vec::<u8>::new());
+        assert_eq!(encode_transaction(&tx).unwrap(), MULTISIG_TX);
+
+        let value = serde_json::to_value(&tx).unwrap();
+        assert_eq!(
+            value["witness"][0]["items"][0],
+            json!({ "size": "00", "item": "" })
+        );
+        let legacy = legacy_json(&tx);
+        assert_eq!(
+            legacy["witness"][0]["0"],
+            json!({ "size": "00", "item": "" })
+        );
+
+        // Both shapes read back to the same transaction
+        let typed: crate::BitcoinTransaction = serde_json::from_value(value).unwrap();
+        let untyped: crate::BitcoinTransaction = serde_json::from_value(legacy).unwrap();
+        assert_eq!(typed, tx);
+        assert_eq!(untyped, tx);
+    }
+}
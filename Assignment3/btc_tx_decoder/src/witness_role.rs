@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::classify::{classify_script, ClassifyOptions, ScriptType};
+use crate::inner::{last_push, looks_like_script};
+use crate::script::is_witness_program;
+use crate::signature::parse_der_signature;
+use crate::taproot::{parse_taproot_witness, TaprootSpend};
+use crate::witness::Witness;
+use crate::BitcoinTransaction;
+
+const ANNEX_TAG: u8 = 0x50;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WitnessRole {
+    Signature,
+    Pubkey,
+    WitnessScript,
+}
+
+// What each witness item of an input is, None where that can't be told.
+// With the prevout scriptPubKey the roles follow from the witness program it
+// (or, for P2SH, the redeem script) commits to. Without it only the common
+// single-sig and P2WSH stack shapes are recognized.
+pub fn witness_roles(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_pubkey: Option<&[u8]>,
+) -> Vec<Option<WitnessRole>> {
+    let witness = Witness::from_tx(tx, input_index);
+    let items = witness.items();
+    let Some(input) = tx.inputs.get(input_index) else {
+        return vec![None; items.len()];
+    };
+    let script_sig = hex::decode(&input.scriptsig).unwrap_or_default();
+
+    // The witness program, and whether it is nested in P2SH
+    let classified = script_pubkey.map(|script| {
+        (
+            script,
+            classify_script(script, &[], &ClassifyOptions::default()),
+        )
+    });
+    let program = match classified {
+        Some((_, ScriptType::P2sh { .. })) => last_push(&script_sig).map(|redeem| (redeem, true)),
+        Some((script_pubkey, _)) => Some((script_pubkey.to_vec(), false)),
+        None if !script_sig.is_empty() => last_push(&script_sig).map(|redeem| (redeem, true)),
+        None => return guessed_roles(&witness),
+    };
+    match program {
+        Some((program, nested)) => program_roles(&program, nested, items),
+        None => vec![None; items.len()],
+    }
+}
+
+// Sets the role of every witness item of an input, replacing the ones
+// decoding guessed. Pass the prevout scriptPubKey when it is known.
+pub fn set_witness_roles(
+    tx: &mut BitcoinTransaction,
+    input_index: usize,
+    script_pubkey: Option<&[u8]>,
+) {
+    let roles = witness_roles(tx, input_index, script_pubkey);
+    if let Some(stack) = tx.witness.get_mut(input_index) {
+        for (item, role) in stack.items.iter_mut().zip(roles) {
+            item.role = role;
+        }
+    }
+}
+
+fn program_roles(program: &[u8], nested: bool, items: &[Vec<u8>]) -> Vec<Option<WitnessRole>> {
+    let mut roles = vec![None; items.len()];
+    if !is_witness_program(program) {
+        return roles;
+    }
+    match (program[0], program.len() - 2, items.len()) {
+        // P2WPKH: <signature> <pubkey>
+        (0x00, 20, 2) => {
+            roles[0] = Some(WitnessRole::Signature);
+            roles[1] = Some(WitnessRole::Pubkey);
+        }
+        // P2WSH: the witness script comes last
+        (0x00, 32, n) if n > 0 => roles[n - 1] = Some(WitnessRole::WitnessScript),
+        // Taproot key path: a lone signature, maybe followed by an annex
+        (0x51, 32, 1) if !nested => roles[0] = Some(WitnessRole::Signature),
+        (0x51, 32, 2) if !nested && items[1].first() == Some(&ANNEX_TAG) => {
+            roles[0] = Some(WitnessRole::Signature)
+        }
+        _ => {}
+    }
+    roles
+}
+
+fn guessed_roles(witness: &Witness) -> Vec<Option<WitnessRole>> {
+    let items = witness.items();
+    let mut roles = vec![None; items.len()];
+    match items {
+        [signature, key]
+            if parse_der_signature(signature).is_some()
+                && key.len() == 33
+                && matches!(key[0], 0x02 | 0x03) =>
+        {
+            roles[0] = Some(WitnessRole::Signature);
+            roles[1] = Some(WitnessRole::Pubkey);
+        }
+        [signature] if matches!(signature.len(), 64 | 65) => {
+            roles[0] = Some(WitnessRole::Signature)
+        }
+        // A tapscript spend can end in something script-like too (an annex),
+        // so only call it P2WSH when it doesn't also read as taproot
+        [.., script]
+            if items.len() > 1
+                && looks_like_script(script)
+                && !matches!(
+                    parse_taproot_witness(witness),
+                    TaprootSpend::ScriptPath { .. }
+                ) =>
+        {
+            roles[items.len() - 1] = Some(WitnessRole::WitnessScript)
+        }
+        _ => {}
+    }
+    roles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SAMPLE_TX;
+    use crate::{decode_transaction, WitnessItem, WitnessStack};
+
+    #[test]
+    fn test_p2wpkh_roles() {
+        let tx = decode_transaction(SAMPLE_TX).unwrap();
+        let roles: Vec<_> = tx.witness[0].items.iter().map(|item| item.role).collect();
+        assert_eq!(
+            roles,
+            vec![Some(WitnessRole::Signature), Some(WitnessRole::Pubkey)]
+        );
+
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["witness"][0]["items"][1]["role"], "pubkey");
+    }
+
+    #[test]
+    fn test_prevout_overrides_guess() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        let p2wsh = hex::decode(format!("0020{}", "11".repeat(32))).unwrap();
+        assert_eq!(
+            witness_roles(&tx, 0, Some(&p2wsh)),
+            vec![None, Some(WitnessRole::WitnessScript)]
+        );
+
+        // A P2PKH prevout has no witness roles at all
+        let p2pkh = hex::decode(format!("76a914{}88ac", "11".repeat(20))).unwrap();
+        set_witness_roles(&mut tx, 0, Some(&p2pkh));
+        assert!(tx.witness[0].items.iter().all(|item| item.role.is_none()));
+    }
+
+    #[test]
+    fn test_ambiguous_stack() {
+        let mut tx = decode_transaction(SAMPLE_TX).unwrap();
+        tx.witness[0] = WitnessStack::new(vec![
+            WitnessItem::new(&[0x01; 20]),
+            WitnessItem::new(&[0x02; 33]),
+        ]);
+        assert_eq!(witness_roles(&tx, 0, None), vec![None, None]);
+
+        // Taproot key path: one 64-byte signature
+        tx.witness[0] = WitnessStack::new(vec![WitnessItem::new(&[0x11; 64])]);
+        assert_eq!(
+            witness_roles(&tx, 0, None),
+            vec![Some(WitnessRole::Signature)]
+        );
+    }
+}
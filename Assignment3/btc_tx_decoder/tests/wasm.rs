@@ -0,0 +1,42 @@
+// Run with `wasm-pack test --node -- --no-default-features --features wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use btc_tx_decoder::wasm::{decode_tx, tx_id};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const SAMPLE_TX: &str = "0200000000010131811cd355c357e0e01437d9bcf690df824e9ff785012b6115dfae3d8e8b36c10100000000fdffffff0220a107000000000016001485d78eb795bd9c8a21afefc8b6fdaedf718368094c08100000000000160014840ab165c9c2555d4a31b9208ad806f89d2535e20247304402207bce86d430b58bb6b79e8c1bbecdf67a530eff3bc61581a1399e0b28a741c0ee0220303d5ce926c60bf15577f2e407f28a2ef8fe8453abd4048b716e97dbb1e3a85c01210260828bc77486a55e3bc6032ccbeda915d9494eda17b4a54dbe3b24506d40e4ff43030e00";
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    js_sys::Reflect::get(value, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn decode_tx_sample() {
+    let tx = decode_tx(SAMPLE_TX).unwrap();
+    assert_eq!(get(&tx, "version").as_string().unwrap(), "02000000");
+    assert_eq!(get(&tx, "locktime").as_string().unwrap(), "43030e00");
+
+    let outputs = get(&tx, "outputs");
+    assert_eq!(get(&outputs, "length").as_f64().unwrap(), 2.0);
+    assert_eq!(
+        get(&get(&outputs, "0"), "scriptpubkey")
+            .as_string()
+            .unwrap(),
+        "001485d78eb795bd9c8a21afefc8b6fdaedf71836809"
+    );
+}
+
+#[wasm_bindgen_test]
+fn decode_tx_rejects_bad_hex() {
+    assert!(decode_tx("zz").is_err());
+    assert!(tx_id("zz").is_err());
+}
+
+#[wasm_bindgen_test]
+fn tx_id_sample() {
+    assert_eq!(
+        tx_id(SAMPLE_TX).unwrap(),
+        "04f487fe9754a925c2e96492afeab47e7c839d0582eef80b3ecc9ca3afa05842"
+    );
+}